@@ -0,0 +1,161 @@
+//! Builds the Merkle trees behind `create_distributor` from CSV.
+//!
+//! The CSV has a `beneficiary,total_amount` header and one row per
+//! beneficiary, with the total in base units. Leaves are hashed with
+//! `nlov::merkle`, so every proof in the output verifies on-chain.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use nlov::merkle;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug)]
+pub enum Error {
+    Csv(csv::Error),
+    InvalidBeneficiary { row: usize, value: String },
+    ZeroAmount { row: usize },
+    DuplicateBeneficiary { row: usize, beneficiary: Pubkey },
+    TotalOverflow,
+    Empty,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Csv(err) => write!(f, "{err}"),
+            Error::InvalidBeneficiary { row, value } => {
+                write!(f, "row {row}: invalid beneficiary {value:?}")
+            }
+            Error::ZeroAmount { row } => write!(f, "row {row}: total_amount is 0"),
+            Error::DuplicateBeneficiary { row, beneficiary } => {
+                write!(f, "row {row}: {beneficiary} appears more than once")
+            }
+            Error::TotalOverflow => write!(f, "total_amount does not fit in a u64"),
+            Error::Empty => write!(f, "no beneficiaries"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<csv::Error> for Error {
+    fn from(err: csv::Error) -> Self {
+        Error::Csv(err)
+    }
+}
+
+#[derive(Deserialize)]
+struct Row {
+    beneficiary: String,
+    total_amount: u64,
+}
+
+/// One beneficiary's leaf and the proof to pass to `claim_distribution`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Claim {
+    pub beneficiary: String,
+    pub total_amount: u64,
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// The inputs of `create_distributor` and the claims of every beneficiary.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Distribution {
+    pub schedule_id: u64,
+    pub root: [u8; 32],
+    pub total_amount: u64,
+    pub claims: Vec<Claim>,
+}
+
+/// Reads `(beneficiary, total_amount)` rows from CSV. Row numbers in errors
+/// count the header as row 1.
+pub fn read_csv(input: impl Read) -> Result<Vec<(Pubkey, u64)>, Error> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(input);
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+    for (i, row) in reader.deserialize::<Row>().enumerate() {
+        let row_number = i + 2;
+        let row = row?;
+        let beneficiary =
+            Pubkey::from_str(&row.beneficiary).map_err(|_| Error::InvalidBeneficiary {
+                row: row_number,
+                value: row.beneficiary.clone(),
+            })?;
+        if row.total_amount == 0 {
+            return Err(Error::ZeroAmount { row: row_number });
+        }
+        // A claim status is kept per beneficiary, so each may have one leaf.
+        if !seen.insert(beneficiary) {
+            return Err(Error::DuplicateBeneficiary {
+                row: row_number,
+                beneficiary,
+            });
+        }
+        entries.push((beneficiary, row.total_amount));
+    }
+    Ok(entries)
+}
+
+/// Builds the tree of `entries` for distributor `schedule_id`. A node without
+/// a sibling moves up a level unchanged.
+pub fn build(schedule_id: u64, entries: &[(Pubkey, u64)]) -> Result<Distribution, Error> {
+    if entries.is_empty() {
+        return Err(Error::Empty);
+    }
+    let total_amount = entries
+        .iter()
+        .try_fold(0u64, |sum, (_, amount)| sum.checked_add(*amount))
+        .ok_or(Error::TotalOverflow)?;
+
+    let mut levels = vec![entries
+        .iter()
+        .map(|(beneficiary, amount)| merkle::leaf(beneficiary, *amount, schedule_id))
+        .collect::<Vec<_>>()];
+    while levels.last().unwrap().len() > 1 {
+        let next = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => merkle::parent(a, b),
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+        levels.push(next);
+    }
+    let root = levels.last().unwrap()[0];
+
+    let claims = entries
+        .iter()
+        .enumerate()
+        .map(|(i, (beneficiary, amount))| {
+            let mut index = i;
+            let mut proof = Vec::new();
+            for level in &levels[..levels.len() - 1] {
+                if let Some(sibling) = level.get(index ^ 1) {
+                    proof.push(*sibling);
+                }
+                index /= 2;
+            }
+            Claim {
+                beneficiary: beneficiary.to_string(),
+                total_amount: *amount,
+                proof,
+            }
+        })
+        .collect();
+
+    Ok(Distribution {
+        schedule_id,
+        root,
+        total_amount,
+        claims,
+    })
+}
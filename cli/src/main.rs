@@ -0,0 +1,43 @@
+use std::fs::File;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: nlov-cli merkle-tree <schedule-id> <input.csv> [output.json]";
+
+fn merkle_tree(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let [schedule_id, input, rest @ ..] = args else {
+        return Err(USAGE.into());
+    };
+    if rest.len() > 1 {
+        return Err(USAGE.into());
+    }
+    let schedule_id = schedule_id
+        .parse()
+        .map_err(|_| format!("invalid schedule id {schedule_id:?}"))?;
+    let entries = nlov_cli::read_csv(File::open(input)?)?;
+    let distribution = nlov_cli::build(schedule_id, &entries)?;
+    match rest.first() {
+        Some(output) => serde_json::to_writer_pretty(File::create(output)?, &distribution)?,
+        None => println!("{}", serde_json::to_string_pretty(&distribution)?),
+    }
+    eprintln!(
+        "{} beneficiaries, {} base units",
+        distribution.claims.len(),
+        distribution.total_amount
+    );
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.split_first() {
+        Some((command, rest)) if command == "merkle-tree" => merkle_tree(rest),
+        _ => Err(USAGE.into()),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use nlov::merkle;
+use nlov_cli::{build, read_csv, Error};
+
+fn entries(n: usize) -> Vec<(Pubkey, u64)> {
+    (1..=n as u64)
+        .map(|amount| (Pubkey::new_unique(), amount * 1_000))
+        .collect()
+}
+
+#[test]
+fn test_every_proof_verifies() {
+    for n in [1, 2, 3, 7, 8, 100] {
+        let entries = entries(n);
+        let distribution = build(4, &entries).unwrap();
+        assert_eq!(distribution.claims.len(), n);
+        assert_eq!(
+            distribution.total_amount,
+            entries.iter().map(|(_, amount)| amount).sum::<u64>()
+        );
+        for claim in &distribution.claims {
+            let beneficiary = Pubkey::from_str(&claim.beneficiary).unwrap();
+            let leaf = merkle::leaf(&beneficiary, claim.total_amount, 4);
+            assert!(merkle::verify(&claim.proof, &distribution.root, leaf));
+            // Leaves are bound to the distributor.
+            let other = merkle::leaf(&beneficiary, claim.total_amount, 5);
+            assert!(n == 1 || !merkle::verify(&claim.proof, &distribution.root, other));
+        }
+    }
+}
+
+#[test]
+fn test_read_csv() {
+    let [a, b] = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let csv = format!("beneficiary,total_amount\n{a}, 10\n{b},20\n");
+    assert_eq!(read_csv(csv.as_bytes()).unwrap(), vec![(a, 10), (b, 20)]);
+
+    let duplicate = format!("beneficiary,total_amount\n{a},10\n{a},20\n");
+    assert!(matches!(
+        read_csv(duplicate.as_bytes()),
+        Err(Error::DuplicateBeneficiary { row: 3, .. })
+    ));
+    let zero = format!("beneficiary,total_amount\n{a},0\n");
+    assert!(matches!(
+        read_csv(zero.as_bytes()),
+        Err(Error::ZeroAmount { row: 2 })
+    ));
+    let bad_key = "beneficiary,total_amount\nnot-a-key,10\n";
+    assert!(matches!(
+        read_csv(bad_key.as_bytes()),
+        Err(Error::InvalidBeneficiary { row: 2, .. })
+    ));
+    assert!(matches!(
+        read_csv("beneficiary,total_amount\n".as_bytes()).and_then(|entries| build(0, &entries)),
+        Err(Error::Empty)
+    ));
+}
+
+#[test]
+fn test_total_overflow() {
+    let entries = [(Pubkey::new_unique(), u64::MAX), (Pubkey::new_unique(), 1)];
+    assert!(matches!(build(0, &entries), Err(Error::TotalOverflow)));
+}
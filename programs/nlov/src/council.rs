@@ -0,0 +1,187 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+use crate::withdrawal_limit::WithdrawalLimit;
+use crate::{ErrorCode, ProceedsLeg, PROCEEDS_LEGS};
+
+/// Most signers a council may have. Approvals are kept in a `u16` bitmap.
+pub const MAX_COUNCIL_MEMBERS: usize = 16;
+
+/// Longest a proposal stays open for approvals (14 days).
+pub const MAX_PROPOSAL_TTL: i64 = 14 * 24 * 60 * 60;
+
+/// M-of-N signers that replace the owner for privileged actions, at
+/// `[b"council", presale]`.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct Council {
+    pub presale: Pubkey,
+    #[max_len(MAX_COUNCIL_MEMBERS)]
+    pub members: Vec<Pubkey>,
+    pub threshold: u8,
+    /// Proposals created so far, which is also the id of the next one.
+    pub proposal_count: u64,
+    pub bump: u8,
+}
+
+impl Council {
+    pub fn configure(&mut self, members: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        require!(
+            !members.is_empty() && members.len() <= MAX_COUNCIL_MEMBERS,
+            ErrorCode::InvalidCouncil
+        );
+        require!(
+            threshold >= 1 && threshold as usize <= members.len(),
+            ErrorCode::InvalidCouncil
+        );
+        for (i, member) in members.iter().enumerate() {
+            require!(
+                *member != Pubkey::default() && !members[..i].contains(member),
+                ErrorCode::InvalidCouncil
+            );
+        }
+
+        self.members = members;
+        self.threshold = threshold;
+        Ok(())
+    }
+
+    /// Hash of `members` in order, as named by `AdminAction::CreateCouncil`.
+    pub fn members_hash(members: &[Pubkey]) -> [u8; 32] {
+        let keys: Vec<&[u8]> = members.iter().map(|member| member.as_ref()).collect();
+        hashv(&keys).to_bytes()
+    }
+
+    /// Position of `key` in `members`, which is its bit in proposal approvals.
+    pub fn member_index(&self, key: &Pubkey) -> Result<usize> {
+        self.members
+            .iter()
+            .position(|member| member == key)
+            .ok_or(ErrorCode::NotCouncilMember.into())
+    }
+}
+
+/// A privileged action and its arguments. A proposal only authorizes the
+/// exact call it describes.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdminAction {
+    Withdraw {
+        amount: u64,
+    },
+    DistributeProceeds {
+        amount: u64,
+    },
+    ConfigureProceeds {
+        legs: [ProceedsLeg; PROCEEDS_LEGS],
+    },
+    ConfigureLiquidityLock {
+        sol_bps: u16,
+        nlov_amount: u64,
+        lock_duration: i64,
+    },
+    SetTge {
+        tge_timestamp: i64,
+    },
+    PostponeTge {
+        tge_timestamp: i64,
+    },
+    ConfigureAllocationTransfers {
+        enabled: bool,
+        transfer_agent: Pubkey,
+    },
+    TransferOwnership {
+        new_owner: Pubkey,
+    },
+    SetTimelockDelay {
+        delay: i64,
+    },
+    ConfigureWithdrawalLimit {
+        limit: WithdrawalLimit,
+    },
+    CouncilWithdraw {
+        amount: u64,
+    },
+    FinalizePresale,
+    CancelPresale,
+    ClosePresale,
+    SweepUnsoldTokens {
+        amount: u64,
+    },
+    /// Members are committed to by `Council::members_hash`, which keeps the
+    /// action small.
+    CreateCouncil {
+        members_hash: [u8; 32],
+        threshold: u8,
+    },
+    StartSale,
+    EndSale,
+    OpenClaims,
+    Pause,
+    Unpause,
+    DeclareEmergency,
+    EnableReceipts {
+        transferable: bool,
+    },
+    DepositLpTokens {
+        lp_mint: Pubkey,
+        amount: u64,
+    },
+}
+
+/// An `AdminAction` collecting council approvals, at
+/// `[b"proposal", council, id]`.
+#[account]
+#[derive(InitSpace)]
+pub struct AdminProposal {
+    pub council: Pubkey,
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub action: AdminAction,
+    /// Bit `i` is set while `council.members[i]` approves.
+    pub approvals: u16,
+    pub expires_at: i64,
+    pub executed: bool,
+    pub bump: u8,
+}
+
+impl AdminProposal {
+    pub fn approval_count(&self) -> u8 {
+        self.approvals.count_ones() as u8
+    }
+
+    fn require_open(&self, now: i64) -> Result<()> {
+        require!(!self.executed, ErrorCode::ProposalAlreadyExecuted);
+        require!(now < self.expires_at, ErrorCode::ProposalExpired);
+        Ok(())
+    }
+
+    /// Records the approval of the member at `member_index`.
+    pub fn approve(&mut self, member_index: usize, now: i64) -> Result<()> {
+        self.require_open(now)?;
+        let bit = 1u16 << member_index;
+        require!(self.approvals & bit == 0, ErrorCode::AlreadyApproved);
+        self.approvals |= bit;
+        Ok(())
+    }
+
+    /// Withdraws the approval of the member at `member_index`.
+    pub fn revoke(&mut self, member_index: usize) -> Result<()> {
+        require!(!self.executed, ErrorCode::ProposalAlreadyExecuted);
+        let bit = 1u16 << member_index;
+        require!(self.approvals & bit != 0, ErrorCode::NotApproved);
+        self.approvals &= !bit;
+        Ok(())
+    }
+
+    /// Spends the proposal on `action`, which must be exactly the one proposed.
+    pub fn execute(&mut self, council: &Council, action: &AdminAction, now: i64) -> Result<()> {
+        self.require_open(now)?;
+        require!(self.action == *action, ErrorCode::ProposalMismatch);
+        require!(
+            self.approval_count() >= council.threshold,
+            ErrorCode::ProposalNotApproved
+        );
+        self.executed = true;
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+
+use crate::vesting::{UnlockSchedule, VestingCategory, MAX_UNLOCK_STEPS};
+use crate::ErrorCode;
+
+/// Longest proof `claim_distribution` accepts, enough for 2^24 beneficiaries.
+pub const MAX_PROOF_LEN: usize = 24;
+
+/// Tokens vesting for many beneficiaries under one schedule, at
+/// `[b"distributor", mint, id]`. Only the Merkle root of the
+/// `merkle::leaf(beneficiary, total, id)` leaves is stored; the id comes from
+/// the same `VestingLedger` counter as single schedules.
+#[account]
+#[derive(InitSpace)]
+pub struct MerkleDistributor {
+    pub mint: Pubkey,
+    pub id: u64,
+    pub category: VestingCategory,
+    pub escrow: Pubkey,
+    pub root: [u8; 32],
+    /// Sum of the leaf totals.
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    /// Share unlocked at the mint's TGE, in basis points.
+    pub tge_bps: u16,
+    /// Seconds after TGE before the linear release starts.
+    pub cliff_duration: i64,
+    /// Seconds the rest is released over once the cliff ends.
+    pub linear_duration: i64,
+    pub bump: u8,
+    /// Seconds per step, or 0 for a continuous release.
+    pub step_period: i64,
+    /// Basis points unlocked at the end of each step after the cliff.
+    #[max_len(MAX_UNLOCK_STEPS)]
+    pub step_bps: Vec<u16>,
+}
+
+impl MerkleDistributor {
+    /// Tokens of a leaf's `total_amount` vested by `now` for a TGE at
+    /// `tge_timestamp`.
+    pub fn vested_amount(&self, total_amount: u64, tge_timestamp: i64, now: i64) -> Result<u64> {
+        self.unlock()
+            .vested_amount(total_amount, tge_timestamp, now)
+    }
+
+    pub fn unlock(&self) -> UnlockSchedule<'_> {
+        UnlockSchedule {
+            tge_bps: self.tge_bps,
+            cliff_duration: self.cliff_duration,
+            linear_duration: self.linear_duration,
+            step_period: self.step_period,
+            step_bps: &self.step_bps,
+        }
+    }
+
+    /// Counts `amount` as claimed, failing if the leaves claim more than the
+    /// distributor holds.
+    pub fn record_claim(&mut self, amount: u64) -> Result<()> {
+        let claimed = self
+            .claimed_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+        require!(
+            claimed <= self.total_amount,
+            ErrorCode::VestingAllocationExceeded
+        );
+        self.claimed_amount = claimed;
+        Ok(())
+    }
+
+    /// PDA signer seeds; `id` is `self.id.to_le_bytes()`.
+    pub fn signer_seeds<'a>(&'a self, id: &'a [u8; 8]) -> [&'a [u8]; 4] {
+        [
+            b"distributor",
+            self.mint.as_ref(),
+            id,
+            std::slice::from_ref(&self.bump),
+        ]
+    }
+}
+
+/// What one beneficiary has taken from a distributor, at
+/// `[b"claim_status", distributor, beneficiary]`.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct ClaimStatus {
+    pub distributor: Pubkey,
+    pub beneficiary: Pubkey,
+    pub claimed_amount: u64,
+    pub bump: u8,
+}
@@ -0,0 +1,102 @@
+use anchor_lang::prelude::*;
+
+use crate::vesting::VESTING_MONTH;
+use crate::ErrorCode;
+
+/// NLOV supply in whole tokens.
+pub const TOTAL_SUPPLY: u64 = 500_000_000;
+
+/// Decimals of the NLOV mint created by `bootstrap_token`.
+pub const NLOV_DECIMALS: u8 = 9;
+
+/// Most months an `EmissionSchedule` covers, TGE included (five years).
+pub const MAX_EMISSION_MONTHS: usize = 61;
+
+/// Circulating supply promised in `Documents/NLOV_Structure.md` section 3,
+/// in whole NLOV: at TGE, then at the start of months 1 to 6.
+pub const PUBLISHED_CEILINGS: [u64; 7] = [
+    39_500_000, 43_000_000, 47_200_000, 53_000_000, 61_500_000, 71_700_000, 84_200_000,
+];
+
+/// Cap on the tokens of one mint that may enter circulation, at
+/// `[b"emission_schedule", mint]`. Every release of tokens from the genesis
+/// category vaults counts what it pays out here, and none can pay out before
+/// the schedule exists. It also holds the mint's TGE, which every vesting
+/// schedule of the mint counts from.
+#[account]
+#[derive(InitSpace)]
+pub struct EmissionSchedule {
+    pub mint: Pubkey,
+    /// Sale whose `set_tge` and `postpone_tge` set the mint's TGE.
+    pub presale: Pubkey,
+    /// The mint's TGE, or 0 until that sale schedules it.
+    pub tge_timestamp: i64,
+    /// Cumulative ceiling in base units at TGE, then from the start of each
+    /// following `VESTING_MONTH`. The last one holds after the schedule ends.
+    #[max_len(MAX_EMISSION_MONTHS)]
+    pub ceilings: Vec<u64>,
+    /// Base units released so far.
+    pub emitted: u64,
+    pub bump: u8,
+}
+
+impl EmissionSchedule {
+    /// Fails unless `ceilings` never decrease, stay within the supply of a
+    /// mint with `decimals` and fit in the account.
+    pub fn validate(ceilings: &[u64], decimals: u8) -> Result<()> {
+        let supply = 10u64
+            .checked_pow(decimals as u32)
+            .and_then(|unit| TOTAL_SUPPLY.checked_mul(unit))
+            .ok_or(ErrorCode::CalculationError)?;
+        require!(
+            !ceilings.is_empty()
+                && ceilings.len() <= MAX_EMISSION_MONTHS
+                && ceilings.windows(2).all(|pair| pair[0] <= pair[1])
+                && ceilings.last().is_some_and(|last| *last <= supply),
+            ErrorCode::InvalidEmissionSchedule
+        );
+        Ok(())
+    }
+
+    /// The mint's TGE. Until it is scheduled this is `i64::MAX`, so nothing
+    /// vests or circulates.
+    pub fn tge(&self) -> i64 {
+        if self.tge_timestamp == 0 {
+            i64::MAX
+        } else {
+            self.tge_timestamp
+        }
+    }
+
+    /// Ceiling in force at `now`; nothing may circulate before TGE.
+    pub fn ceiling(&self, now: i64) -> u64 {
+        let tge = self.tge();
+        if now < tge {
+            return 0;
+        }
+        let month = ((now - tge) / VESTING_MONTH) as usize;
+        self.ceilings[month.min(self.ceilings.len() - 1)]
+    }
+
+    /// Moves the mint's TGE to `tge_timestamp` if `presale` is the sale it
+    /// follows. Other sales of the mint only set their own claim start.
+    pub fn follow_tge(&mut self, presale: &Pubkey, tge_timestamp: i64) {
+        if self.presale == *presale {
+            self.tge_timestamp = tge_timestamp;
+        }
+    }
+
+    /// Counts `amount` as released at `now`.
+    pub fn record(&mut self, amount: u64, now: i64) -> Result<()> {
+        let emitted = self
+            .emitted
+            .checked_add(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+        require!(
+            emitted <= self.ceiling(now),
+            ErrorCode::EmissionCeilingExceeded
+        );
+        self.emitted = emitted;
+        Ok(())
+    }
+}
@@ -1,16 +1,120 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::system_program;
+use anchor_spl::token_2022::spl_token_2022::extension::ExtensionType;
+use anchor_spl::token_2022::spl_token_2022::instruction::AuthorityType;
+use anchor_spl::token_2022::{spl_token_2022, Token2022};
+use anchor_spl::token_interface::{
+    self, Mint, MintTo, SetAuthority, TokenAccount, TokenInterface, Transfer,
+};
 // use pyth_sdk_solana::state::PriceStatus;
 // use pyth_sdk_solana::{load_price_feed_from_account_info, PriceFeed};
 
+pub mod council;
+pub mod distributor;
+pub mod emission;
+pub mod merkle;
+pub mod metadata;
+pub mod migration;
+pub mod milestone;
+pub mod staking;
+pub mod timelock;
+pub mod vesting;
+pub mod withdrawal_limit;
+
+use council::{AdminAction, AdminProposal, Council, MAX_PROPOSAL_TTL};
+use distributor::{ClaimStatus, MerkleDistributor, MAX_PROOF_LEN};
+use emission::{EmissionSchedule, NLOV_DECIMALS, TOTAL_SUPPLY};
+use metadata::{MetadataAuthority, MetadataEntry, MetadataField, NLOV_NAME, NLOV_SYMBOL};
+use milestone::{Milestone, MilestoneAttestation, MilestoneSchedule, MilestoneTerms};
+use staking::StakePosition;
+use timelock::{QueuedAction, MAX_TIMELOCK_DELAY, MIN_TIMELOCK_DELAY};
+use vesting::{
+    UnlockSchedule, VestingCategory, VestingLedger, VestingSchedule, VESTING_CATEGORIES,
+    VESTING_MONTH,
+};
+use withdrawal_limit::{WithdrawalLimit, WithdrawalWindow, DEFAULT_WITHDRAWAL_LIMIT};
+
 declare_id!("HB5YUkkQ15LPEqE5sBaF3BsWNjHBqB1HzZbiNiLv7ufK");
 
+/// Rate the unversioned v1 sale charged whatever its `token_price`: 1 lamport
+/// bought 25 NLOV base units.
+pub const NLOV_PER_LAMPORT: u64 = 25;
+
+/// Current `Presale` account layout version.
+pub const PRESALE_VERSION: u8 = 2;
+
+/// Current `UserInfo` account layout version.
+pub const USER_INFO_VERSION: u8 = 2;
+
+/// How many times a scheduled TGE may be pushed back.
+pub const MAX_TGE_POSTPONEMENTS: u8 = 2;
+
+/// Longest single postponement of the TGE (30 days).
+pub const MAX_TGE_POSTPONEMENT: i64 = 30 * 24 * 60 * 60;
+
+/// Basis points in 100%.
+pub const BPS_DENOMINATOR: u16 = 10_000;
+
+/// Number of proceeds destinations, one per `ProceedsDestination`.
+pub const PROCEEDS_LEGS: usize = 4;
+
+/// Highest platform fee the protocol may take from proceeds (10%).
+pub const MAX_PLATFORM_FEE_BPS: u16 = 1_000;
+
+/// Longest reason accepted by `pause` and `unpause`, in bytes.
+pub const MAX_PAUSE_REASON_LEN: usize = 200;
+
 #[program]
 pub mod neurolov_presale {
     use super::*;
 
-    pub fn initialize(
-        ctx: Context<Initialize>,
+    pub fn initialize_protocol(
+        ctx: Context<InitializeProtocol>,
+        fee_recipient: Pubkey,
+        listing_fee: u64,
+        platform_fee_bps: u16,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.protocol_config;
+        config.authority = *ctx.accounts.authority.key;
+        config.bump = ctx.bumps.protocol_config;
+        config.set_fees(fee_recipient, listing_fee, platform_fee_bps)?;
+
+        let clock = Clock::get()?;
+        emit_cpi!(ProtocolConfigUpdated {
+            authority: config.authority,
+            fee_recipient,
+            listing_fee,
+            platform_fee_bps,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn update_protocol_config(
+        ctx: Context<UpdateProtocolConfig>,
+        fee_recipient: Pubkey,
+        listing_fee: u64,
+        platform_fee_bps: u16,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.protocol_config;
+        config.set_fees(fee_recipient, listing_fee, platform_fee_bps)?;
+
+        let clock = Clock::get()?;
+        emit_cpi!(ProtocolConfigUpdated {
+            authority: config.authority,
+            fee_recipient,
+            listing_fee,
+            platform_fee_bps,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn create_presale(
+        ctx: Context<CreatePresale>,
         start_time: i64,
         end_time: i64,
         token_amount: u64,
@@ -20,18 +124,41 @@ pub mod neurolov_presale {
         if start_time >= end_time {
             return Err(ErrorCode::InvalidTimeRange.into());
         }
+        require!(token_price > 0, ErrorCode::InvalidTokenPrice);
 
+        let config = &ctx.accounts.protocol_config;
+        presale.version = PRESALE_VERSION;
         presale.start_time = start_time;
         presale.end_time = end_time;
         presale.token_mint = ctx.accounts.token_mint.key();
         presale.presale_token_account = ctx.accounts.presale_token_account.key();
         presale.presale_supply = token_amount;
-        presale.token_price = token_price; // Price in lamports per NLOV token
+        presale.token_price = token_price; // Price in lamports per whole token
+        presale.token_decimals = ctx.accounts.token_mint.decimals;
         presale.total_contributed = 0;
-        presale.is_active = true;
+        presale.phase = SalePhase::Pending;
         presale.is_paused = false;
         presale.owner = *ctx.accounts.owner.key;
-        presale.public_sale_end_time = end_time + 3 * 7 * 24 * 60 * 60; // 3 weeks after presale end
+        presale.tge_timestamp = 0; // Scheduled later with `set_tge`
+        presale.tge_postponements = 0;
+        presale.creator = *ctx.accounts.owner.key;
+        presale.bump = ctx.bumps.presale;
+        // Fee terms are fixed when the sale is listed.
+        presale.platform_fee_bps = config.platform_fee_bps;
+        // Only the council can loosen it, so an owner key alone never drains
+        // the vault in one go.
+        presale.withdrawal_limit = DEFAULT_WITHDRAWAL_LIMIT;
+
+        if config.listing_fee > 0 {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.fee_recipient.to_account_info(),
+                },
+            );
+            system_program::transfer(cpi_ctx, config.listing_fee)?;
+        }
 
         // Transfer tokens to the presale account
         let cpi_accounts = Transfer {
@@ -41,20 +168,25 @@ pub mod neurolov_presale {
         };
         let cpi_program = ctx.accounts.token_program.to_account_info();
         let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        token::transfer(cpi_ctx, token_amount)?;
+        transfer_tokens(cpi_ctx, token_amount)?;
 
-        emit!(PresaleInitialized {
+        emit_cpi!(PresaleInitialized {
+            header: event_header(presale)?,
+            creator: presale.creator,
+            token_mint: presale.token_mint,
             start_time,
             end_time,
             token_amount,
             token_price,
+            listing_fee: config.listing_fee,
+            platform_fee_bps: presale.platform_fee_bps,
         });
         Ok(())
     }
 
     pub fn contribute(ctx: Context<Contribute>, amount: u64) -> Result<()> {
         let presale = &mut ctx.accounts.presale;
-        require!(!presale.is_paused, ErrorCode::PresalePaused);
+        presale.require_phase(&[SalePhase::Live])?;
 
         let now = Clock::get()?.unix_timestamp;
         require!(
@@ -62,15 +194,17 @@ pub mod neurolov_presale {
             ErrorCode::PresaleNotActive
         );
 
-        // Simplified price calculation (1 SOL = 25 NLOV tokens)
-        let nlov_amount = amount.checked_mul(25).ok_or(ErrorCode::CalculationError)?;
+        let nlov_amount = presale.tokens_for_lamports(amount)?;
 
         require!(
             nlov_amount >= 1 && nlov_amount <= presale.presale_supply - presale.total_contributed,
             ErrorCode::InvalidAmount
         );
 
-        presale.total_contributed += nlov_amount;
+        presale.total_contributed = presale
+            .total_contributed
+            .checked_add(nlov_amount)
+            .ok_or(ErrorCode::CalculationError)?;
 
         // Transfer SOL from user to presale account
         let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
@@ -88,10 +222,20 @@ pub mod neurolov_presale {
 
         // Record user contribution
         let user_info = &mut ctx.accounts.user_info;
+        user_info.version = USER_INFO_VERSION;
         user_info.user = *ctx.accounts.user.key;
-        user_info.amount_contributed += nlov_amount;
+        user_info.record_contribution(amount, nlov_amount, 0, now)?;
+
+        mint_receipts(
+            presale,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.user_receipt_account,
+            &ctx.accounts.token_2022_program,
+            nlov_amount,
+        )?;
 
-        emit!(ContributionMade {
+        emit_cpi!(ContributionMade {
+            header: event_header(presale)?,
             user: *ctx.accounts.user.key,
             sol_amount: amount,
             nlov_amount,
@@ -102,18 +246,38 @@ pub mod neurolov_presale {
     }
     pub fn claim_tokens(ctx: Context<ClaimTokens>) -> Result<()> {
         let presale = &ctx.accounts.presale;
-        require!(!presale.is_paused, ErrorCode::PresalePaused);
+        presale.require_phase(&[SalePhase::Claiming])?;
 
-        let user_info = &ctx.accounts.user_info;
         let now = Clock::get()?.unix_timestamp;
+        let claim_start = presale.claim_start()?;
+        require!(now >= claim_start, ErrorCode::ClaimingNotAvailable);
 
-        require!(
-            now > presale.public_sale_end_time,
-            ErrorCode::ClaimingNotAvailable
-        );
-
-        let amount_to_claim = user_info.amount_contributed - user_info.amount_claimed;
+        let mut amount_to_claim =
+            ctx.accounts
+                .user_info
+                .claimable(&presale.unlock(), claim_start, now)?;
+        if presale.receipts_transferable {
+            // The receipts are the claim: whoever holds them redeems what
+            // has unlocked, up to what they hold.
+            amount_to_claim =
+                amount_to_claim.min(receipt_balance(&ctx.accounts.user_receipt_account));
+        }
         require!(amount_to_claim > 0, ErrorCode::NothingToClaim);
+        record_emission(
+            &mut ctx.accounts.emission_schedule,
+            presale.genesis_funded,
+            amount_to_claim,
+            now,
+        )?;
+
+        burn_receipts(
+            presale,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.user_receipt_account,
+            ctx.accounts.user.to_account_info(),
+            &ctx.accounts.token_2022_program,
+            amount_to_claim,
+        )?;
 
         // Transfer tokens from presale account to user
         let cpi_accounts = Transfer {
@@ -122,228 +286,5353 @@ pub mod neurolov_presale {
             authority: ctx.accounts.presale.to_account_info(),
         };
         let cpi_program = ctx.accounts.token_program.to_account_info();
-        let seeds = &[b"presale".as_ref(), &[ctx.bumps.presale]];
+        let seeds = ctx.accounts.presale.signer_seeds();
         let signer = &[&seeds[..]];
         let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-        token::transfer(cpi_ctx, amount_to_claim)?;
+        transfer_tokens(cpi_ctx, amount_to_claim)?;
 
         // Update user info
-        let user_info = &mut ctx.accounts.user_info;
-        user_info.amount_claimed += amount_to_claim;
+        ctx.accounts.user_info.record_claim(amount_to_claim)?;
+        ctx.accounts.presale.total_claimed += amount_to_claim;
 
-        emit!(TokensClaimed {
+        emit_cpi!(TokensClaimed {
+            header: event_header(&mut ctx.accounts.presale)?,
             user: *ctx.accounts.user.key,
+            allocation: ctx.accounts.user_info.user,
             amount: amount_to_claim,
         });
 
         Ok(())
     }
 
-    pub fn pause(ctx: Context<PauseUnpause>) -> Result<()> {
+    pub fn pause(ctx: Context<PauseUnpause>, reason: String) -> Result<()> {
         let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::Pause,
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+        presale.check_phase(&[
+            SalePhase::Pending,
+            SalePhase::Live,
+            SalePhase::Ended,
+            SalePhase::Finalized,
+            SalePhase::Claiming,
+            SalePhase::Refunding,
+        ])?;
+        // Emergency refunds must stay claimable.
+        require!(!presale.emergency, ErrorCode::EmergencyDeclared);
+        require!(!presale.is_paused, ErrorCode::AlreadyPaused);
         require!(
-            presale.owner == *ctx.accounts.owner.key,
-            ErrorCode::Unauthorized
+            reason.len() <= MAX_PAUSE_REASON_LEN,
+            ErrorCode::PauseReasonTooLong
         );
-        require!(!presale.is_paused, ErrorCode::AlreadyPaused);
 
         presale.is_paused = true;
-        emit!(PresalePaused {});
+        emit_cpi!(PresalePaused {
+            header: event_header(presale)?,
+            actor: ctx.accounts.owner.key(),
+            reason,
+        });
         Ok(())
     }
 
-    pub fn unpause(ctx: Context<PauseUnpause>) -> Result<()> {
+    pub fn unpause(ctx: Context<PauseUnpause>, reason: String) -> Result<()> {
         let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::Unpause,
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+        require!(presale.is_paused, ErrorCode::NotPaused);
         require!(
-            presale.owner == *ctx.accounts.owner.key,
-            ErrorCode::Unauthorized
+            reason.len() <= MAX_PAUSE_REASON_LEN,
+            ErrorCode::PauseReasonTooLong
         );
-        require!(presale.is_paused, ErrorCode::NotPaused);
 
         presale.is_paused = false;
-        emit!(PresaleUnpaused {});
+        emit_cpi!(PresaleUnpaused {
+            header: event_header(presale)?,
+            actor: ctx.accounts.owner.key(),
+            reason,
+        });
+        Ok(())
+    }
+
+    pub fn start_sale(ctx: Context<PhaseTransition>) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::StartSale,
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= presale.start_time && now <= presale.end_time,
+            ErrorCode::PresaleNotActive
+        );
+
+        let event = change_phase(presale, SalePhase::Live)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn end_sale(ctx: Context<PhaseTransition>) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::EndSale,
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+
+        // The sale may close early once the whole supply has been sold.
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now > presale.end_time || presale.total_contributed == presale.presale_supply,
+            ErrorCode::PresaleStillActive
+        );
+
+        let event = change_phase(presale, SalePhase::Ended)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn configure_liquidity_lock(
+        ctx: Context<ConfigureLiquidityLock>,
+        sol_bps: u16,
+        nlov_amount: u64,
+        lock_duration: i64,
+    ) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::ConfigureLiquidityLock {
+                sol_bps,
+                nlov_amount,
+                lock_duration,
+            },
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+        presale.require_phase(&[SalePhase::Pending, SalePhase::Live, SalePhase::Ended])?;
+        presale.configure_liquidity_lock(sol_bps, nlov_amount, lock_duration)?;
+
+        emit_cpi!(LiquidityLockConfigured {
+            header: event_header(presale)?,
+            sol_bps,
+            nlov_amount,
+            lock_duration,
+        });
         Ok(())
     }
 
     pub fn finalize_presale(ctx: Context<FinalizePresale>) -> Result<()> {
         let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::FinalizePresale,
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+        // The lock pays out to the liquidity wallet, so the split must be known.
         require!(
-            presale.owner == *ctx.accounts.owner.key,
-            ErrorCode::Unauthorized
+            presale.proceeds_configured,
+            ErrorCode::ProceedsNotConfigured
+        );
+        require!(
+            presale.liquidity_lock_configured,
+            ErrorCode::LiquidityLockNotConfigured
         );
-        require!(presale.is_active, ErrorCode::PresaleAlreadyFinalized);
+        let event = change_phase(presale, SalePhase::Finalized)?;
+        emit_cpi!(event);
 
         let now = Clock::get()?.unix_timestamp;
-        require!(now > presale.end_time, ErrorCode::PresaleStillActive);
+        let sol_amount = presale.liquidity_lock_sol(ctx.accounts.presale_account.lamports())?;
+        let nlov_amount = presale.liquidity_lock_nlov;
+
+        let lock = &mut ctx.accounts.liquidity_lock;
+        lock.presale = presale.key();
+        lock.beneficiary = presale.proceeds[ProceedsDestination::Liquidity as usize].wallet;
+        lock.sol_amount = sol_amount;
+        lock.nlov_amount = nlov_amount;
+        lock.lp_mint = Pubkey::default();
+        lock.lp_amount = 0;
+        lock.locked_at = now;
+        lock.unlock_at = now
+            .checked_add(presale.liquidity_lock_duration)
+            .ok_or(ErrorCode::CalculationError)?;
+        lock.released = false;
+        lock.bump = ctx.bumps.liquidity_lock;
+        lock.genesis_funded = presale.genesis_funded;
+
+        presale.record_withdrawal(sol_amount)?;
+        if sol_amount > 0 {
+            transfer_from_vault(
+                presale.key(),
+                &ctx.accounts.presale_account,
+                ctx.bumps.presale_account,
+                ctx.accounts.liquidity_lock.to_account_info(),
+                &ctx.accounts.system_program,
+                sol_amount,
+            )?;
+        }
 
-        presale.is_active = false;
-        emit!(PresaleFinalized {
+        if nlov_amount > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.owner_token_account.to_account_info(),
+                to: ctx.accounts.lock_token_account.to_account_info(),
+                authority: ctx.accounts.owner.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+            transfer_tokens(cpi_ctx, nlov_amount)?;
+        }
+
+        let lock = &ctx.accounts.liquidity_lock;
+        emit_cpi!(LiquidityLocked {
+            header: event_header(presale)?,
+            lock: lock.key(),
+            beneficiary: lock.beneficiary,
+            sol_amount,
+            nlov_amount,
+            unlock_at: lock.unlock_at,
+        });
+        emit_cpi!(PresaleFinalized {
+            header: event_header(presale)?,
             total_contributed: presale.total_contributed,
             end_time: presale.end_time,
         });
         Ok(())
     }
 
-    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
-        let presale = &ctx.accounts.presale;
-        require!(
-            presale.owner == *ctx.accounts.owner.key,
-            ErrorCode::Unauthorized
-        );
-        require!(!presale.is_active, ErrorCode::PresaleStillActive);
+    pub fn deposit_lp_tokens(ctx: Context<DepositLpTokens>, amount: u64) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::DepositLpTokens {
+                lp_mint: ctx.accounts.lp_mint.key(),
+                amount,
+            },
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
 
-        let presale_balance = ctx.accounts.presale_account.lamports();
-        require!(amount <= presale_balance, ErrorCode::InsufficientFunds);
+        let lock = &mut ctx.accounts.liquidity_lock;
+        lock.record_lp_deposit(ctx.accounts.lp_mint.key(), amount)?;
 
-        **ctx.accounts.presale_account.try_borrow_mut_lamports()? -= amount;
-        **ctx.accounts.owner.try_borrow_mut_lamports()? += amount;
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.owner_lp_token_account.to_account_info(),
+            to: ctx.accounts.lp_token_account.to_account_info(),
+            authority: ctx.accounts.owner.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        transfer_tokens(cpi_ctx, amount)?;
 
-        emit!(FundsWithdrawn {
-            owner: *ctx.accounts.owner.key,
+        emit_cpi!(LpTokensDeposited {
+            header: event_header(&mut ctx.accounts.presale)?,
+            lock: lock.key(),
+            lp_mint: lock.lp_mint,
             amount,
+            lp_amount: lock.lp_amount,
         });
-
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(init, payer = owner, space = 8 + 256, seeds = [b"presale"], bump)]
-    pub presale: Account<'info, Presale>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    pub token_mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub owner_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub presale_token_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-}
+    pub fn release_liquidity(ctx: Context<ReleaseLiquidity>) -> Result<()> {
+        // `declare_emergency` moves the SOL back to the vault for refunds, so
+        // only the NLOV and LP tokens are left to release.
+        let now = Clock::get()?.unix_timestamp;
+        let lock = &mut ctx.accounts.liquidity_lock;
+        require!(
+            !ctx.accounts.presale.emergency || lock.sol_amount == 0,
+            ErrorCode::EmergencyDeclared
+        );
+        lock.release(now)?;
 
-#[derive(Accounts)]
-pub struct Contribute<'info> {
-    #[account(mut, seeds = [b"presale"], bump)]
-    pub presale: Account<'info, Presale>,
-    #[account(mut)]
-    pub presale_account: SystemAccount<'info>,
-    #[account(
-        init_if_needed,
-        payer = user,
-        space = 8 + 32 + 8 + 8,
-        seeds = [b"user_info", presale.key().as_ref(), user.key().as_ref()],
-        bump
-    )]
-    pub user_info: Account<'info, UserInfo>,
-    #[account(mut)]
-    pub user: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+        let sol_amount = lock.sol_amount;
+        let nlov_amount = ctx.accounts.lock_token_account.amount;
+        let lp_amount = lock.lp_amount;
 
-#[derive(Accounts)]
-pub struct ClaimTokens<'info> {
-    #[account(mut, seeds = [b"presale"], bump)]
-    pub presale: Account<'info, Presale>,
-    #[account(mut)]
-    pub presale_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
-    #[account(
-        mut,
-        seeds = [b"user_info", presale.key().as_ref(), user.key().as_ref()],
-        bump
-    )]
-    pub user_info: Account<'info, UserInfo>,
-    pub user: Signer<'info>,
-    pub token_program: Program<'info, Token>,
-}
+        if sol_amount > 0 {
+            **lock.to_account_info().try_borrow_mut_lamports()? -= sol_amount;
+            **ctx.accounts.beneficiary.try_borrow_mut_lamports()? += sol_amount;
+        }
 
-#[derive(Accounts)]
-pub struct PauseUnpause<'info> {
-    #[account(mut, seeds = [b"presale"], bump)]
-    pub presale: Account<'info, Presale>,
-    pub owner: Signer<'info>,
-}
+        let presale_key = ctx.accounts.presale.key();
+        let seeds = &[
+            b"liquidity_lock".as_ref(),
+            presale_key.as_ref(),
+            &[lock.bump],
+        ];
+        let signer = &[&seeds[..]];
 
-#[derive(Accounts)]
-pub struct FinalizePresale<'info> {
-    #[account(mut, seeds = [b"presale"], bump)]
-    pub presale: Account<'info, Presale>,
-    pub owner: Signer<'info>,
-}
+        if nlov_amount > 0 {
+            record_emission(
+                &mut ctx.accounts.emission_schedule,
+                lock.genesis_funded,
+                nlov_amount,
+                now,
+            )?;
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.lock_token_account.to_account_info(),
+                to: ctx.accounts.beneficiary_token_account.to_account_info(),
+                authority: lock.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            transfer_tokens(cpi_ctx, nlov_amount)?;
+        }
 
-#[derive(Accounts)]
-pub struct Withdraw<'info> {
-    #[account(seeds = [b"presale"], bump)]
-    pub presale: Account<'info, Presale>,
-    #[account(mut)]
-    pub presale_account: SystemAccount<'info>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+        if lp_amount > 0 {
+            let (Some(lp_token_account), Some(beneficiary_lp_token_account)) = (
+                &ctx.accounts.lp_token_account,
+                &ctx.accounts.beneficiary_lp_token_account,
+            ) else {
+                return err!(ErrorCode::MissingLpTokenAccount);
+            };
+            let cpi_accounts = Transfer {
+                from: lp_token_account.to_account_info(),
+                to: beneficiary_lp_token_account.to_account_info(),
+                authority: lock.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            transfer_tokens(cpi_ctx, lp_amount)?;
+        }
 
-#[account]
-pub struct Presale {
-    pub start_time: i64,
-    pub end_time: i64,
-    pub public_sale_end_time: i64,
-    pub token_mint: Pubkey,
-    pub presale_token_account: Pubkey,
-    pub presale_supply: u64,
-    pub token_price: u64,
-    pub total_contributed: u64,
-    pub is_active: bool,
-    pub is_paused: bool,
-    pub owner: Pubkey,
-}
+        emit_cpi!(LiquidityReleased {
+            header: event_header(&mut ctx.accounts.presale)?,
+            lock: lock.key(),
+            beneficiary: lock.beneficiary,
+            sol_amount,
+            nlov_amount,
+            lp_amount,
+        });
+        Ok(())
+    }
 
-#[account]
-pub struct UserInfo {
-    pub user: Pubkey,
-    pub amount_contributed: u64,
-    pub amount_claimed: u64,
-}
+    pub fn open_claims(ctx: Context<PhaseTransition>) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::OpenClaims,
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
 
-#[event]
-pub struct PresaleInitialized {
-    pub start_time: i64,
-    pub end_time: i64,
-    pub token_amount: u64,
-    pub token_price: u64,
-}
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= presale.claim_start()?,
+            ErrorCode::ClaimingNotAvailable
+        );
 
-#[event]
-pub struct ContributionMade {
-    pub user: Pubkey,
-    pub sol_amount: u64,
-    pub nlov_amount: u64,
-    pub total_contributed: u64,
-}
+        let event = change_phase(presale, SalePhase::Claiming)?;
+        emit_cpi!(event);
+        Ok(())
+    }
 
-#[event]
-pub struct TokensClaimed {
-    pub user: Pubkey,
-    pub amount: u64,
-}
+    pub fn set_tge(ctx: Context<SetTge>, tge_timestamp: i64) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::SetTge { tge_timestamp },
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+        presale.require_phase(&[
+            SalePhase::Pending,
+            SalePhase::Live,
+            SalePhase::Ended,
+            SalePhase::Finalized,
+        ])?;
 
-#[event]
-pub struct PresalePaused {}
+        let now = Clock::get()?.unix_timestamp;
+        presale.schedule_tge(tge_timestamp, now)?;
+        follow_tge(&mut ctx.accounts.emission_schedule, presale, tge_timestamp)?;
 
-#[event]
-pub struct PresaleUnpaused {}
+        emit_cpi!(TgeScheduled {
+            header: event_header(presale)?,
+            tge_timestamp,
+            previous_tge_timestamp: 0,
+            postponements: presale.tge_postponements,
+        });
+        Ok(())
+    }
 
-#[event]
-pub struct PresaleFinalized {
-    pub total_contributed: u64,
-    pub end_time: i64,
-}
+    pub fn postpone_tge(ctx: Context<SetTge>, tge_timestamp: i64) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::PostponeTge { tge_timestamp },
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+        presale.require_phase(&[
+            SalePhase::Pending,
+            SalePhase::Live,
+            SalePhase::Ended,
+            SalePhase::Finalized,
+        ])?;
 
-#[event]
-pub struct FundsWithdrawn {
-    pub owner: Pubkey,
-    pub amount: u64,
+        let previous_tge_timestamp = presale.tge_timestamp;
+        let now = Clock::get()?.unix_timestamp;
+        presale.postpone_tge(tge_timestamp, now)?;
+        follow_tge(&mut ctx.accounts.emission_schedule, presale, tge_timestamp)?;
+
+        emit_cpi!(TgeScheduled {
+            header: event_header(presale)?,
+            tge_timestamp,
+            previous_tge_timestamp,
+            postponements: presale.tge_postponements,
+        });
+        Ok(())
+    }
+
+    pub fn cancel_presale(ctx: Context<AdminUpdate>) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::CancelPresale,
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+        let event = change_phase(presale, SalePhase::Refunding)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn close_presale(ctx: Context<AdminUpdate>) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::ClosePresale,
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+        // Emergency refunds stay open for good.
+        require!(!presale.emergency, ErrorCode::EmergencyDeclared);
+        let event = change_phase(presale, SalePhase::Closed)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn sweep_unsold_tokens(ctx: Context<SweepUnsoldTokens>, amount: u64) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::SweepUnsoldTokens { amount },
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+        presale.require_phase(&[
+            SalePhase::Finalized,
+            SalePhase::Claiming,
+            SalePhase::Refunding,
+            SalePhase::Closed,
+        ])?;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            amount <= presale.sweepable_tokens(ctx.accounts.presale_token_account.amount),
+            ErrorCode::InsufficientFunds
+        );
+        presale.record_sweep(amount);
+        // Genesis NLOV swept to the owner circulates like a claim does.
+        record_emission(
+            &mut ctx.accounts.emission_schedule,
+            presale.genesis_funded,
+            amount,
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.presale_token_account.to_account_info(),
+            to: ctx.accounts.destination.to_account_info(),
+            authority: ctx.accounts.presale.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let seeds = ctx.accounts.presale.signer_seeds();
+        let signer = &[&seeds[..]];
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        transfer_tokens(cpi_ctx, amount)?;
+
+        emit_cpi!(UnsoldTokensSwept {
+            header: event_header(&mut ctx.accounts.presale)?,
+            destination: ctx.accounts.destination.key(),
+            amount,
+        });
+        Ok(())
+    }
+
+    pub fn declare_emergency(ctx: Context<DeclareEmergency>) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::DeclareEmergency,
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+
+        // After finalization part of the raise sits on the liquidity lock. It
+        // goes back to the vault so that the refund snapshot covers it.
+        let mut liquidity_returned = 0;
+        if presale.phase == SalePhase::Finalized || presale.phase == SalePhase::Claiming {
+            let Some(lock) = &mut ctx.accounts.liquidity_lock else {
+                return err!(ErrorCode::MissingLiquidityLock);
+            };
+            liquidity_returned = lock.reclaim_sol();
+            if liquidity_returned > 0 {
+                **lock.to_account_info().try_borrow_mut_lamports()? -= liquidity_returned;
+                **ctx.accounts.presale_account.try_borrow_mut_lamports()? += liquidity_returned;
+                presale.record_return(liquidity_returned)?;
+            }
+        }
+
+        let vault_balance = ctx.accounts.presale_account.lamports();
+        let from = presale.declare_emergency(vault_balance)?;
+
+        emit_cpi!(PhaseChanged {
+            header: event_header(presale)?,
+            from,
+            to: SalePhase::Refunding,
+        });
+        emit_cpi!(EmergencyDeclared {
+            header: event_header(presale)?,
+            owner: *ctx.accounts.owner.key,
+            vault_balance,
+            outstanding_allocation: presale.emergency_outstanding_snapshot,
+            liquidity_returned,
+            declared_at: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn claim_refund(ctx: Context<ClaimRefund>) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        presale.require_phase(&[SalePhase::Refunding])?;
+
+        let user_info = &mut ctx.accounts.user_info;
+        let nlov_amount = user_info.unclaimed()?;
+        require!(nlov_amount > 0, ErrorCode::NothingToRefund);
+        // With transferable receipts the refund goes to whoever hands in
+        // receipts for the whole unclaimed allocation.
+        require!(
+            !presale.receipts_transferable
+                || receipt_balance(&ctx.accounts.user_receipt_account) >= nlov_amount,
+            ErrorCode::InsufficientReceipts
+        );
+
+        burn_receipts(
+            presale,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.user_receipt_account,
+            ctx.accounts.user.to_account_info(),
+            &ctx.accounts.token_2022_program,
+            nlov_amount,
+        )?;
+
+        let refund_amount = if presale.emergency {
+            presale.emergency_refund_amount(nlov_amount)?
+        } else {
+            user_info.lamports_paid_for(nlov_amount)?
+        };
+
+        user_info.record_refund(refund_amount)?;
+        presale.total_contributed = presale
+            .total_contributed
+            .checked_sub(nlov_amount)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        transfer_from_vault(
+            presale.key(),
+            &ctx.accounts.presale_account,
+            ctx.bumps.presale_account,
+            ctx.accounts.user.to_account_info(),
+            &ctx.accounts.system_program,
+            refund_amount,
+        )?;
+
+        emit_cpi!(RefundClaimed {
+            header: event_header(presale)?,
+            user: *ctx.accounts.user.key,
+            allocation: user_info.user,
+            sol_amount: refund_amount,
+            nlov_amount,
+        });
+
+        Ok(())
+    }
+
+    pub fn migrate_presale(ctx: Context<MigratePresale>) -> Result<()> {
+        let account = ctx.accounts.presale.to_account_info();
+        require!(account.owner == &crate::ID, ErrorCode::InvalidAccountLayout);
+
+        let (from_version, mut presale) = migration::upgrade_presale(&account.try_borrow_data()?)?;
+        require!(
+            presale.owner == *ctx.accounts.owner.key,
+            ErrorCode::Unauthorized
+        );
+        if presale.creator == Pubkey::default() {
+            // Sales from before the launchpad live at the singleton address.
+            let (legacy, bump) = Pubkey::find_program_address(&[b"presale"], &crate::ID);
+            require!(account.key() == legacy, ErrorCode::InvalidAccountLayout);
+            presale.bump = bump;
+        }
+        if from_version < 2 {
+            // Withdrawals were not counted before v2; whatever left the vault was.
+            presale.total_withdrawn = presale
+                .contributed_lamports()?
+                .saturating_sub(ctx.accounts.presale_account.lamports());
+        }
+
+        let header = presale.next_event_header(account.key(), &Clock::get()?)?;
+        migration::rewrite_account(
+            &account,
+            &presale,
+            8 + Presale::INIT_SPACE,
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        emit_cpi!(AccountMigrated {
+            header,
+            account: account.key(),
+            from_version,
+            to_version: PRESALE_VERSION,
+        });
+        Ok(())
+    }
+
+    pub fn migrate_user_info(ctx: Context<MigrateUserInfo>) -> Result<()> {
+        let account = ctx.accounts.user_info.to_account_info();
+        require!(account.owner == &crate::ID, ErrorCode::InvalidAccountLayout);
+
+        let (from_version, user_info) = migration::upgrade_user_info(&account.try_borrow_data()?)?;
+        require!(
+            user_info.user == ctx.accounts.user.key(),
+            ErrorCode::InvalidAccountLayout
+        );
+
+        migration::rewrite_account(
+            &account,
+            &user_info,
+            8 + UserInfo::INIT_SPACE,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        emit_cpi!(AccountMigrated {
+            header: event_header(&mut ctx.accounts.presale)?,
+            account: account.key(),
+            from_version,
+            to_version: USER_INFO_VERSION,
+        });
+        Ok(())
+    }
+
+    pub fn configure_proceeds(
+        ctx: Context<ConfigureProceeds>,
+        legs: [ProceedsLeg; PROCEEDS_LEGS],
+    ) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::ConfigureProceeds { legs },
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+        // `finalize_presale` requires the split, so only a sale migrated as
+        // `Finalized` can get this far unconfigured; the split is still set once.
+        presale.require_phase(&[
+            SalePhase::Pending,
+            SalePhase::Live,
+            SalePhase::Ended,
+            SalePhase::Finalized,
+            SalePhase::Claiming,
+        ])?;
+        presale.configure_proceeds(legs)?;
+
+        emit_cpi!(ProceedsConfigured {
+            header: event_header(presale)?,
+            legs,
+        });
+        Ok(())
+    }
+
+    pub fn distribute_proceeds(ctx: Context<DistributeProceeds>, amount: u64) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::DistributeProceeds { amount },
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+        require!(!presale.emergency, ErrorCode::EmergencyDeclared);
+        presale.require_phase(&[SalePhase::Finalized, SalePhase::Claiming, SalePhase::Closed])?;
+
+        let presale_balance = ctx.accounts.presale_account.lamports();
+        require!(amount <= presale_balance, ErrorCode::InsufficientFunds);
+        let now = Clock::get()?.unix_timestamp;
+        presale.record_payout(amount, presale_balance, now, true)?;
+
+        let fee = presale.platform_fee(amount)?;
+        if fee > 0 {
+            transfer_from_vault(
+                presale.key(),
+                &ctx.accounts.presale_account,
+                ctx.bumps.presale_account,
+                ctx.accounts.fee_recipient.to_account_info(),
+                &ctx.accounts.system_program,
+                fee,
+            )?;
+            emit_cpi!(PlatformFeePaid {
+                header: event_header(presale)?,
+                fee_recipient: ctx.accounts.fee_recipient.key(),
+                amount: fee,
+            });
+        }
+
+        let shares = presale.split_proceeds(amount - fee)?;
+        let wallets = [
+            ctx.accounts.liquidity_wallet.to_account_info(),
+            ctx.accounts.treasury_wallet.to_account_info(),
+            ctx.accounts.development_wallet.to_account_info(),
+            ctx.accounts.marketing_wallet.to_account_info(),
+        ];
+
+        for ((destination, wallet), share) in ProceedsDestination::ALL
+            .into_iter()
+            .zip(wallets)
+            .zip(shares)
+        {
+            if share > 0 {
+                transfer_from_vault(
+                    presale.key(),
+                    &ctx.accounts.presale_account,
+                    ctx.bumps.presale_account,
+                    wallet.clone(),
+                    &ctx.accounts.system_program,
+                    share,
+                )?;
+            }
+
+            emit_cpi!(ProceedsDistributed {
+                header: event_header(presale)?,
+                destination,
+                wallet: wallet.key(),
+                bps: presale.proceeds[destination as usize].bps,
+                amount: share,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn withdraw(mut ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::Withdraw { amount },
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+        pay_out_withdrawal(&mut ctx, amount, true)
+    }
+
+    /// Withdraws past the rolling limit. Only a council can approve this.
+    pub fn council_withdraw(mut ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        require!(
+            presale.council != Pubkey::default(),
+            ErrorCode::CouncilRequired
+        );
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::CouncilWithdraw { amount },
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+        pay_out_withdrawal(&mut ctx, amount, false)
+    }
+
+    pub fn configure_withdrawal_limit(
+        ctx: Context<AdminUpdate>,
+        limit: WithdrawalLimit,
+    ) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::ConfigureWithdrawalLimit { limit },
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+        presale.require_current_version()?;
+        limit.validate()?;
+        // Otherwise a stolen owner key could lift the limit and drain the
+        // vault. A timelock alone would only delay that, so only a council
+        // can approve it.
+        if presale.withdrawal_limit.is_loosened_by(&limit) {
+            require!(
+                presale.council != Pubkey::default(),
+                ErrorCode::WithdrawalLimitLocked
+            );
+        }
+
+        let previous_limit = presale.withdrawal_limit;
+        presale.withdrawal_limit = limit;
+
+        emit_cpi!(WithdrawalLimitConfigured {
+            header: event_header(presale)?,
+            previous_limit,
+            limit,
+        });
+        Ok(())
+    }
+
+    pub fn verify_invariants(ctx: Context<VerifyInvariants>) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let header = event_header(presale)?;
+        let report = presale.reconcile(
+            header,
+            ctx.accounts.presale_account.lamports(),
+            ctx.accounts.presale_token_account.amount,
+        )?;
+
+        emit_cpi!(report);
+        Ok(())
+    }
+
+    pub fn configure_allocation_transfers(
+        ctx: Context<ConfigureAllocationTransfers>,
+        enabled: bool,
+        transfer_agent: Pubkey,
+    ) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::ConfigureAllocationTransfers {
+                enabled,
+                transfer_agent,
+            },
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+        presale.require_phase(&[
+            SalePhase::Pending,
+            SalePhase::Live,
+            SalePhase::Ended,
+            SalePhase::Finalized,
+            SalePhase::Claiming,
+        ])?;
+
+        presale.allocation_transfers_enabled = enabled;
+        presale.transfer_agent = transfer_agent;
+
+        emit_cpi!(AllocationTransfersConfigured {
+            header: event_header(presale)?,
+            enabled,
+            transfer_agent,
+        });
+        Ok(())
+    }
+
+    pub fn transfer_allocation(ctx: Context<TransferAllocation>, amount: u64) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        presale.require_phase(&[
+            SalePhase::Live,
+            SalePhase::Ended,
+            SalePhase::Finalized,
+            SalePhase::Claiming,
+        ])?;
+        let transfer_agent = ctx
+            .accounts
+            .transfer_agent
+            .as_ref()
+            .map(|agent| agent.key());
+        presale.authorize_allocation_transfer(transfer_agent)?;
+
+        let to_user_info = &mut ctx.accounts.to_user_info;
+        to_user_info.version = USER_INFO_VERSION;
+        to_user_info.user = ctx.accounts.recipient.key();
+        ctx.accounts
+            .from_user_info
+            .move_allocation(to_user_info, amount)?;
+
+        burn_receipts(
+            presale,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.from_receipt_account,
+            ctx.accounts.from.to_account_info(),
+            &ctx.accounts.token_2022_program,
+            amount,
+        )?;
+        mint_receipts(
+            presale,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.to_receipt_account,
+            &ctx.accounts.token_2022_program,
+            amount,
+        )?;
+
+        emit_cpi!(AllocationTransferred {
+            header: event_header(presale)?,
+            from: ctx.accounts.from.key(),
+            to: ctx.accounts.recipient.key(),
+            amount,
+            transfer_agent,
+        });
+        Ok(())
+    }
+
+    pub fn enable_receipts(ctx: Context<EnableReceipts>, transferable: bool) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::EnableReceipts { transferable },
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+        presale.require_phase(&[SalePhase::Pending])?;
+        require!(
+            !presale.receipts_enabled(),
+            ErrorCode::ReceiptsAlreadyEnabled
+        );
+
+        let receipt_mint = ctx.accounts.receipt_mint.to_account_info();
+        let token_program = ctx.accounts.token_2022_program.to_account_info();
+        // Non-transferable receipts stay with the allocation, which moves with
+        // `transfer_allocation`. Transferable ones are the claim itself, and
+        // the sale burns them from whoever redeems them as permanent delegate.
+        let extension = if transferable {
+            ExtensionType::PermanentDelegate
+        } else {
+            ExtensionType::NonTransferable
+        };
+        let space =
+            ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[extension])?;
+
+        let presale_key = presale.key();
+        let seeds = &[
+            b"receipt_mint".as_ref(),
+            presale_key.as_ref(),
+            &[ctx.bumps.receipt_mint],
+        ];
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: receipt_mint.clone(),
+                },
+                &[&seeds[..]],
+            ),
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            &Token2022::id(),
+        )?;
+        if transferable {
+            token_interface::permanent_delegate_initialize(
+                CpiContext::new(
+                    token_program.clone(),
+                    token_interface::PermanentDelegateInitialize {
+                        token_program_id: token_program.clone(),
+                        mint: receipt_mint.clone(),
+                    },
+                ),
+                &presale_key,
+            )?;
+        } else {
+            token_interface::non_transferable_mint_initialize(CpiContext::new(
+                token_program.clone(),
+                token_interface::NonTransferableMintInitialize {
+                    token_program_id: token_program.clone(),
+                    mint: receipt_mint.clone(),
+                },
+            ))?;
+        }
+        // One receipt unit per NLOV base unit, so the decimals match.
+        token_interface::initialize_mint2(
+            CpiContext::new(
+                token_program,
+                token_interface::InitializeMint2 { mint: receipt_mint },
+            ),
+            ctx.accounts.token_mint.decimals,
+            &presale_key,
+            None,
+        )?;
+
+        presale.receipt_mint = ctx.accounts.receipt_mint.key();
+        presale.receipts_transferable = transferable;
+
+        emit_cpi!(ReceiptsEnabled {
+            header: event_header(presale)?,
+            receipt_mint: presale.receipt_mint,
+            transferable,
+        });
+        Ok(())
+    }
+
+    pub fn create_council(
+        ctx: Context<CreateCouncil>,
+        members: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        require!(
+            presale.council == Pubkey::default(),
+            ErrorCode::CouncilAlreadyExists
+        );
+        let action = AdminAction::CreateCouncil {
+            members_hash: Council::members_hash(&members),
+            threshold,
+        };
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &None,
+            &mut None,
+            &mut ctx.accounts.queued_action,
+            action,
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+        presale.require_phase(&[
+            SalePhase::Pending,
+            SalePhase::Live,
+            SalePhase::Ended,
+            SalePhase::Finalized,
+            SalePhase::Claiming,
+        ])?;
+
+        let council = &mut ctx.accounts.council;
+        council.presale = presale.key();
+        council.bump = ctx.bumps.council;
+        council.configure(members, threshold)?;
+        // From here on the owner alone can no longer take privileged actions.
+        presale.council = council.key();
+
+        emit_cpi!(CouncilCreated {
+            header: event_header(presale)?,
+            council: presale.council,
+            members: council.members.clone(),
+            threshold,
+        });
+        Ok(())
+    }
+
+    pub fn propose(ctx: Context<Propose>, action: AdminAction, ttl: i64) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        presale.require_current_version()?;
+        require!(
+            ttl > 0 && ttl <= MAX_PROPOSAL_TTL,
+            ErrorCode::InvalidProposalTtl
+        );
+
+        let council = &mut ctx.accounts.council;
+        let member_index = council.member_index(ctx.accounts.proposer.key)?;
+        let now = Clock::get()?.unix_timestamp;
+
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.council = council.key();
+        proposal.id = council.proposal_count;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.action = action;
+        proposal.expires_at = now.checked_add(ttl).ok_or(ErrorCode::CalculationError)?;
+        proposal.bump = ctx.bumps.proposal;
+        // Proposing counts as the proposer's approval.
+        proposal.approve(member_index, now)?;
+        council.proposal_count = council
+            .proposal_count
+            .checked_add(1)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        emit_cpi!(ProposalCreated {
+            header: event_header(presale)?,
+            proposal: proposal.key(),
+            id: proposal.id,
+            proposer: proposal.proposer,
+            action,
+            expires_at: proposal.expires_at,
+        });
+        Ok(())
+    }
+
+    pub fn approve_proposal(ctx: Context<ReviewProposal>) -> Result<()> {
+        let member_index = ctx.accounts.council.member_index(ctx.accounts.member.key)?;
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.approve(member_index, Clock::get()?.unix_timestamp)?;
+
+        emit_cpi!(ProposalApproved {
+            header: event_header(&mut ctx.accounts.presale)?,
+            proposal: proposal.key(),
+            member: ctx.accounts.member.key(),
+            approvals: proposal.approval_count(),
+        });
+        Ok(())
+    }
+
+    pub fn revoke_approval(ctx: Context<ReviewProposal>) -> Result<()> {
+        let member_index = ctx.accounts.council.member_index(ctx.accounts.member.key)?;
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.revoke(member_index)?;
+
+        emit_cpi!(ApprovalRevoked {
+            header: event_header(&mut ctx.accounts.presale)?,
+            proposal: proposal.key(),
+            member: ctx.accounts.member.key(),
+            approvals: proposal.approval_count(),
+        });
+        Ok(())
+    }
+
+    pub fn transfer_ownership(ctx: Context<AdminUpdate>, new_owner: Pubkey) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::TransferOwnership { new_owner },
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+        presale.require_current_version()?;
+        require!(new_owner != Pubkey::default(), ErrorCode::InvalidNewOwner);
+
+        let previous_owner = presale.owner;
+        presale.owner = new_owner;
+
+        emit_cpi!(OwnershipTransferred {
+            header: event_header(presale)?,
+            previous_owner,
+            new_owner,
+        });
+        Ok(())
+    }
+
+    pub fn set_timelock_delay(ctx: Context<AdminUpdate>, delay: i64) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        let approval = authorize_admin(
+            presale,
+            ctx.accounts.owner.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.queued_action,
+            AdminAction::SetTimelockDelay { delay },
+        )?;
+        approval.emit(&ctx.accounts.event_authority, ctx.bumps.event_authority)?;
+        presale.require_current_version()?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let previous_delay = presale.timelock_delay_at(now);
+        let effective_at = presale.change_timelock_delay(delay, now)?;
+
+        emit_cpi!(TimelockDelayChanged {
+            header: event_header(presale)?,
+            previous_delay,
+            delay,
+            effective_at,
+        });
+        Ok(())
+    }
+
+    pub fn queue_action(ctx: Context<QueueAction>, action: AdminAction) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        presale.require_current_version()?;
+        let now = Clock::get()?.unix_timestamp;
+        let timelock_delay = presale.timelock_delay_at(now);
+        require!(timelock_delay > 0, ErrorCode::TimelockDisabled);
+        // Approval happens now; execution only waits out the delay.
+        if let Some(event) = approve_admin_action(
+            presale,
+            ctx.accounts.authority.key,
+            &ctx.accounts.council,
+            &mut ctx.accounts.proposal,
+            action,
+        )? {
+            emit_cpi!(event);
+        }
+
+        let queued_action = &mut ctx.accounts.queued_action;
+        queued_action.presale = presale.key();
+        queued_action.id = presale.queued_action_count;
+        queued_action.action = action;
+        queued_action.queued_by = ctx.accounts.authority.key();
+        queued_action.council = presale.council;
+        queued_action.eta = now
+            .checked_add(timelock_delay)
+            .ok_or(ErrorCode::CalculationError)?;
+        queued_action.bump = ctx.bumps.queued_action;
+        presale.queued_action_count = presale
+            .queued_action_count
+            .checked_add(1)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        emit_cpi!(ActionQueued {
+            header: event_header(presale)?,
+            queued_action: queued_action.key(),
+            id: queued_action.id,
+            action,
+            queued_by: queued_action.queued_by,
+            eta: queued_action.eta,
+        });
+        Ok(())
+    }
+
+    pub fn cancel_queued_action(ctx: Context<CancelQueuedAction>) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        presale.require_current_version()?;
+        // Any single admin may cancel, so one honest member can stop an action.
+        require_admin(presale, ctx.accounts.authority.key, &ctx.accounts.council)?;
+
+        let queued_action = &mut ctx.accounts.queued_action;
+        queued_action.cancel()?;
+
+        emit_cpi!(QueuedActionCancelled {
+            header: event_header(presale)?,
+            queued_action: queued_action.key(),
+            id: queued_action.id,
+            cancelled_by: ctx.accounts.authority.key(),
+        });
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_vesting(
+        ctx: Context<CreateVesting>,
+        beneficiary: Pubkey,
+        category: VestingCategory,
+        total_amount: u64,
+        tge_bps: u16,
+        cliff_duration: i64,
+        linear_duration: i64,
+        revocable: bool,
+        step_period: i64,
+        step_bps: Vec<u16>,
+    ) -> Result<()> {
+        require!(total_amount > 0, ErrorCode::InvalidAmount);
+        category.terms().check_unlock(&UnlockSchedule {
+            tge_bps,
+            cliff_duration,
+            linear_duration,
+            step_period,
+            step_bps: &step_bps,
+        })?;
+
+        let ledger = &mut ctx.accounts.vesting_ledger;
+        ledger.mint = ctx.accounts.mint.key();
+        ledger.bump = ctx.bumps.vesting_ledger;
+        ledger.allocate(category, total_amount, ctx.accounts.mint.decimals)?;
+
+        let schedule = &mut ctx.accounts.vesting_schedule;
+        schedule.mint = ledger.mint;
+        schedule.id = ledger.schedule_count;
+        schedule.beneficiary = beneficiary;
+        schedule.category = category;
+        schedule.escrow = ctx.accounts.escrow.key();
+        schedule.total_amount = total_amount;
+        schedule.released_amount = 0;
+        schedule.tge_bps = tge_bps;
+        schedule.cliff_duration = cliff_duration;
+        schedule.linear_duration = linear_duration;
+        schedule.bump = ctx.bumps.vesting_schedule;
+        schedule.revocable = revocable;
+        schedule.revoked_at = 0;
+        schedule.step_period = step_period;
+        schedule.step_bps = step_bps;
+        ledger.schedule_count = ledger
+            .schedule_count
+            .checked_add(1)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        fund_escrow(
+            &ctx.accounts.funder_token_account,
+            &ctx.accounts.escrow,
+            ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.vault_authority,
+            ctx.bumps.vault_authority,
+            category,
+            &ctx.accounts.token_program,
+            total_amount,
+        )?;
+
+        let clock = Clock::get()?;
+        emit_cpi!(VestingCreated {
+            schedule: schedule.key(),
+            id: schedule.id,
+            beneficiary,
+            category,
+            total_amount,
+            revocable,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn release(ctx: Context<Release>) -> Result<()> {
+        let clock = Clock::get()?;
+        let schedule = &ctx.accounts.vesting_schedule;
+        let amount =
+            schedule.releasable(ctx.accounts.emission_schedule.tge(), clock.unix_timestamp)?;
+        require!(amount > 0, ErrorCode::NothingToClaim);
+        ctx.accounts
+            .emission_schedule
+            .record(amount, clock.unix_timestamp)?;
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.escrow.to_account_info(),
+            to: ctx.accounts.beneficiary_token_account.to_account_info(),
+            authority: schedule.to_account_info(),
+        };
+        let id = schedule.id.to_le_bytes();
+        let seeds = schedule.signer_seeds(&id);
+        let signer = &[&seeds[..]];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        );
+        transfer_tokens(cpi_ctx, amount)?;
+
+        let schedule = &mut ctx.accounts.vesting_schedule;
+        schedule.released_amount = schedule
+            .released_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        emit_cpi!(VestingReleased {
+            schedule: schedule.key(),
+            beneficiary: schedule.beneficiary,
+            amount,
+            released_amount: schedule.released_amount,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn revoke_grant(ctx: Context<RevokeGrant>) -> Result<()> {
+        let clock = Clock::get()?;
+        let schedule = &mut ctx.accounts.vesting_schedule;
+        let returned_amount =
+            schedule.revoke(ctx.accounts.emission_schedule.tge(), clock.unix_timestamp)?;
+        let vested_amount = schedule.total_amount - returned_amount;
+        ctx.accounts
+            .vesting_ledger
+            .deallocate(schedule.category, returned_amount)?;
+
+        if returned_amount > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.escrow.to_account_info(),
+                to: ctx.accounts.category_vault.to_account_info(),
+                authority: schedule.to_account_info(),
+            };
+            let id = schedule.id.to_le_bytes();
+            let seeds = schedule.signer_seeds(&id);
+            let signer = &[&seeds[..]];
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            );
+            transfer_tokens(cpi_ctx, returned_amount)?;
+        }
+
+        emit_cpi!(GrantRevoked {
+            schedule: schedule.key(),
+            beneficiary: schedule.beneficiary,
+            category: schedule.category,
+            vested_amount,
+            returned_amount,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn revoke_milestone_grant(ctx: Context<RevokeMilestoneGrant>) -> Result<()> {
+        let clock = Clock::get()?;
+        let schedule = &mut ctx.accounts.milestone_schedule;
+        let returned_amount =
+            schedule.revoke(ctx.accounts.emission_schedule.tge(), clock.unix_timestamp)?;
+        let vested_amount = schedule.total_amount - returned_amount;
+        ctx.accounts
+            .vesting_ledger
+            .deallocate(schedule.category, returned_amount)?;
+
+        if returned_amount > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.escrow.to_account_info(),
+                to: ctx.accounts.category_vault.to_account_info(),
+                authority: schedule.to_account_info(),
+            };
+            let id = schedule.id.to_le_bytes();
+            let seeds = schedule.signer_seeds(&id);
+            let signer = &[&seeds[..]];
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                cpi_accounts,
+                signer,
+            );
+            transfer_tokens(cpi_ctx, returned_amount)?;
+        }
+
+        emit_cpi!(GrantRevoked {
+            schedule: schedule.key(),
+            beneficiary: schedule.beneficiary,
+            category: schedule.category,
+            vested_amount,
+            returned_amount,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn fund_presale(
+        ctx: Context<FundPresale>,
+        category: VestingCategory,
+        amount: u64,
+    ) -> Result<()> {
+        require!(
+            matches!(
+                category,
+                VestingCategory::Presale | VestingCategory::PublicSale
+            ),
+            ErrorCode::InvalidFundingCategory
+        );
+        let presale = &mut ctx.accounts.presale;
+        require!(
+            presale.owner == ctx.accounts.authority.key(),
+            ErrorCode::Unauthorized
+        );
+        presale.require_phase(&[SalePhase::Pending])?;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let ledger = &mut ctx.accounts.vesting_ledger;
+        ledger.mint = ctx.accounts.mint.key();
+        ledger.bump = ctx.bumps.vesting_ledger;
+        ledger.allocate(category, amount, ctx.accounts.mint.decimals)?;
+        // The tokens go on sale alongside those the owner deposited.
+        presale.presale_supply = presale
+            .presale_supply
+            .checked_add(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+        presale.genesis_funded = true;
+
+        fund_escrow(
+            &ctx.accounts.category_vault,
+            &ctx.accounts.presale_token_account,
+            ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.vault_authority,
+            ctx.bumps.vault_authority,
+            category,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        emit_cpi!(SaleFunded {
+            header: event_header(presale)?,
+            category,
+            destination: ctx.accounts.presale_token_account.key(),
+            amount,
+        });
+        Ok(())
+    }
+
+    pub fn fund_liquidity_lock(ctx: Context<FundLiquidityLock>, amount: u64) -> Result<()> {
+        let presale = &mut ctx.accounts.presale;
+        require!(
+            presale.owner == ctx.accounts.authority.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(!presale.emergency, ErrorCode::EmergencyDeclared);
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let lock = &mut ctx.accounts.liquidity_lock;
+        require!(!lock.released, ErrorCode::LiquidityAlreadyReleased);
+        lock.nlov_amount = lock
+            .nlov_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+        lock.genesis_funded = true;
+
+        let ledger = &mut ctx.accounts.vesting_ledger;
+        ledger.mint = ctx.accounts.mint.key();
+        ledger.bump = ctx.bumps.vesting_ledger;
+        ledger.allocate(
+            VestingCategory::Liquidity,
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        fund_escrow(
+            &ctx.accounts.category_vault,
+            &ctx.accounts.lock_token_account,
+            ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.vault_authority,
+            ctx.bumps.vault_authority,
+            VestingCategory::Liquidity,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        emit_cpi!(SaleFunded {
+            header: event_header(presale)?,
+            category: VestingCategory::Liquidity,
+            destination: ctx.accounts.lock_token_account.key(),
+            amount,
+        });
+        Ok(())
+    }
+
+    pub fn create_milestone_vesting(
+        ctx: Context<CreateMilestoneVesting>,
+        beneficiary: Pubkey,
+        category: VestingCategory,
+        total_amount: u64,
+        attester: Pubkey,
+        milestones: Vec<MilestoneTerms>,
+        revocable: bool,
+    ) -> Result<()> {
+        require!(total_amount > 0, ErrorCode::InvalidAmount);
+        let milestones = MilestoneSchedule::milestones_from(category, &milestones)?;
+
+        let ledger = &mut ctx.accounts.vesting_ledger;
+        ledger.mint = ctx.accounts.mint.key();
+        ledger.bump = ctx.bumps.vesting_ledger;
+        ledger.allocate(category, total_amount, ctx.accounts.mint.decimals)?;
+
+        let schedule = &mut ctx.accounts.milestone_schedule;
+        schedule.mint = ledger.mint;
+        schedule.id = ledger.schedule_count;
+        schedule.beneficiary = beneficiary;
+        schedule.category = category;
+        schedule.escrow = ctx.accounts.escrow.key();
+        schedule.attester = attester;
+        schedule.total_amount = total_amount;
+        schedule.released_amount = 0;
+        schedule.milestones = milestones;
+        schedule.bump = ctx.bumps.milestone_schedule;
+        schedule.revocable = revocable;
+        schedule.revoked_at = 0;
+        ledger.schedule_count = ledger
+            .schedule_count
+            .checked_add(1)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        fund_escrow(
+            &ctx.accounts.funder_token_account,
+            &ctx.accounts.escrow,
+            ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.vault_authority,
+            ctx.bumps.vault_authority,
+            category,
+            &ctx.accounts.token_program,
+            total_amount,
+        )?;
+
+        let clock = Clock::get()?;
+        emit_cpi!(MilestoneVestingCreated {
+            schedule: schedule.key(),
+            id: schedule.id,
+            beneficiary,
+            category,
+            total_amount,
+            attester,
+            milestones: schedule.milestones.clone(),
+            revocable,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn attest_milestone(
+        ctx: Context<AttestMilestone>,
+        index: u8,
+        evidence: [u8; 32],
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let schedule = &mut ctx.accounts.milestone_schedule;
+        let by_fallback = schedule.achieve(
+            index as usize,
+            ctx.accounts.signer.key,
+            ctx.accounts.emission_schedule.tge(),
+            clock.unix_timestamp,
+        )?;
+
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.schedule = schedule.key();
+        attestation.index = index;
+        attestation.attested_by = ctx.accounts.signer.key();
+        attestation.by_fallback = by_fallback;
+        attestation.evidence = evidence;
+        attestation.attested_at = clock.unix_timestamp;
+        attestation.bump = ctx.bumps.attestation;
+
+        emit_cpi!(MilestoneAchieved {
+            schedule: schedule.key(),
+            index,
+            bps: schedule.milestones[index as usize].bps,
+            attested_by: attestation.attested_by,
+            by_fallback,
+            evidence,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn release_milestones(ctx: Context<ReleaseMilestones>) -> Result<()> {
+        let clock = Clock::get()?;
+        let schedule = &ctx.accounts.milestone_schedule;
+        let amount =
+            schedule.releasable(ctx.accounts.emission_schedule.tge(), clock.unix_timestamp)?;
+        require!(amount > 0, ErrorCode::NothingToClaim);
+        ctx.accounts
+            .emission_schedule
+            .record(amount, clock.unix_timestamp)?;
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.escrow.to_account_info(),
+            to: ctx.accounts.beneficiary_token_account.to_account_info(),
+            authority: schedule.to_account_info(),
+        };
+        let id = schedule.id.to_le_bytes();
+        let seeds = schedule.signer_seeds(&id);
+        let signer = &[&seeds[..]];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        );
+        transfer_tokens(cpi_ctx, amount)?;
+
+        let schedule = &mut ctx.accounts.milestone_schedule;
+        schedule.released_amount = schedule
+            .released_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        emit_cpi!(VestingReleased {
+            schedule: schedule.key(),
+            beneficiary: schedule.beneficiary,
+            amount,
+            released_amount: schedule.released_amount,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_distributor(
+        ctx: Context<CreateDistributor>,
+        root: [u8; 32],
+        category: VestingCategory,
+        total_amount: u64,
+        tge_bps: u16,
+        cliff_duration: i64,
+        linear_duration: i64,
+        step_period: i64,
+        step_bps: Vec<u16>,
+    ) -> Result<()> {
+        require!(total_amount > 0, ErrorCode::InvalidAmount);
+        category.terms().check_unlock(&UnlockSchedule {
+            tge_bps,
+            cliff_duration,
+            linear_duration,
+            step_period,
+            step_bps: &step_bps,
+        })?;
+
+        let ledger = &mut ctx.accounts.vesting_ledger;
+        ledger.mint = ctx.accounts.mint.key();
+        ledger.bump = ctx.bumps.vesting_ledger;
+        ledger.allocate(category, total_amount, ctx.accounts.mint.decimals)?;
+
+        let distributor = &mut ctx.accounts.distributor;
+        distributor.mint = ledger.mint;
+        distributor.id = ledger.schedule_count;
+        distributor.category = category;
+        distributor.escrow = ctx.accounts.escrow.key();
+        distributor.root = root;
+        distributor.total_amount = total_amount;
+        distributor.claimed_amount = 0;
+        distributor.tge_bps = tge_bps;
+        distributor.cliff_duration = cliff_duration;
+        distributor.linear_duration = linear_duration;
+        distributor.bump = ctx.bumps.distributor;
+        distributor.step_period = step_period;
+        distributor.step_bps = step_bps;
+        ledger.schedule_count = ledger
+            .schedule_count
+            .checked_add(1)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        fund_escrow(
+            &ctx.accounts.funder_token_account,
+            &ctx.accounts.escrow,
+            ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.vault_authority,
+            ctx.bumps.vault_authority,
+            category,
+            &ctx.accounts.token_program,
+            total_amount,
+        )?;
+
+        let clock = Clock::get()?;
+        emit_cpi!(DistributorCreated {
+            distributor: distributor.key(),
+            id: distributor.id,
+            category,
+            root,
+            total_amount,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn claim_distribution(
+        ctx: Context<ClaimDistribution>,
+        total_amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require!(proof.len() <= MAX_PROOF_LEN, ErrorCode::InvalidProof);
+        let distributor = &ctx.accounts.distributor;
+        let beneficiary = ctx.accounts.beneficiary.key();
+        require!(
+            merkle::verify(
+                &proof,
+                &distributor.root,
+                merkle::leaf(&beneficiary, total_amount, distributor.id),
+            ),
+            ErrorCode::InvalidProof
+        );
+
+        let clock = Clock::get()?;
+        let claim_status = &mut ctx.accounts.claim_status;
+        claim_status.distributor = distributor.key();
+        claim_status.beneficiary = beneficiary;
+        claim_status.bump = ctx.bumps.claim_status;
+        let amount = distributor
+            .vested_amount(
+                total_amount,
+                ctx.accounts.emission_schedule.tge(),
+                clock.unix_timestamp,
+            )?
+            .checked_sub(claim_status.claimed_amount)
+            .ok_or(ErrorCode::CalculationError)?;
+        require!(amount > 0, ErrorCode::NothingToClaim);
+        ctx.accounts
+            .emission_schedule
+            .record(amount, clock.unix_timestamp)?;
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.escrow.to_account_info(),
+            to: ctx.accounts.beneficiary_token_account.to_account_info(),
+            authority: distributor.to_account_info(),
+        };
+        let id = distributor.id.to_le_bytes();
+        let seeds = distributor.signer_seeds(&id);
+        let signer = &[&seeds[..]];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        );
+        transfer_tokens(cpi_ctx, amount)?;
+
+        ctx.accounts.distributor.record_claim(amount)?;
+        let claim_status = &mut ctx.accounts.claim_status;
+        claim_status.claimed_amount = claim_status
+            .claimed_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        emit_cpi!(DistributionClaimed {
+            distributor: ctx.accounts.distributor.key(),
+            beneficiary,
+            amount,
+            claimed_amount: claim_status.claimed_amount,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Creates the mint's emission schedule. Its TGE follows `presale`, the
+    /// sale that launches the token.
+    pub fn initialize_emission_schedule(
+        ctx: Context<InitializeEmissionSchedule>,
+        ceilings: Vec<u64>,
+    ) -> Result<()> {
+        EmissionSchedule::validate(&ceilings, ctx.accounts.mint.decimals)?;
+
+        let emission_schedule = &mut ctx.accounts.emission_schedule;
+        emission_schedule.mint = ctx.accounts.mint.key();
+        emission_schedule.presale = ctx.accounts.presale.key();
+        emission_schedule.tge_timestamp = ctx.accounts.presale.tge_timestamp;
+        emission_schedule.ceilings = ceilings;
+        emission_schedule.emitted = 0;
+        emission_schedule.bump = ctx.bumps.emission_schedule;
+
+        let clock = Clock::get()?;
+        emit_cpi!(EmissionScheduleUpdated {
+            emission_schedule: emission_schedule.key(),
+            mint: emission_schedule.mint,
+            tge_timestamp: emission_schedule.tge_timestamp,
+            ceilings: emission_schedule.ceilings.clone(),
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Appends ceilings for the months after the current schedule. Months
+    /// already set can never be changed.
+    pub fn extend_emission_schedule(
+        ctx: Context<ExtendEmissionSchedule>,
+        ceilings: Vec<u64>,
+    ) -> Result<()> {
+        let emission_schedule = &mut ctx.accounts.emission_schedule;
+        let mut extended = emission_schedule.ceilings.clone();
+        extended.extend_from_slice(&ceilings);
+        require!(
+            extended.len() > emission_schedule.ceilings.len(),
+            ErrorCode::InvalidEmissionSchedule
+        );
+        EmissionSchedule::validate(&extended, ctx.accounts.mint.decimals)?;
+        emission_schedule.ceilings = extended;
+
+        let clock = Clock::get()?;
+        emit_cpi!(EmissionScheduleUpdated {
+            emission_schedule: emission_schedule.key(),
+            mint: emission_schedule.mint,
+            tge_timestamp: emission_schedule.tge_timestamp,
+            ceilings: emission_schedule.ceilings.clone(),
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Stakes `amount` of the schedule's locked tokens for voting weight.
+    /// The tokens stay in escrow and keep vesting as before.
+    pub fn stake_locked(ctx: Context<StakeLocked>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let clock = Clock::get()?;
+        let schedule = &ctx.accounts.vesting_schedule;
+        let tge_timestamp = ctx.accounts.emission_schedule.tge();
+        let position = &mut ctx.accounts.stake_position;
+        let staked_amount = position
+            .staked_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+        require!(
+            staked_amount <= schedule.locked_amount(tge_timestamp, clock.unix_timestamp)?,
+            ErrorCode::StakeExceedsLocked
+        );
+        position.vesting_schedule = schedule.key();
+        position.beneficiary = schedule.beneficiary;
+        position.staked_amount = staked_amount;
+        position.bump = ctx.bumps.stake_position;
+
+        emit_cpi!(LockedStakeChanged {
+            stake_position: position.key(),
+            vesting_schedule: position.vesting_schedule,
+            beneficiary: position.beneficiary,
+            staked_amount,
+            voting_weight: position.voting_weight(schedule, tge_timestamp, clock.unix_timestamp)?,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn unstake_locked(ctx: Context<UnstakeLocked>, amount: u64) -> Result<()> {
+        let position = &mut ctx.accounts.stake_position;
+        require!(
+            amount > 0 && amount <= position.staked_amount,
+            ErrorCode::InvalidAmount
+        );
+        position.staked_amount -= amount;
+
+        let clock = Clock::get()?;
+        emit_cpi!(LockedStakeChanged {
+            stake_position: position.key(),
+            vesting_schedule: position.vesting_schedule,
+            beneficiary: position.beneficiary,
+            staked_amount: position.staked_amount,
+            voting_weight: position.voting_weight(
+                &ctx.accounts.vesting_schedule,
+                ctx.accounts.emission_schedule.tge(),
+                clock.unix_timestamp,
+            )?,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Creates the NLOV mint with its Token-2022 metadata, mints the whole
+    /// supply into one vault per allocation category and revokes the mint
+    /// authority, all at once.
+    pub fn bootstrap_token(
+        ctx: Context<BootstrapToken>,
+        uri: String,
+        additional_metadata: Vec<MetadataEntry>,
+    ) -> Result<()> {
+        let metadata_authority = &mut ctx.accounts.metadata_authority;
+        metadata_authority.mint = ctx.accounts.mint.key();
+        metadata_authority.authority = ctx.accounts.authority.key();
+        metadata_authority.bump = ctx.bumps.metadata_authority;
+
+        // The metadata lives in the mint itself, where the pointer set up by
+        // `init` leads.
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            token_interface::TokenMetadataInitialize {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                metadata: ctx.accounts.mint.to_account_info(),
+                update_authority: metadata_authority.to_account_info(),
+                mint_authority: ctx.accounts.authority.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+            },
+        );
+        token_interface::token_metadata_initialize(
+            cpi_ctx,
+            NLOV_NAME.to_string(),
+            NLOV_SYMBOL.to_string(),
+            uri,
+        )?;
+        for entry in additional_metadata {
+            update_metadata_field(
+                &ctx.accounts.mint,
+                metadata_authority,
+                &ctx.accounts.token_program,
+                MetadataField::Key(entry.key),
+                entry.value,
+            )?;
+        }
+        fund_rent_exemption(
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program,
+        )?;
+
+        let unit = 10u64.pow(NLOV_DECIMALS as u32);
+        let mut supply = 0u64;
+        for (category, vault) in VestingCategory::ALL.into_iter().zip(ctx.accounts.vaults()) {
+            let amount = category
+                .terms()
+                .tokens
+                .checked_mul(unit)
+                .ok_or(ErrorCode::CalculationError)?;
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: vault.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            );
+            token_interface::mint_to(cpi_ctx, amount)?;
+            supply = supply
+                .checked_add(amount)
+                .ok_or(ErrorCode::CalculationError)?;
+        }
+        require!(
+            supply == TOTAL_SUPPLY * unit,
+            ErrorCode::GenesisSupplyMismatch
+        );
+
+        // The mint is created without a freeze authority, so this leaves it
+        // with no authority at all.
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            SetAuthority {
+                current_authority: ctx.accounts.authority.to_account_info(),
+                account_or_mint: ctx.accounts.mint.to_account_info(),
+            },
+        );
+        token_interface::set_authority(cpi_ctx, AuthorityType::MintTokens, None)?;
+
+        let clock = Clock::get()?;
+        emit_cpi!(TokenBootstrapped {
+            mint: ctx.accounts.mint.key(),
+            supply,
+            vaults: ctx.accounts.vaults().map(|vault| vault.key()).to_vec(),
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn update_token_metadata(
+        ctx: Context<UpdateTokenMetadata>,
+        field: MetadataField,
+        value: String,
+    ) -> Result<()> {
+        update_metadata_field(
+            &ctx.accounts.mint,
+            &ctx.accounts.metadata_authority,
+            &ctx.accounts.token_program,
+            field.clone(),
+            value.clone(),
+        )?;
+        fund_rent_exemption(
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program,
+        )?;
+
+        let clock = Clock::get()?;
+        emit_cpi!(TokenMetadataUpdated {
+            mint: ctx.accounts.mint.key(),
+            field,
+            value,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Hands the metadata role to `new_authority`, such as a governance
+    /// program's PDA.
+    pub fn set_metadata_authority(
+        ctx: Context<SetMetadataAuthority>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        let metadata_authority = &mut ctx.accounts.metadata_authority;
+        let previous_authority = metadata_authority.authority;
+        metadata_authority.authority = new_authority;
+
+        let clock = Clock::get()?;
+        emit_cpi!(MetadataAuthorityChanged {
+            mint: metadata_authority.mint,
+            previous_authority,
+            new_authority,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+/// Sets a field of the metadata stored in `mint`, signed by its metadata
+/// authority PDA.
+fn update_metadata_field<'info>(
+    mint: &InterfaceAccount<'info, Mint>,
+    metadata_authority: &Account<'info, MetadataAuthority>,
+    token_program: &Program<'info, Token2022>,
+    field: MetadataField,
+    value: String,
+) -> Result<()> {
+    let seeds = metadata_authority.signer_seeds();
+    let signer = &[&seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        token_interface::TokenMetadataUpdateField {
+            token_program_id: token_program.to_account_info(),
+            metadata: mint.to_account_info(),
+            update_authority: metadata_authority.to_account_info(),
+        },
+        signer,
+    );
+    token_interface::token_metadata_update_field(cpi_ctx, field.into(), value)
+}
+
+/// Tops `account` up to rent exemption from `payer`. Token-2022 grows the
+/// mint to fit the metadata but leaves the rent to the caller.
+fn fund_rent_exemption<'info>(
+    account: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let minimum = Rent::get()?.minimum_balance(account.data_len());
+    let shortfall = minimum.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        let cpi_ctx = CpiContext::new(
+            system_program.to_account_info(),
+            system_program::Transfer {
+                from: payer,
+                to: account,
+            },
+        );
+        system_program::transfer(cpi_ctx, shortfall)?;
+    }
+    Ok(())
+}
+
+/// Moves `amount` from `funder` into a new escrow. A genesis category vault
+/// only funds its own category and is signed for by the vault authority;
+/// any other funding account belongs to `authority`.
+#[allow(clippy::too_many_arguments)]
+fn fund_escrow<'info>(
+    funder: &InterfaceAccount<'info, TokenAccount>,
+    escrow: &InterfaceAccount<'info, TokenAccount>,
+    authority: AccountInfo<'info>,
+    vault_authority: &UncheckedAccount<'info>,
+    vault_authority_bump: u8,
+    category: VestingCategory,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<()> {
+    let mint = funder.mint;
+    let from_vault = funder.owner == vault_authority.key();
+    let cpi_accounts = Transfer {
+        from: funder.to_account_info(),
+        to: escrow.to_account_info(),
+        authority: if from_vault {
+            vault_authority.to_account_info()
+        } else {
+            authority
+        },
+    };
+    if !from_vault {
+        let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+        return transfer_tokens(cpi_ctx, amount);
+    }
+
+    let (category_vault, _) = Pubkey::find_program_address(
+        &[b"category_vault", mint.as_ref(), &[category as u8]],
+        &crate::ID,
+    );
+    require!(
+        funder.key() == category_vault,
+        ErrorCode::CategoryVaultMismatch
+    );
+    let seeds = &[
+        b"category_vault_authority".as_ref(),
+        mint.as_ref(),
+        &[vault_authority_bump],
+    ];
+    let signer = &[&seeds[..]];
+    let cpi_ctx =
+        CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer);
+    transfer_tokens(cpi_ctx, amount)
+}
+
+/// Moves tokens under either token program. Token-2022 only rejects the
+/// unchecked `Transfer` for mints with a transfer fee or hook, and NLOV has
+/// neither.
+#[allow(deprecated)]
+fn transfer_tokens<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Transfer<'info>>,
+    amount: u64,
+) -> Result<()> {
+    token_interface::transfer(ctx, amount)
+}
+
+/// Moves lamports out of the presale vault PDA.
+fn transfer_from_vault<'info>(
+    presale: Pubkey,
+    vault: &SystemAccount<'info>,
+    vault_bump: u8,
+    to: AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+) -> Result<()> {
+    let seeds = &[b"vault".as_ref(), presale.as_ref(), &[vault_bump]];
+    let signer = &[&seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(
+        system_program.to_account_info(),
+        system_program::Transfer {
+            from: vault.to_account_info(),
+            to,
+        },
+        signer,
+    );
+    system_program::transfer(cpi_ctx, amount)
+}
+
+/// Mints `amount` allocation receipts into `to` if the sale issues them.
+fn mint_receipts<'info>(
+    presale: &Account<'info, Presale>,
+    receipt_mint: &Option<InterfaceAccount<'info, Mint>>,
+    to: &Option<InterfaceAccount<'info, TokenAccount>>,
+    token_program: &Option<Program<'info, Token2022>>,
+    amount: u64,
+) -> Result<()> {
+    if !presale.receipts_enabled() {
+        return Ok(());
+    }
+    let (Some(receipt_mint), Some(to), Some(token_program)) = (receipt_mint, to, token_program)
+    else {
+        return err!(ErrorCode::MissingReceiptAccounts);
+    };
+
+    let seeds = presale.signer_seeds();
+    let signer = &[&seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        token_interface::MintTo {
+            mint: receipt_mint.to_account_info(),
+            to: to.to_account_info(),
+            authority: presale.to_account_info(),
+        },
+        signer,
+    );
+    token_interface::mint_to(cpi_ctx, amount)
+}
+
+/// Burns `amount` allocation receipts held by `authority` in `from` if the
+/// sale issues them. Transferable receipts are burned by the sale itself, as
+/// the mint's permanent delegate.
+fn burn_receipts<'info>(
+    presale: &Account<'info, Presale>,
+    receipt_mint: &Option<InterfaceAccount<'info, Mint>>,
+    from: &Option<InterfaceAccount<'info, TokenAccount>>,
+    authority: AccountInfo<'info>,
+    token_program: &Option<Program<'info, Token2022>>,
+    amount: u64,
+) -> Result<()> {
+    if !presale.receipts_enabled() {
+        return Ok(());
+    }
+    let (Some(receipt_mint), Some(from), Some(token_program)) = (receipt_mint, from, token_program)
+    else {
+        return err!(ErrorCode::MissingReceiptAccounts);
+    };
+
+    let seeds = presale.signer_seeds();
+    let signer = &[&seeds[..]];
+    let (authority, signer_seeds): (_, &[&[&[u8]]]) = if presale.receipts_transferable {
+        (presale.to_account_info(), signer)
+    } else {
+        (authority, &[])
+    };
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        token_interface::Burn {
+            mint: receipt_mint.to_account_info(),
+            from: from.to_account_info(),
+            authority,
+        },
+        signer_seeds,
+    );
+    token_interface::burn(cpi_ctx, amount)
+}
+
+/// Receipts in `account`, or none if it was not passed.
+fn receipt_balance(account: &Option<InterfaceAccount<TokenAccount>>) -> u64 {
+    account.as_ref().map_or(0, |account| account.amount)
+}
+
+/// Pays `amount` out of the vault to the treasury, less the platform fee.
+/// `rate_limited` withdrawals must fit the sale's rolling withdrawal limit;
+/// the rest still count towards it.
+fn pay_out_withdrawal(ctx: &mut Context<Withdraw>, amount: u64, rate_limited: bool) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+    require!(!presale.emergency, ErrorCode::EmergencyDeclared);
+    presale.require_phase(&[SalePhase::Finalized, SalePhase::Claiming, SalePhase::Closed])?;
+    require!(
+        presale.proceeds_configured,
+        ErrorCode::ProceedsNotConfigured
+    );
+
+    let presale_balance = ctx.accounts.presale_account.lamports();
+    require!(amount <= presale_balance, ErrorCode::InsufficientFunds);
+    let now = Clock::get()?.unix_timestamp;
+    presale.record_payout(amount, presale_balance, now, rate_limited)?;
+
+    let fee = presale.platform_fee(amount)?;
+    if fee > 0 {
+        transfer_from_vault(
+            presale.key(),
+            &ctx.accounts.presale_account,
+            ctx.bumps.presale_account,
+            ctx.accounts.fee_recipient.to_account_info(),
+            &ctx.accounts.system_program,
+            fee,
+        )?;
+        emit_cpi!(PlatformFeePaid {
+            header: event_header(presale)?,
+            fee_recipient: ctx.accounts.fee_recipient.key(),
+            amount: fee,
+        });
+    }
+
+    // Ad-hoc withdrawals only ever go to the configured treasury wallet.
+    transfer_from_vault(
+        presale.key(),
+        &ctx.accounts.presale_account,
+        ctx.bumps.presale_account,
+        ctx.accounts.treasury_wallet.to_account_info(),
+        &ctx.accounts.system_program,
+        amount - fee,
+    )?;
+
+    emit_cpi!(FundsWithdrawn {
+        header: event_header(presale)?,
+        owner: *ctx.accounts.owner.key,
+        destination: ctx.accounts.treasury_wallet.key(),
+        amount: amount - fee,
+    });
+
+    Ok(())
+}
+
+/// Events recording what `authorize_admin` spent.
+struct AdminApproval {
+    proposal_executed: Option<ProposalExecuted>,
+    queued_action_executed: Option<QueuedActionExecuted>,
+}
+
+impl AdminApproval {
+    /// Emits the events, from a handler whose accounts are `#[event_cpi]`.
+    fn emit(self, event_authority: &AccountInfo, event_authority_bump: u8) -> Result<()> {
+        if let Some(event) = self.proposal_executed {
+            emit_event_cpi(event_authority, event_authority_bump, &event)?;
+        }
+        if let Some(event) = self.queued_action_executed {
+            emit_event_cpi(event_authority, event_authority_bump, &event)?;
+        }
+        Ok(())
+    }
+}
+
+/// The self-CPI `emit_cpi!` makes, for helpers that cannot reach the
+/// handler's `ctx`.
+fn emit_event_cpi<E: anchor_lang::Event>(
+    event_authority: &AccountInfo,
+    event_authority_bump: u8,
+    event: &E,
+) -> Result<()> {
+    let data: Vec<u8> = anchor_lang::event::EVENT_IX_TAG_LE
+        .into_iter()
+        .chain(event.data())
+        .collect();
+    let ix = anchor_lang::solana_program::instruction::Instruction::new_with_bytes(
+        crate::ID,
+        &data,
+        vec![AccountMeta::new_readonly(*event_authority.key, true)],
+    );
+    anchor_lang::solana_program::program::invoke_signed(
+        &ix,
+        std::slice::from_ref(event_authority),
+        &[&[b"__event_authority", &[event_authority_bump]]],
+    )
+    .map_err(Into::into)
+}
+
+/// Checks that `signer` may take `action` on `presale` now. Without a
+/// timelock the action is approved on the spot by `approve_admin_action`.
+/// With one, `signer` must be an admin and `queued_action` the matured queue
+/// entry for exactly this action, which is spent.
+fn authorize_admin(
+    presale: &mut Account<Presale>,
+    signer: &Pubkey,
+    council: &Option<Account<Council>>,
+    proposal: &mut Option<Account<AdminProposal>>,
+    queued_action: &mut Option<Account<QueuedAction>>,
+    action: AdminAction,
+) -> Result<AdminApproval> {
+    let now = Clock::get()?.unix_timestamp;
+    if presale.timelock_delay_at(now) == 0 {
+        return Ok(AdminApproval {
+            proposal_executed: approve_admin_action(presale, signer, council, proposal, action)?,
+            queued_action_executed: None,
+        });
+    }
+
+    require_admin(presale, signer, council)?;
+    let Some(queued_action) = queued_action else {
+        return err!(ErrorCode::QueuedActionRequired);
+    };
+    queued_action.execute(&action, presale.council, now)?;
+    Ok(AdminApproval {
+        proposal_executed: None,
+        queued_action_executed: Some(QueuedActionExecuted {
+            header: event_header(presale)?,
+            queued_action: queued_action.key(),
+            id: queued_action.id,
+            executor: *signer,
+            action,
+        }),
+    })
+}
+
+/// Fails unless `signer` is the owner or, under a council, a member.
+fn require_admin(
+    presale: &Presale,
+    signer: &Pubkey,
+    council: &Option<Account<Council>>,
+) -> Result<()> {
+    if presale.council == Pubkey::default() {
+        require!(presale.owner == *signer, ErrorCode::Unauthorized);
+        return Ok(());
+    }
+    let Some(council) = council else {
+        return err!(ErrorCode::MissingCouncil);
+    };
+    council.member_index(signer)?;
+    Ok(())
+}
+
+/// Checks that `signer` may approve `action` on `presale`. Without a council
+/// the owner acts alone. With one, `signer` must be a member and `proposal`
+/// an approved, unexpired proposal for exactly this action, which is spent.
+/// Returns the event recording the execution, if a proposal was spent.
+fn approve_admin_action(
+    presale: &mut Account<Presale>,
+    signer: &Pubkey,
+    council: &Option<Account<Council>>,
+    proposal: &mut Option<Account<AdminProposal>>,
+    action: AdminAction,
+) -> Result<Option<ProposalExecuted>> {
+    if presale.council == Pubkey::default() {
+        require!(presale.owner == *signer, ErrorCode::Unauthorized);
+        return Ok(None);
+    }
+    let (Some(council), Some(proposal)) = (council, proposal) else {
+        return err!(ErrorCode::ProposalRequired);
+    };
+
+    council.member_index(signer)?;
+    proposal.execute(council, &action, Clock::get()?.unix_timestamp)?;
+    Ok(Some(ProposalExecuted {
+        header: event_header(presale)?,
+        proposal: proposal.key(),
+        id: proposal.id,
+        executor: *signer,
+        action,
+    }))
+}
+
+/// Stamps the next event for `presale` with the current slot and time.
+/// Counts `amount` against the mint's emission ceiling when it came from the
+/// genesis category vaults. Tokens a sale brought itself, such as a partner
+/// mint or NLOV already in circulation, do not touch the ceiling.
+fn record_emission(
+    emission_schedule: &mut Option<Account<EmissionSchedule>>,
+    genesis_funded: bool,
+    amount: u64,
+    now: i64,
+) -> Result<()> {
+    if !genesis_funded {
+        return Ok(());
+    }
+    let Some(emission_schedule) = emission_schedule else {
+        return err!(ErrorCode::MissingEmissionSchedule);
+    };
+    emission_schedule.record(amount, now)
+}
+
+/// Moves the mint's TGE along with `presale`'s. A `genesis_funded` sale must
+/// pass the schedule; other sales only pass it if the mint follows them.
+fn follow_tge(
+    emission_schedule: &mut Option<Account<EmissionSchedule>>,
+    presale: &Account<Presale>,
+    tge_timestamp: i64,
+) -> Result<()> {
+    match emission_schedule {
+        Some(emission_schedule) => emission_schedule.follow_tge(&presale.key(), tge_timestamp),
+        None => require!(!presale.genesis_funded, ErrorCode::MissingEmissionSchedule),
+    }
+    Ok(())
+}
+
+fn event_header(presale: &mut Account<Presale>) -> Result<EventHeader> {
+    let key = presale.key();
+    presale.next_event_header(key, &Clock::get()?)
+}
+
+/// Moves `presale` to `next` and returns the event recording it.
+fn change_phase(presale: &mut Account<Presale>, next: SalePhase) -> Result<PhaseChanged> {
+    let from = presale.transition(next)?;
+    Ok(PhaseChanged {
+        header: event_header(presale)?,
+        from,
+        to: next,
+    })
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct InitializeProtocol<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ProtocolConfig::INIT_SPACE,
+        seeds = [b"protocol_config"],
+        bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    // Only the upgrade authority may set up the protocol.
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateProtocolConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreatePresale<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Presale::INIT_SPACE,
+        seeds = [b"presale", owner.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub presale: Account<'info, Presale>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut, address = protocol_config.fee_recipient @ ErrorCode::InvalidFeeRecipient)]
+    pub fee_recipient: SystemAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        constraint = owner_token_account.mint == token_mint.key() @ ErrorCode::InvalidTokenMint,
+        constraint = owner_token_account.owner == owner.key() @ ErrorCode::InvalidTokenAccountOwner
+    )]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = presale_token_account.mint == token_mint.key() @ ErrorCode::InvalidTokenMint,
+        constraint = presale_token_account.owner == presale.key() @ ErrorCode::InvalidTokenAccountOwner
+    )]
+    pub presale_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Contribute<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(mut, seeds = [b"vault", presale.key().as_ref()], bump)]
+    pub presale_account: SystemAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserInfo::INIT_SPACE,
+        seeds = [b"user_info", presale.key().as_ref(), user.key().as_ref()],
+        bump,
+        constraint = user_info.user == Pubkey::default()
+            || user_info.user == user.key() @ ErrorCode::UserInfoMismatch
+    )]
+    pub user_info: Account<'info, UserInfo>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(mut, address = presale.receipt_mint @ ErrorCode::InvalidReceiptMint)]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        constraint = user_receipt_account.mint == presale.receipt_mint @ ErrorCode::InvalidReceiptMint,
+        constraint = user_receipt_account.owner == user.key() @ ErrorCode::InvalidTokenAccountOwner
+    )]
+    pub user_receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimTokens<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(
+        mut,
+        address = presale.presale_token_account @ ErrorCode::InvalidPresaleTokenAccount
+    )]
+    pub presale_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = user_token_account.mint == presale.token_mint @ ErrorCode::InvalidTokenMint,
+        constraint = user_token_account.owner == user.key() @ ErrorCode::InvalidTokenAccountOwner
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    /// The signer's own allocation, or any allocation of the sale when
+    /// redeeming transferable receipts.
+    #[account(
+        mut,
+        seeds = [b"user_info", presale.key().as_ref(), user_info.user.as_ref()],
+        bump,
+        constraint = user_info.user == user.key()
+            || presale.receipts_transferable @ ErrorCode::UserInfoMismatch
+    )]
+    pub user_info: Account<'info, UserInfo>,
+    pub user: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    #[account(mut, address = presale.receipt_mint @ ErrorCode::InvalidReceiptMint)]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        constraint = user_receipt_account.mint == presale.receipt_mint @ ErrorCode::InvalidReceiptMint,
+        constraint = user_receipt_account.owner == user.key() @ ErrorCode::InvalidTokenAccountOwner
+    )]
+    pub user_receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+    /// Required when the tokens came from the genesis category vaults.
+    #[account(
+        mut,
+        seeds = [b"emission_schedule", presale.token_mint.as_ref()],
+        bump = emission_schedule.bump
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PauseUnpause<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    pub owner: Signer<'info>,
+    #[account(address = presale.council @ ErrorCode::InvalidCouncil)]
+    pub council: Option<Account<'info, Council>>,
+    #[account(
+        mut,
+        constraint = proposal.council == presale.council @ ErrorCode::ProposalMismatch
+    )]
+    pub proposal: Option<Account<'info, AdminProposal>>,
+    #[account(
+        mut,
+        constraint = queued_action.presale == presale.key() @ ErrorCode::QueuedActionMismatch
+    )]
+    pub queued_action: Option<Account<'info, QueuedAction>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PhaseTransition<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    pub owner: Signer<'info>,
+    #[account(address = presale.council @ ErrorCode::InvalidCouncil)]
+    pub council: Option<Account<'info, Council>>,
+    #[account(
+        mut,
+        constraint = proposal.council == presale.council @ ErrorCode::ProposalMismatch
+    )]
+    pub proposal: Option<Account<'info, AdminProposal>>,
+    #[account(
+        mut,
+        constraint = queued_action.presale == presale.key() @ ErrorCode::QueuedActionMismatch
+    )]
+    pub queued_action: Option<Account<'info, QueuedAction>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetTge<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    pub owner: Signer<'info>,
+    #[account(address = presale.council @ ErrorCode::InvalidCouncil)]
+    pub council: Option<Account<'info, Council>>,
+    #[account(
+        mut,
+        constraint = proposal.council == presale.council @ ErrorCode::ProposalMismatch
+    )]
+    pub proposal: Option<Account<'info, AdminProposal>>,
+    #[account(
+        mut,
+        constraint = queued_action.presale == presale.key() @ ErrorCode::QueuedActionMismatch
+    )]
+    pub queued_action: Option<Account<'info, QueuedAction>>,
+    /// Required when the tokens came from the genesis category vaults.
+    #[account(
+        mut,
+        seeds = [b"emission_schedule", presale.token_mint.as_ref()],
+        bump = emission_schedule.bump
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ConfigureLiquidityLock<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    pub owner: Signer<'info>,
+    #[account(address = presale.council @ ErrorCode::InvalidCouncil)]
+    pub council: Option<Account<'info, Council>>,
+    #[account(
+        mut,
+        constraint = proposal.council == presale.council @ ErrorCode::ProposalMismatch
+    )]
+    pub proposal: Option<Account<'info, AdminProposal>>,
+    #[account(
+        mut,
+        constraint = queued_action.presale == presale.key() @ ErrorCode::QueuedActionMismatch
+    )]
+    pub queued_action: Option<Account<'info, QueuedAction>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FinalizePresale<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(mut, seeds = [b"vault", presale.key().as_ref()], bump)]
+    pub presale_account: SystemAccount<'info>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + LiquidityLock::INIT_SPACE,
+        seeds = [b"liquidity_lock", presale.key().as_ref()],
+        bump
+    )]
+    pub liquidity_lock: Account<'info, LiquidityLock>,
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"liquidity_lock_nlov", liquidity_lock.key().as_ref()],
+        bump,
+        token::mint = token_mint,
+        token::authority = liquidity_lock
+    )]
+    pub lock_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = presale.token_mint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, token::mint = token_mint, token::authority = owner)]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    #[account(address = presale.council @ ErrorCode::InvalidCouncil)]
+    pub council: Option<Account<'info, Council>>,
+    #[account(
+        mut,
+        constraint = proposal.council == presale.council @ ErrorCode::ProposalMismatch
+    )]
+    pub proposal: Option<Account<'info, AdminProposal>>,
+    #[account(
+        mut,
+        constraint = queued_action.presale == presale.key() @ ErrorCode::QueuedActionMismatch
+    )]
+    pub queued_action: Option<Account<'info, QueuedAction>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DepositLpTokens<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(
+        mut,
+        seeds = [b"liquidity_lock", presale.key().as_ref()],
+        bump = liquidity_lock.bump
+    )]
+    pub liquidity_lock: Account<'info, LiquidityLock>,
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [b"liquidity_lock_lp", liquidity_lock.key().as_ref()],
+        bump,
+        token::mint = lp_mint,
+        token::authority = liquidity_lock
+    )]
+    pub lp_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = lp_mint, token::authority = owner)]
+    pub owner_lp_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    #[account(address = presale.council @ ErrorCode::InvalidCouncil)]
+    pub council: Option<Account<'info, Council>>,
+    #[account(
+        mut,
+        constraint = proposal.council == presale.council @ ErrorCode::ProposalMismatch
+    )]
+    pub proposal: Option<Account<'info, AdminProposal>>,
+    #[account(
+        mut,
+        constraint = queued_action.presale == presale.key() @ ErrorCode::QueuedActionMismatch
+    )]
+    pub queued_action: Option<Account<'info, QueuedAction>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReleaseLiquidity<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(
+        mut,
+        seeds = [b"liquidity_lock", presale.key().as_ref()],
+        bump = liquidity_lock.bump
+    )]
+    pub liquidity_lock: Account<'info, LiquidityLock>,
+    #[account(mut, address = liquidity_lock.beneficiary)]
+    pub beneficiary: SystemAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"liquidity_lock_nlov", liquidity_lock.key().as_ref()],
+        bump
+    )]
+    pub lock_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = presale.token_mint, token::authority = beneficiary)]
+    pub beneficiary_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"liquidity_lock_lp", liquidity_lock.key().as_ref()],
+        bump
+    )]
+    pub lp_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = liquidity_lock.lp_mint,
+        token::authority = beneficiary
+    )]
+    pub beneficiary_lp_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Required when the tokens came from the genesis category vaults.
+    #[account(
+        mut,
+        seeds = [b"emission_schedule", presale.token_mint.as_ref()],
+        bump = emission_schedule.bump
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DeclareEmergency<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(mut, seeds = [b"vault", presale.key().as_ref()], bump)]
+    pub presale_account: SystemAccount<'info>,
+    /// Required once the sale has been finalized.
+    #[account(
+        mut,
+        seeds = [b"liquidity_lock", presale.key().as_ref()],
+        bump = liquidity_lock.bump
+    )]
+    pub liquidity_lock: Option<Account<'info, LiquidityLock>>,
+    pub owner: Signer<'info>,
+    #[account(address = presale.council @ ErrorCode::InvalidCouncil)]
+    pub council: Option<Account<'info, Council>>,
+    #[account(
+        mut,
+        constraint = proposal.council == presale.council @ ErrorCode::ProposalMismatch
+    )]
+    pub proposal: Option<Account<'info, AdminProposal>>,
+    #[account(
+        mut,
+        constraint = queued_action.presale == presale.key() @ ErrorCode::QueuedActionMismatch
+    )]
+    pub queued_action: Option<Account<'info, QueuedAction>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimRefund<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(mut, seeds = [b"vault", presale.key().as_ref()], bump)]
+    pub presale_account: SystemAccount<'info>,
+    /// The signer's own allocation, or any allocation of the sale when
+    /// redeeming transferable receipts.
+    #[account(
+        mut,
+        seeds = [b"user_info", presale.key().as_ref(), user_info.user.as_ref()],
+        bump,
+        constraint = user_info.user == user.key()
+            || presale.receipts_transferable @ ErrorCode::UserInfoMismatch
+    )]
+    pub user_info: Account<'info, UserInfo>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(mut, address = presale.receipt_mint @ ErrorCode::InvalidReceiptMint)]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        constraint = user_receipt_account.mint == presale.receipt_mint @ ErrorCode::InvalidReceiptMint,
+        constraint = user_receipt_account.owner == user.key() @ ErrorCode::InvalidTokenAccountOwner
+    )]
+    pub user_receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MigratePresale<'info> {
+    /// CHECK: Decoded by `migration::upgrade_presale`, which accepts older layouts.
+    #[account(mut)]
+    pub presale: UncheckedAccount<'info>,
+    #[account(seeds = [b"vault", presale.key().as_ref()], bump)]
+    pub presale_account: SystemAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MigrateUserInfo<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    /// CHECK: Decoded by `migration::upgrade_user_info`, which accepts older layouts.
+    #[account(
+        mut,
+        seeds = [b"user_info", presale.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_info: UncheckedAccount<'info>,
+    /// CHECK: Only used to derive and check the `user_info` address.
+    pub user: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ConfigureProceeds<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    pub owner: Signer<'info>,
+    #[account(address = presale.council @ ErrorCode::InvalidCouncil)]
+    pub council: Option<Account<'info, Council>>,
+    #[account(
+        mut,
+        constraint = proposal.council == presale.council @ ErrorCode::ProposalMismatch
+    )]
+    pub proposal: Option<Account<'info, AdminProposal>>,
+    #[account(
+        mut,
+        constraint = queued_action.presale == presale.key() @ ErrorCode::QueuedActionMismatch
+    )]
+    pub queued_action: Option<Account<'info, QueuedAction>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DistributeProceeds<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(mut, seeds = [b"vault", presale.key().as_ref()], bump)]
+    pub presale_account: SystemAccount<'info>,
+    #[account(
+        mut,
+        address = presale.proceeds[ProceedsDestination::Liquidity as usize].wallet
+            @ ErrorCode::InvalidProceedsWallet
+    )]
+    pub liquidity_wallet: SystemAccount<'info>,
+    #[account(
+        mut,
+        address = presale.proceeds[ProceedsDestination::Treasury as usize].wallet
+            @ ErrorCode::InvalidProceedsWallet
+    )]
+    pub treasury_wallet: SystemAccount<'info>,
+    #[account(
+        mut,
+        address = presale.proceeds[ProceedsDestination::Development as usize].wallet
+            @ ErrorCode::InvalidProceedsWallet
+    )]
+    pub development_wallet: SystemAccount<'info>,
+    #[account(
+        mut,
+        address = presale.proceeds[ProceedsDestination::Marketing as usize].wallet
+            @ ErrorCode::InvalidProceedsWallet
+    )]
+    pub marketing_wallet: SystemAccount<'info>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut, address = protocol_config.fee_recipient @ ErrorCode::InvalidFeeRecipient)]
+    pub fee_recipient: SystemAccount<'info>,
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(address = presale.council @ ErrorCode::InvalidCouncil)]
+    pub council: Option<Account<'info, Council>>,
+    #[account(
+        mut,
+        constraint = proposal.council == presale.council @ ErrorCode::ProposalMismatch
+    )]
+    pub proposal: Option<Account<'info, AdminProposal>>,
+    #[account(
+        mut,
+        constraint = queued_action.presale == presale.key() @ ErrorCode::QueuedActionMismatch
+    )]
+    pub queued_action: Option<Account<'info, QueuedAction>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(mut, seeds = [b"vault", presale.key().as_ref()], bump)]
+    pub presale_account: SystemAccount<'info>,
+    #[account(
+        mut,
+        address = presale.proceeds[ProceedsDestination::Treasury as usize].wallet
+            @ ErrorCode::InvalidProceedsWallet
+    )]
+    pub treasury_wallet: SystemAccount<'info>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut, address = protocol_config.fee_recipient @ ErrorCode::InvalidFeeRecipient)]
+    pub fee_recipient: SystemAccount<'info>,
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(address = presale.council @ ErrorCode::InvalidCouncil)]
+    pub council: Option<Account<'info, Council>>,
+    #[account(
+        mut,
+        constraint = proposal.council == presale.council @ ErrorCode::ProposalMismatch
+    )]
+    pub proposal: Option<Account<'info, AdminProposal>>,
+    #[account(
+        mut,
+        constraint = queued_action.presale == presale.key() @ ErrorCode::QueuedActionMismatch
+    )]
+    pub queued_action: Option<Account<'info, QueuedAction>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct VerifyInvariants<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(seeds = [b"vault", presale.key().as_ref()], bump)]
+    pub presale_account: SystemAccount<'info>,
+    #[account(address = presale.presale_token_account @ ErrorCode::InvalidPresaleTokenAccount)]
+    pub presale_token_account: InterfaceAccount<'info, TokenAccount>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ConfigureAllocationTransfers<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    pub owner: Signer<'info>,
+    #[account(address = presale.council @ ErrorCode::InvalidCouncil)]
+    pub council: Option<Account<'info, Council>>,
+    #[account(
+        mut,
+        constraint = proposal.council == presale.council @ ErrorCode::ProposalMismatch
+    )]
+    pub proposal: Option<Account<'info, AdminProposal>>,
+    #[account(
+        mut,
+        constraint = queued_action.presale == presale.key() @ ErrorCode::QueuedActionMismatch
+    )]
+    pub queued_action: Option<Account<'info, QueuedAction>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TransferAllocation<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(
+        mut,
+        seeds = [b"user_info", presale.key().as_ref(), from.key().as_ref()],
+        bump,
+        constraint = from_user_info.user == from.key() @ ErrorCode::UserInfoMismatch
+    )]
+    pub from_user_info: Account<'info, UserInfo>,
+    #[account(
+        init_if_needed,
+        payer = from,
+        space = 8 + UserInfo::INIT_SPACE,
+        seeds = [b"user_info", presale.key().as_ref(), recipient.key().as_ref()],
+        bump,
+        constraint = to_user_info.user == Pubkey::default()
+            || to_user_info.user == recipient.key() @ ErrorCode::UserInfoMismatch
+    )]
+    pub to_user_info: Account<'info, UserInfo>,
+    #[account(mut)]
+    pub from: Signer<'info>,
+    /// CHECK: Only its address is used, to derive the recipient's user info.
+    #[account(constraint = recipient.key() != from.key() @ ErrorCode::InvalidTransferRecipient)]
+    pub recipient: UncheckedAccount<'info>,
+    #[account(address = presale.transfer_agent @ ErrorCode::InvalidTransferAgent)]
+    pub transfer_agent: Option<Signer<'info>>,
+    pub system_program: Program<'info, System>,
+    #[account(mut, address = presale.receipt_mint @ ErrorCode::InvalidReceiptMint)]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        constraint = from_receipt_account.mint == presale.receipt_mint @ ErrorCode::InvalidReceiptMint,
+        constraint = from_receipt_account.owner == from.key() @ ErrorCode::InvalidTokenAccountOwner
+    )]
+    pub from_receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = to_receipt_account.mint == presale.receipt_mint @ ErrorCode::InvalidReceiptMint,
+        constraint = to_receipt_account.owner == recipient.key() @ ErrorCode::InvalidTokenAccountOwner
+    )]
+    pub to_receipt_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct EnableReceipts<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(address = presale.token_mint @ ErrorCode::InvalidTokenMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    /// CHECK: Created and initialized as a Token-2022 mint by `enable_receipts`.
+    #[account(mut, seeds = [b"receipt_mint", presale.key().as_ref()], bump)]
+    pub receipt_mint: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub token_2022_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+    #[account(address = presale.council @ ErrorCode::InvalidCouncil)]
+    pub council: Option<Account<'info, Council>>,
+    #[account(
+        mut,
+        constraint = proposal.council == presale.council @ ErrorCode::ProposalMismatch
+    )]
+    pub proposal: Option<Account<'info, AdminProposal>>,
+    #[account(
+        mut,
+        constraint = queued_action.presale == presale.key() @ ErrorCode::QueuedActionMismatch
+    )]
+    pub queued_action: Option<Account<'info, QueuedAction>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateCouncil<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Council::INIT_SPACE,
+        seeds = [b"council", presale.key().as_ref()],
+        bump
+    )]
+    pub council: Account<'info, Council>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        constraint = queued_action.presale == presale.key() @ ErrorCode::QueuedActionMismatch
+    )]
+    pub queued_action: Option<Account<'info, QueuedAction>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Propose<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(mut, address = presale.council @ ErrorCode::InvalidCouncil)]
+    pub council: Account<'info, Council>,
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + AdminProposal::INIT_SPACE,
+        seeds = [
+            b"proposal",
+            council.key().as_ref(),
+            council.proposal_count.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub proposal: Account<'info, AdminProposal>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReviewProposal<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(address = presale.council @ ErrorCode::InvalidCouncil)]
+    pub council: Account<'info, Council>,
+    #[account(
+        mut,
+        constraint = proposal.council == council.key() @ ErrorCode::ProposalMismatch
+    )]
+    pub proposal: Account<'info, AdminProposal>,
+    pub member: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AdminUpdate<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    pub owner: Signer<'info>,
+    #[account(address = presale.council @ ErrorCode::InvalidCouncil)]
+    pub council: Option<Account<'info, Council>>,
+    #[account(
+        mut,
+        constraint = proposal.council == presale.council @ ErrorCode::ProposalMismatch
+    )]
+    pub proposal: Option<Account<'info, AdminProposal>>,
+    #[account(
+        mut,
+        constraint = queued_action.presale == presale.key() @ ErrorCode::QueuedActionMismatch
+    )]
+    pub queued_action: Option<Account<'info, QueuedAction>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SweepUnsoldTokens<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(
+        mut,
+        address = presale.presale_token_account @ ErrorCode::InvalidPresaleTokenAccount
+    )]
+    pub presale_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = destination.mint == presale.token_mint @ ErrorCode::InvalidTokenMint,
+        constraint = destination.owner == presale.owner @ ErrorCode::InvalidTokenAccountOwner
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    #[account(address = presale.council @ ErrorCode::InvalidCouncil)]
+    pub council: Option<Account<'info, Council>>,
+    #[account(
+        mut,
+        constraint = proposal.council == presale.council @ ErrorCode::ProposalMismatch
+    )]
+    pub proposal: Option<Account<'info, AdminProposal>>,
+    #[account(
+        mut,
+        constraint = queued_action.presale == presale.key() @ ErrorCode::QueuedActionMismatch
+    )]
+    pub queued_action: Option<Account<'info, QueuedAction>>,
+    /// Required when the sale is `genesis_funded`.
+    #[account(
+        mut,
+        seeds = [b"emission_schedule", presale.token_mint.as_ref()],
+        bump = emission_schedule.bump
+    )]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct QueueAction<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + QueuedAction::INIT_SPACE,
+        seeds = [
+            b"queued_action",
+            presale.key().as_ref(),
+            presale.queued_action_count.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub queued_action: Account<'info, QueuedAction>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(address = presale.council @ ErrorCode::InvalidCouncil)]
+    pub council: Option<Account<'info, Council>>,
+    #[account(
+        mut,
+        constraint = proposal.council == presale.council @ ErrorCode::ProposalMismatch
+    )]
+    pub proposal: Option<Account<'info, AdminProposal>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelQueuedAction<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(
+        mut,
+        constraint = queued_action.presale == presale.key() @ ErrorCode::QueuedActionMismatch
+    )]
+    pub queued_action: Account<'info, QueuedAction>,
+    pub authority: Signer<'info>,
+    #[account(address = presale.council @ ErrorCode::InvalidCouncil)]
+    pub council: Option<Account<'info, Council>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateVesting<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + VestingLedger::INIT_SPACE,
+        seeds = [b"vesting_ledger", mint.key().as_ref()],
+        bump
+    )]
+    pub vesting_ledger: Account<'info, VestingLedger>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VestingSchedule::INIT_SPACE,
+        seeds = [
+            b"vesting",
+            mint.key().as_ref(),
+            vesting_ledger.schedule_count.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub vesting_schedule: Account<'info, VestingSchedule>,
+    // Created by the client and handed to the schedule, like the presale
+    // token account.
+    #[account(
+        mut,
+        constraint = escrow.mint == mint.key() @ ErrorCode::InvalidTokenMint,
+        constraint = escrow.owner == vesting_schedule.key() @ ErrorCode::InvalidTokenAccountOwner
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        constraint = funder_token_account.mint == mint.key() @ ErrorCode::InvalidTokenMint,
+        constraint = funder_token_account.owner == authority.key()
+            || funder_token_account.owner == vault_authority.key()
+            @ ErrorCode::InvalidTokenAccountOwner
+    )]
+    pub funder_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut, address = protocol_config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Signs for the mint's category vaults; holds no data.
+    #[account(seeds = [b"category_vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Release<'info> {
+    #[account(mut, has_one = beneficiary @ ErrorCode::Unauthorized)]
+    pub vesting_schedule: Account<'info, VestingSchedule>,
+    #[account(mut, address = vesting_schedule.escrow @ ErrorCode::InvalidTokenAccountOwner)]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = beneficiary_token_account.mint == vesting_schedule.mint @ ErrorCode::InvalidTokenMint,
+        constraint = beneficiary_token_account.owner == beneficiary.key() @ ErrorCode::InvalidTokenAccountOwner
+    )]
+    pub beneficiary_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub beneficiary: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    #[account(
+        mut,
+        seeds = [b"emission_schedule", vesting_schedule.mint.as_ref()],
+        bump = emission_schedule.bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevokeGrant<'info> {
+    #[account(
+        mut,
+        seeds = [b"vesting_ledger", vesting_schedule.mint.as_ref()],
+        bump = vesting_ledger.bump
+    )]
+    pub vesting_ledger: Account<'info, VestingLedger>,
+    #[account(mut)]
+    pub vesting_schedule: Account<'info, VestingSchedule>,
+    #[account(mut, address = vesting_schedule.escrow @ ErrorCode::InvalidTokenAccountOwner)]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    /// The vault of the schedule's category, whose ledger allocation the
+    /// returned tokens free up.
+    #[account(
+        mut,
+        seeds = [
+            b"category_vault",
+            vesting_schedule.mint.as_ref(),
+            &[vesting_schedule.category as u8]
+        ],
+        bump
+    )]
+    pub category_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(address = protocol_config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Source of the mint's TGE.
+    #[account(
+        seeds = [b"emission_schedule", vesting_schedule.mint.as_ref()],
+        bump = emission_schedule.bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(category: VestingCategory)]
+pub struct FundPresale<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(
+        mut,
+        address = presale.presale_token_account @ ErrorCode::InvalidPresaleTokenAccount
+    )]
+    pub presale_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = presale.token_mint @ ErrorCode::InvalidTokenMint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + VestingLedger::INIT_SPACE,
+        seeds = [b"vesting_ledger", mint.key().as_ref()],
+        bump
+    )]
+    pub vesting_ledger: Account<'info, VestingLedger>,
+    #[account(
+        mut,
+        seeds = [b"category_vault", mint.key().as_ref(), &[category as u8]],
+        bump
+    )]
+    pub category_vault: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Signs for the mint's category vaults; holds no data.
+    #[account(seeds = [b"category_vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut, address = protocol_config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FundLiquidityLock<'info> {
+    #[account(mut)]
+    pub presale: Account<'info, Presale>,
+    #[account(
+        mut,
+        seeds = [b"liquidity_lock", presale.key().as_ref()],
+        bump = liquidity_lock.bump
+    )]
+    pub liquidity_lock: Account<'info, LiquidityLock>,
+    #[account(
+        mut,
+        seeds = [b"liquidity_lock_nlov", liquidity_lock.key().as_ref()],
+        bump
+    )]
+    pub lock_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(address = presale.token_mint @ ErrorCode::InvalidTokenMint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + VestingLedger::INIT_SPACE,
+        seeds = [b"vesting_ledger", mint.key().as_ref()],
+        bump
+    )]
+    pub vesting_ledger: Account<'info, VestingLedger>,
+    #[account(
+        mut,
+        seeds = [
+            b"category_vault",
+            mint.key().as_ref(),
+            &[VestingCategory::Liquidity as u8]
+        ],
+        bump
+    )]
+    pub category_vault: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Signs for the mint's category vaults; holds no data.
+    #[account(seeds = [b"category_vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut, address = protocol_config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevokeMilestoneGrant<'info> {
+    #[account(
+        mut,
+        seeds = [b"vesting_ledger", milestone_schedule.mint.as_ref()],
+        bump = vesting_ledger.bump
+    )]
+    pub vesting_ledger: Account<'info, VestingLedger>,
+    #[account(mut)]
+    pub milestone_schedule: Account<'info, MilestoneSchedule>,
+    #[account(mut, address = milestone_schedule.escrow @ ErrorCode::InvalidTokenAccountOwner)]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    /// The vault of the schedule's category.
+    #[account(
+        mut,
+        seeds = [
+            b"category_vault",
+            milestone_schedule.mint.as_ref(),
+            &[milestone_schedule.category as u8]
+        ],
+        bump
+    )]
+    pub category_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(address = protocol_config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Source of the mint's TGE.
+    #[account(
+        seeds = [b"emission_schedule", milestone_schedule.mint.as_ref()],
+        bump = emission_schedule.bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateMilestoneVesting<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + VestingLedger::INIT_SPACE,
+        seeds = [b"vesting_ledger", mint.key().as_ref()],
+        bump
+    )]
+    pub vesting_ledger: Account<'info, VestingLedger>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MilestoneSchedule::INIT_SPACE,
+        seeds = [
+            b"milestone_vesting",
+            mint.key().as_ref(),
+            vesting_ledger.schedule_count.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub milestone_schedule: Account<'info, MilestoneSchedule>,
+    #[account(
+        mut,
+        constraint = escrow.mint == mint.key() @ ErrorCode::InvalidTokenMint,
+        constraint = escrow.owner == milestone_schedule.key() @ ErrorCode::InvalidTokenAccountOwner
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        constraint = funder_token_account.mint == mint.key() @ ErrorCode::InvalidTokenMint,
+        constraint = funder_token_account.owner == authority.key()
+            || funder_token_account.owner == vault_authority.key()
+            @ ErrorCode::InvalidTokenAccountOwner
+    )]
+    pub funder_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut, address = protocol_config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Signs for the mint's category vaults; holds no data.
+    #[account(seeds = [b"category_vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(index: u8)]
+pub struct AttestMilestone<'info> {
+    #[account(mut)]
+    pub milestone_schedule: Account<'info, MilestoneSchedule>,
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + MilestoneAttestation::INIT_SPACE,
+        seeds = [b"milestone_attestation", milestone_schedule.key().as_ref(), &[index]],
+        bump
+    )]
+    pub attestation: Account<'info, MilestoneAttestation>,
+    /// The attester, or anyone once the tranche's fallback has passed.
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// Source of the mint's TGE.
+    #[account(
+        seeds = [b"emission_schedule", milestone_schedule.mint.as_ref()],
+        bump = emission_schedule.bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReleaseMilestones<'info> {
+    #[account(mut, has_one = beneficiary @ ErrorCode::Unauthorized)]
+    pub milestone_schedule: Account<'info, MilestoneSchedule>,
+    #[account(mut, address = milestone_schedule.escrow @ ErrorCode::InvalidTokenAccountOwner)]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = beneficiary_token_account.mint == milestone_schedule.mint @ ErrorCode::InvalidTokenMint,
+        constraint = beneficiary_token_account.owner == beneficiary.key() @ ErrorCode::InvalidTokenAccountOwner
+    )]
+    pub beneficiary_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub beneficiary: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    #[account(
+        mut,
+        seeds = [b"emission_schedule", milestone_schedule.mint.as_ref()],
+        bump = emission_schedule.bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateDistributor<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + VestingLedger::INIT_SPACE,
+        seeds = [b"vesting_ledger", mint.key().as_ref()],
+        bump
+    )]
+    pub vesting_ledger: Account<'info, VestingLedger>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MerkleDistributor::INIT_SPACE,
+        seeds = [
+            b"distributor",
+            mint.key().as_ref(),
+            vesting_ledger.schedule_count.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub distributor: Account<'info, MerkleDistributor>,
+    #[account(
+        mut,
+        constraint = escrow.mint == mint.key() @ ErrorCode::InvalidTokenMint,
+        constraint = escrow.owner == distributor.key() @ ErrorCode::InvalidTokenAccountOwner
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        constraint = funder_token_account.mint == mint.key() @ ErrorCode::InvalidTokenMint,
+        constraint = funder_token_account.owner == authority.key()
+            || funder_token_account.owner == vault_authority.key()
+            @ ErrorCode::InvalidTokenAccountOwner
+    )]
+    pub funder_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut, address = protocol_config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Signs for the mint's category vaults; holds no data.
+    #[account(seeds = [b"category_vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimDistribution<'info> {
+    #[account(mut)]
+    pub distributor: Account<'info, MerkleDistributor>,
+    #[account(
+        init_if_needed,
+        payer = beneficiary,
+        space = 8 + ClaimStatus::INIT_SPACE,
+        seeds = [b"claim_status", distributor.key().as_ref(), beneficiary.key().as_ref()],
+        bump
+    )]
+    pub claim_status: Account<'info, ClaimStatus>,
+    #[account(mut, address = distributor.escrow @ ErrorCode::InvalidTokenAccountOwner)]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = beneficiary_token_account.mint == distributor.mint @ ErrorCode::InvalidTokenMint,
+        constraint = beneficiary_token_account.owner == beneficiary.key() @ ErrorCode::InvalidTokenAccountOwner
+    )]
+    pub beneficiary_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        seeds = [b"emission_schedule", distributor.mint.as_ref()],
+        bump = emission_schedule.bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct InitializeEmissionSchedule<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + EmissionSchedule::INIT_SPACE,
+        seeds = [b"emission_schedule", mint.key().as_ref()],
+        bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(constraint = presale.token_mint == mint.key() @ ErrorCode::InvalidTokenMint)]
+    pub presale: Account<'info, Presale>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut, address = protocol_config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExtendEmissionSchedule<'info> {
+    #[account(
+        mut,
+        seeds = [b"emission_schedule", mint.key().as_ref()],
+        bump = emission_schedule.bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(address = protocol_config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct StakeLocked<'info> {
+    #[account(has_one = beneficiary @ ErrorCode::Unauthorized)]
+    pub vesting_schedule: Account<'info, VestingSchedule>,
+    #[account(
+        init_if_needed,
+        payer = beneficiary,
+        space = 8 + StakePosition::INIT_SPACE,
+        seeds = [b"stake_position", vesting_schedule.key().as_ref()],
+        bump
+    )]
+    pub stake_position: Account<'info, StakePosition>,
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// Source of the mint's TGE.
+    #[account(
+        seeds = [b"emission_schedule", vesting_schedule.mint.as_ref()],
+        bump = emission_schedule.bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UnstakeLocked<'info> {
+    #[account(has_one = beneficiary @ ErrorCode::Unauthorized)]
+    pub vesting_schedule: Account<'info, VestingSchedule>,
+    #[account(
+        mut,
+        seeds = [b"stake_position", vesting_schedule.key().as_ref()],
+        bump = stake_position.bump
+    )]
+    pub stake_position: Account<'info, StakePosition>,
+    pub beneficiary: Signer<'info>,
+    /// Source of the mint's TGE.
+    #[account(
+        seeds = [b"emission_schedule", vesting_schedule.mint.as_ref()],
+        bump = emission_schedule.bump
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct BootstrapToken<'info> {
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = NLOV_DECIMALS,
+        mint::authority = authority,
+        mint::token_program = token_program,
+        extensions::metadata_pointer::authority = metadata_authority,
+        extensions::metadata_pointer::metadata_address = mint,
+        seeds = [b"nlov_mint"],
+        bump
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MetadataAuthority::INIT_SPACE,
+        seeds = [b"metadata_authority", mint.key().as_ref()],
+        bump
+    )]
+    pub metadata_authority: Account<'info, MetadataAuthority>,
+    /// CHECK: Signs for the category vaults; holds no data.
+    #[account(seeds = [b"category_vault_authority", mint.key().as_ref()], bump)]
+    pub vault_authority: UncheckedAccount<'info>,
+    // One vault per allocation category, in `VestingCategory::ALL` order.
+    // Only the program can move their tokens, and only into vesting.
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
+        seeds = [b"category_vault", mint.key().as_ref(), &[VestingCategory::SeedSale as u8]],
+        bump
+    )]
+    pub seed_sale_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
+        seeds = [b"category_vault", mint.key().as_ref(), &[VestingCategory::PrivateSale as u8]],
+        bump
+    )]
+    pub private_sale_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
+        seeds = [b"category_vault", mint.key().as_ref(), &[VestingCategory::Presale as u8]],
+        bump
+    )]
+    pub presale_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
+        seeds = [b"category_vault", mint.key().as_ref(), &[VestingCategory::PublicSale as u8]],
+        bump
+    )]
+    pub public_sale_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
+        seeds = [b"category_vault", mint.key().as_ref(), &[VestingCategory::Team as u8]],
+        bump
+    )]
+    pub team_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
+        seeds = [b"category_vault", mint.key().as_ref(), &[VestingCategory::Treasury as u8]],
+        bump
+    )]
+    pub treasury_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
+        seeds = [b"category_vault", mint.key().as_ref(), &[VestingCategory::Development as u8]],
+        bump
+    )]
+    pub development_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
+        seeds = [b"category_vault", mint.key().as_ref(), &[VestingCategory::Advisors as u8]],
+        bump
+    )]
+    pub advisors_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
+        seeds = [b"category_vault", mint.key().as_ref(), &[VestingCategory::Ecosystem as u8]],
+        bump
+    )]
+    pub ecosystem_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
+        seeds = [b"category_vault", mint.key().as_ref(), &[VestingCategory::NodeRewards as u8]],
+        bump
+    )]
+    pub node_rewards_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
+        seeds = [b"category_vault", mint.key().as_ref(), &[VestingCategory::Liquidity as u8]],
+        bump
+    )]
+    pub liquidity_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
+        seeds = [b"category_vault", mint.key().as_ref(), &[VestingCategory::Partnerships as u8]],
+        bump
+    )]
+    pub partnerships_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
+        seeds = [b"category_vault", mint.key().as_ref(), &[VestingCategory::Marketing as u8]],
+        bump
+    )]
+    pub marketing_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = vault_authority,
+        token::token_program = token_program,
+        seeds = [b"category_vault", mint.key().as_ref(), &[VestingCategory::Community as u8]],
+        bump
+    )]
+    pub community_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut, address = protocol_config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateTokenMetadata<'info> {
+    #[account(mut, mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"metadata_authority", mint.key().as_ref()],
+        bump = metadata_authority.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub metadata_authority: Account<'info, MetadataAuthority>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetMetadataAuthority<'info> {
+    #[account(mut, has_one = authority @ ErrorCode::Unauthorized)]
+    pub metadata_authority: Account<'info, MetadataAuthority>,
+    pub authority: Signer<'info>,
+}
+
+impl<'info> BootstrapToken<'info> {
+    /// The category vaults, in `VestingCategory::ALL` order.
+    fn vaults(&self) -> [&InterfaceAccount<'info, TokenAccount>; VESTING_CATEGORIES] {
+        [
+            &self.seed_sale_vault,
+            &self.private_sale_vault,
+            &self.presale_vault,
+            &self.public_sale_vault,
+            &self.team_vault,
+            &self.treasury_vault,
+            &self.development_vault,
+            &self.advisors_vault,
+            &self.ecosystem_vault,
+            &self.node_rewards_vault,
+            &self.liquidity_vault,
+            &self.partnerships_vault,
+            &self.marketing_vault,
+            &self.community_vault,
+        ]
+    }
+}
+
+#[account]
+#[derive(Default, InitSpace)]
+pub struct Presale {
+    pub version: u8,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub tge_timestamp: i64,
+    pub token_mint: Pubkey,
+    pub presale_token_account: Pubkey,
+    pub presale_supply: u64,
+    pub token_price: u64,
+    pub total_contributed: u64,
+    pub phase: SalePhase,
+    pub is_paused: bool,
+    pub owner: Pubkey,
+    pub tge_postponements: u8,
+    pub total_claimed: u64,
+    pub emergency: bool,
+    pub emergency_vault_snapshot: u64,
+    pub emergency_outstanding_snapshot: u64,
+    pub proceeds_configured: bool,
+    pub proceeds: [ProceedsLeg; PROCEEDS_LEGS],
+    pub liquidity_lock_configured: bool,
+    pub liquidity_lock_sol_bps: u16,
+    pub liquidity_lock_nlov: u64,
+    pub liquidity_lock_duration: i64,
+    pub creator: Pubkey,
+    pub bump: u8,
+    pub platform_fee_bps: u16,
+    /// Decimals of `token_mint`, which `token_price` is quoted against.
+    pub token_decimals: u8,
+    /// Lamports moved out of the vault as proceeds: withdrawals, distributions,
+    /// platform fees and the SOL locked for liquidity.
+    pub total_withdrawn: u64,
+    /// Sequence number of the last event emitted for this sale.
+    pub event_sequence: u64,
+    /// Whether `transfer_allocation` is open for this sale.
+    pub allocation_transfers_enabled: bool,
+    /// Agent that must co-sign every allocation transfer, or the default key
+    /// if none is required.
+    pub transfer_agent: Pubkey,
+    /// Token-2022 mint of the allocation receipts, or the default key if the
+    /// sale does not issue them.
+    pub receipt_mint: Pubkey,
+    /// Council whose approved proposals replace the owner for privileged
+    /// actions, or the default key if the owner acts alone.
+    pub council: Pubkey,
+    /// Seconds a privileged action must wait in the queue, or 0 for none.
+    /// See `timelock_delay_at` for the delay in force.
+    pub timelock_delay: i64,
+    /// Shorter delay that replaces `timelock_delay` from
+    /// `pending_timelock_delay_at`, or 0 if no reduction is pending.
+    pub pending_timelock_delay: i64,
+    pub pending_timelock_delay_at: i64,
+    /// Actions queued so far, which is also the id of the next one.
+    pub queued_action_count: u64,
+    /// Ceiling on `withdraw` and `distribute_proceeds` per rolling 24 hours.
+    pub withdrawal_limit: WithdrawalLimit,
+    /// Lamports withdrawn over the last 24 hours.
+    pub withdrawal_window: WithdrawalWindow,
+    /// Whether the sale holds NLOV from the genesis category vaults, moved in
+    /// by `fund_presale`. Only then do its claims and sweeps count against
+    /// the mint's emission ceiling.
+    pub genesis_funded: bool,
+    /// Whether the receipts can be transferred. They then carry the claim:
+    /// whoever holds them redeems them against any allocation of the sale.
+    pub receipts_transferable: bool,
+}
+
+impl Presale {
+    /// Rejects accounts still on an older layout; see `migrate_presale`.
+    pub fn require_current_version(&self) -> Result<()> {
+        require!(
+            self.version == PRESALE_VERSION,
+            ErrorCode::AccountNeedsMigration
+        );
+        Ok(())
+    }
+
+    /// Fails unless the sale is in one of `allowed`, ignoring the pause switch.
+    pub fn check_phase(&self, allowed: &[SalePhase]) -> Result<()> {
+        self.require_current_version()?;
+        require!(allowed.contains(&self.phase), ErrorCode::InvalidPhase);
+        Ok(())
+    }
+
+    /// Shared guard for every instruction that moves funds or tokens.
+    pub fn require_phase(&self, allowed: &[SalePhase]) -> Result<()> {
+        require!(!self.is_paused, ErrorCode::PresalePaused);
+        self.check_phase(allowed)
+    }
+
+    /// Moves the sale to `next`, rejecting any edge not in the state machine.
+    /// A sale only closes once every allocation has been claimed or refunded,
+    /// since nothing can be claimed or refunded after that. Returns the phase
+    /// it left.
+    pub fn transition(&mut self, next: SalePhase) -> Result<SalePhase> {
+        self.require_current_version()?;
+        require!(!self.is_paused, ErrorCode::PresalePaused);
+        require!(
+            self.phase.can_transition_to(next),
+            ErrorCode::InvalidPhaseTransition
+        );
+        if next == SalePhase::Closed {
+            require!(
+                self.total_claimed == self.total_contributed,
+                ErrorCode::AllocationsOutstanding
+            );
+        }
+
+        let from = self.phase;
+        self.phase = next;
+        Ok(from)
+    }
+
+    /// One-way switch into pro-rata refunds. Allowed from any phase but `Closed`,
+    /// and even while paused, since it is the exit path after an incident.
+    /// Returns the phase it left.
+    pub fn declare_emergency(&mut self, vault_balance: u64) -> Result<SalePhase> {
+        require!(!self.emergency, ErrorCode::EmergencyDeclared);
+        self.check_phase(&[
+            SalePhase::Pending,
+            SalePhase::Live,
+            SalePhase::Ended,
+            SalePhase::Finalized,
+            SalePhase::Claiming,
+            SalePhase::Refunding,
+        ])?;
+
+        let from = self.phase;
+        self.emergency = true;
+        self.is_paused = false;
+        self.phase = SalePhase::Refunding;
+        self.emergency_vault_snapshot = vault_balance;
+        self.emergency_outstanding_snapshot = self
+            .total_contributed
+            .checked_sub(self.total_claimed)
+            .ok_or(ErrorCode::CalculationError)?;
+        Ok(from)
+    }
+
+    /// Share of the vault, as snapshotted by `declare_emergency`, owed for an
+    /// unclaimed allocation of `nlov_amount`.
+    pub fn emergency_refund_amount(&self, nlov_amount: u64) -> Result<u64> {
+        require!(self.emergency, ErrorCode::InvalidPhase);
+        require!(
+            self.emergency_outstanding_snapshot > 0,
+            ErrorCode::NothingToRefund
+        );
+
+        let amount = (self.emergency_vault_snapshot as u128)
+            .checked_mul(nlov_amount as u128)
+            .and_then(|v| v.checked_div(self.emergency_outstanding_snapshot as u128))
+            .ok_or(ErrorCode::CalculationError)?;
+        u64::try_from(amount).map_err(|_| ErrorCode::CalculationError.into())
+    }
+
+    /// PDA signer seeds. Sales created before the launchpad have no creator
+    /// and live at the old singleton address.
+    pub fn signer_seeds(&self) -> Vec<&[u8]> {
+        let bump = std::slice::from_ref(&self.bump);
+        if self.creator == Pubkey::default() {
+            vec![b"presale".as_ref(), bump]
+        } else {
+            vec![
+                b"presale".as_ref(),
+                self.creator.as_ref(),
+                self.token_mint.as_ref(),
+                bump,
+            ]
+        }
+    }
+
+    /// Whether contributions are mirrored by receipt tokens.
+    pub fn receipts_enabled(&self) -> bool {
+        self.receipt_mint != Pubkey::default()
+    }
+
+    /// Checks an allocation transfer against the sale's policy. `agent` is
+    /// the transfer agent that co-signed, if any.
+    pub fn authorize_allocation_transfer(&self, agent: Option<Pubkey>) -> Result<()> {
+        require!(
+            self.allocation_transfers_enabled,
+            ErrorCode::AllocationTransfersDisabled
+        );
+        if self.transfer_agent != Pubkey::default() {
+            require!(
+                agent == Some(self.transfer_agent),
+                ErrorCode::TransferAgentRequired
+            );
+        }
+        Ok(())
+    }
+
+    /// Header for the next event emitted for this sale.
+    pub fn next_event_header(&mut self, presale: Pubkey, clock: &Clock) -> Result<EventHeader> {
+        self.event_sequence = self
+            .event_sequence
+            .checked_add(1)
+            .ok_or(ErrorCode::CalculationError)?;
+        Ok(EventHeader {
+            presale,
+            sequence: self.event_sequence,
+            slot: clock.slot,
+            unix_timestamp: clock.unix_timestamp,
+        })
+    }
+
+    /// Counts `amount` leaving the vault as proceeds.
+    pub fn record_withdrawal(&mut self, amount: u64) -> Result<()> {
+        self.total_withdrawn = self
+            .total_withdrawn
+            .checked_add(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+        Ok(())
+    }
+
+    /// Undoes `record_withdrawal` for lamports paid back into the vault.
+    pub fn record_return(&mut self, amount: u64) -> Result<()> {
+        self.total_withdrawn = self
+            .total_withdrawn
+            .checked_sub(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+        Ok(())
+    }
+
+    /// Counts a payout of `amount` from a vault holding `vault_balance`
+    /// against the rolling withdrawal window. `rate_limited` payouts must fit
+    /// the sale's withdrawal limit.
+    pub fn record_payout(
+        &mut self,
+        amount: u64,
+        vault_balance: u64,
+        now: i64,
+        rate_limited: bool,
+    ) -> Result<()> {
+        let limit = self.withdrawal_limit;
+        if rate_limited {
+            self.withdrawal_window
+                .record(&limit, amount, vault_balance, now)?;
+        } else {
+            self.withdrawal_window.count(amount, now)?;
+        }
+        self.record_withdrawal(amount)
+    }
+
+    /// Base units `lamports` buy at `token_price`, rounded down.
+    pub fn tokens_for_lamports(&self, lamports: u64) -> Result<u64> {
+        require!(self.token_price > 0, ErrorCode::InvalidTokenPrice);
+        let tokens = lamports as u128 * self.base_units_per_token()? / self.token_price as u128;
+        u64::try_from(tokens).map_err(|_| ErrorCode::CalculationError.into())
+    }
+
+    /// Lamports paid in for allocations that have not been refunded. Every
+    /// contribution rounds its allocation down, so this never exceeds what
+    /// was paid.
+    pub fn contributed_lamports(&self) -> Result<u64> {
+        let lamports = self.total_contributed as u128 * self.token_price as u128
+            / self.base_units_per_token()?;
+        u64::try_from(lamports).map_err(|_| ErrorCode::CalculationError.into())
+    }
+
+    fn base_units_per_token(&self) -> Result<u128> {
+        10u128
+            .checked_pow(self.token_decimals as u32)
+            .ok_or(ErrorCode::CalculationError.into())
+    }
+
+    /// Checks the books against the vault and presale token account balances.
+    /// The vault must hold every contributed lamport not yet withdrawn, or after
+    /// an emergency every refund still owed. The token account must hold the
+    /// unclaimed allocations plus the unsold supply. Anything above that is
+    /// surplus, e.g. a direct transfer.
+    pub fn reconcile(
+        &self,
+        header: EventHeader,
+        vault_balance: u64,
+        token_balance: u64,
+    ) -> Result<InvariantReport> {
+        self.require_current_version()?;
+        require!(
+            self.total_claimed <= self.total_contributed
+                && self.total_contributed <= self.presale_supply,
+            ErrorCode::InvariantViolation
+        );
+
+        let outstanding = self.total_contributed - self.total_claimed;
+        let required_vault_balance = if !self.emergency {
+            // Surplus lamports may be withdrawn too, so this floors at zero.
+            self.contributed_lamports()?
+                .saturating_sub(self.total_withdrawn)
+        } else if self.emergency_outstanding_snapshot == 0 {
+            0
+        } else {
+            self.emergency_refund_amount(outstanding)?
+        };
+        let unsold_supply = self.presale_supply - self.total_contributed;
+        let required_token_balance = outstanding + unsold_supply;
+
+        require!(
+            vault_balance >= required_vault_balance && token_balance >= required_token_balance,
+            ErrorCode::InvariantViolation
+        );
+
+        Ok(InvariantReport {
+            header,
+            phase: self.phase,
+            vault_balance,
+            required_vault_balance,
+            token_balance,
+            required_token_balance,
+            total_contributed: self.total_contributed,
+            total_claimed: self.total_claimed,
+            total_withdrawn: self.total_withdrawn,
+            unsold_supply,
+        })
+    }
+
+    /// Tokens above the allocations still owed to buyers: the unsold supply
+    /// plus any surplus sent to the presale token account.
+    pub fn sweepable_tokens(&self, token_balance: u64) -> u64 {
+        token_balance.saturating_sub(self.total_contributed - self.total_claimed)
+    }
+
+    /// Shrinks the supply by the unsold part of a sweep so `reconcile` no
+    /// longer expects it in the token account.
+    pub fn record_sweep(&mut self, amount: u64) {
+        let unsold_supply = self.presale_supply - self.total_contributed;
+        self.presale_supply -= amount.min(unsold_supply);
+    }
+
+    /// Protocol's cut of `amount` leaving the vault.
+    pub fn platform_fee(&self, amount: u64) -> Result<u64> {
+        let fee = amount as u128 * self.platform_fee_bps as u128 / BPS_DENOMINATOR as u128;
+        u64::try_from(fee).map_err(|_| ErrorCode::CalculationError.into())
+    }
+
+    /// Records the proceeds split. It can only be set once.
+    pub fn configure_proceeds(&mut self, legs: [ProceedsLeg; PROCEEDS_LEGS]) -> Result<()> {
+        require!(
+            !self.proceeds_configured,
+            ErrorCode::ProceedsAlreadyConfigured
+        );
+        require!(
+            legs.iter().all(|leg| leg.wallet != Pubkey::default()),
+            ErrorCode::InvalidProceedsWallet
+        );
+        let total_bps = legs.iter().map(|leg| leg.bps as u32).sum::<u32>();
+        require!(
+            total_bps == BPS_DENOMINATOR as u32,
+            ErrorCode::InvalidProceedsSplit
+        );
+
+        self.proceeds = legs;
+        self.proceeds_configured = true;
+        Ok(())
+    }
+
+    /// Splits `amount` across the proceeds legs by basis points. Rounding dust
+    /// goes to the treasury so the legs always add up to `amount`.
+    pub fn split_proceeds(&self, amount: u64) -> Result<[u64; PROCEEDS_LEGS]> {
+        require!(self.proceeds_configured, ErrorCode::ProceedsNotConfigured);
+
+        let mut shares = [0u64; PROCEEDS_LEGS];
+        for (share, leg) in shares.iter_mut().zip(self.proceeds.iter()) {
+            *share = (amount as u128 * leg.bps as u128 / BPS_DENOMINATOR as u128) as u64;
+        }
+        let distributed = shares.iter().sum::<u64>();
+        shares[ProceedsDestination::Treasury as usize] += amount - distributed;
+        Ok(shares)
+    }
+
+    /// Records how much of the raise `finalize_presale` locks for liquidity.
+    pub fn configure_liquidity_lock(
+        &mut self,
+        sol_bps: u16,
+        nlov_amount: u64,
+        lock_duration: i64,
+    ) -> Result<()> {
+        require!(
+            !self.liquidity_lock_configured,
+            ErrorCode::LiquidityLockAlreadyConfigured
+        );
+        require!(sol_bps <= BPS_DENOMINATOR, ErrorCode::InvalidLiquidityLock);
+        require!(lock_duration > 0, ErrorCode::InvalidLiquidityLock);
+
+        self.liquidity_lock_sol_bps = sol_bps;
+        self.liquidity_lock_nlov = nlov_amount;
+        self.liquidity_lock_duration = lock_duration;
+        self.liquidity_lock_configured = true;
+        Ok(())
+    }
+
+    /// Lamports moved from a vault holding `vault_balance` into the liquidity lock.
+    pub fn liquidity_lock_sol(&self, vault_balance: u64) -> Result<u64> {
+        require!(
+            self.liquidity_lock_configured,
+            ErrorCode::LiquidityLockNotConfigured
+        );
+        Ok(
+            (vault_balance as u128 * self.liquidity_lock_sol_bps as u128 / BPS_DENOMINATOR as u128)
+                as u64,
+        )
+    }
+
+    /// Start of the claim window. Every unlock is measured from this point.
+    pub fn claim_start(&self) -> Result<i64> {
+        require!(self.tge_timestamp != 0, ErrorCode::TgeNotSet);
+        Ok(self.tge_timestamp)
+    }
+
+    /// How bought allocations unlock from `claim_start`: on the terms of the
+    /// presale category.
+    pub fn unlock(&self) -> UnlockSchedule<'static> {
+        let terms = VestingCategory::Presale.terms();
+        UnlockSchedule {
+            tge_bps: terms.tge_bps,
+            cliff_duration: terms.cliff_months as i64 * VESTING_MONTH,
+            linear_duration: terms.linear_months as i64 * VESTING_MONTH,
+            step_period: 0,
+            step_bps: &[],
+        }
+    }
+
+    pub fn schedule_tge(&mut self, tge_timestamp: i64, now: i64) -> Result<()> {
+        require!(self.tge_timestamp == 0, ErrorCode::TgeAlreadySet);
+        require!(
+            tge_timestamp > now && tge_timestamp > self.end_time,
+            ErrorCode::InvalidTgeTimestamp
+        );
+
+        self.tge_timestamp = tge_timestamp;
+        Ok(())
+    }
+
+    /// The timelock delay in force at `now`, a pending reduction included
+    /// once it has taken effect.
+    pub fn timelock_delay_at(&self, now: i64) -> i64 {
+        if self.pending_timelock_delay_at != 0 && now >= self.pending_timelock_delay_at {
+            self.pending_timelock_delay
+        } else {
+            self.timelock_delay
+        }
+    }
+
+    /// Sets the timelock delay. A longer delay applies at once. A shorter one
+    /// only applies once the current delay has run out from `now`, so actions
+    /// announced under the current delay still get all of it. Returns when the
+    /// new delay takes effect.
+    pub fn change_timelock_delay(&mut self, delay: i64, now: i64) -> Result<i64> {
+        require!(
+            (MIN_TIMELOCK_DELAY..=MAX_TIMELOCK_DELAY).contains(&delay),
+            ErrorCode::InvalidTimelockDelay
+        );
+
+        let current = self.timelock_delay_at(now);
+        self.timelock_delay = current;
+        self.pending_timelock_delay = 0;
+        self.pending_timelock_delay_at = 0;
+        if delay >= current {
+            self.timelock_delay = delay;
+            return Ok(now);
+        }
+
+        let effective_at = now
+            .checked_add(current)
+            .ok_or(ErrorCode::CalculationError)?;
+        self.pending_timelock_delay = delay;
+        self.pending_timelock_delay_at = effective_at;
+        Ok(effective_at)
+    }
+
+    pub fn postpone_tge(&mut self, tge_timestamp: i64, now: i64) -> Result<()> {
+        let current = self.claim_start()?;
+        require!(now < current, ErrorCode::TgeAlreadyReached);
+        require!(
+            self.tge_postponements < MAX_TGE_POSTPONEMENTS,
+            ErrorCode::TgePostponementLimit
+        );
+        require!(
+            tge_timestamp > current
+                && tge_timestamp <= current.saturating_add(MAX_TGE_POSTPONEMENT),
+            ErrorCode::InvalidTgeTimestamp
+        );
+
+        self.tge_timestamp = tge_timestamp;
+        self.tge_postponements += 1;
+        Ok(())
+    }
+}
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq,
+)]
+pub enum SalePhase {
+    #[default]
+    Pending,
+    Live,
+    Ended,
+    Finalized,
+    Claiming,
+    Refunding,
+    Closed,
+}
+
+/// Destinations for raise proceeds, in the order they are stored on `Presale`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProceedsDestination {
+    Liquidity,
+    Treasury,
+    Development,
+    Marketing,
+}
+
+impl ProceedsDestination {
+    pub const ALL: [ProceedsDestination; PROCEEDS_LEGS] = [
+        ProceedsDestination::Liquidity,
+        ProceedsDestination::Treasury,
+        ProceedsDestination::Development,
+        ProceedsDestination::Marketing,
+    ];
+}
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq,
+)]
+pub struct ProceedsLeg {
+    pub wallet: Pubkey,
+    pub bps: u16,
+}
+
+impl SalePhase {
+    pub const ALL: [SalePhase; 7] = [
+        SalePhase::Pending,
+        SalePhase::Live,
+        SalePhase::Ended,
+        SalePhase::Finalized,
+        SalePhase::Claiming,
+        SalePhase::Refunding,
+        SalePhase::Closed,
+    ];
+
+    pub fn can_transition_to(self, next: SalePhase) -> bool {
+        use SalePhase::*;
+        matches!(
+            (self, next),
+            (Pending, Live)
+                | (Live, Ended)
+                | (Ended, Finalized)
+                | (Finalized, Claiming)
+                | (Pending | Live | Ended, Refunding)
+                | (Claiming | Refunding, Closed)
+        )
+    }
+}
+
+/// Protocol-wide launchpad settings at `[b"protocol_config"]`.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct ProtocolConfig {
+    pub authority: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub listing_fee: u64,
+    pub platform_fee_bps: u16,
+    pub bump: u8,
+}
+
+impl ProtocolConfig {
+    pub fn set_fees(
+        &mut self,
+        fee_recipient: Pubkey,
+        listing_fee: u64,
+        platform_fee_bps: u16,
+    ) -> Result<()> {
+        require!(
+            fee_recipient != Pubkey::default(),
+            ErrorCode::InvalidFeeRecipient
+        );
+        require!(
+            platform_fee_bps <= MAX_PLATFORM_FEE_BPS,
+            ErrorCode::InvalidPlatformFee
+        );
+
+        self.fee_recipient = fee_recipient;
+        self.listing_fee = listing_fee;
+        self.platform_fee_bps = platform_fee_bps;
+        Ok(())
+    }
+}
+
+/// Escrow created by `finalize_presale` for the liquidity allocation. SOL is
+/// held on this account, NLOV and LP tokens in token accounts it owns.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct LiquidityLock {
+    pub presale: Pubkey,
+    pub beneficiary: Pubkey,
+    pub sol_amount: u64,
+    pub nlov_amount: u64,
+    pub lp_mint: Pubkey,
+    pub lp_amount: u64,
+    pub locked_at: i64,
+    pub unlock_at: i64,
+    pub released: bool,
+    pub bump: u8,
+    /// Whether the NLOV came from the genesis Liquidity vault, or from a
+    /// `genesis_funded` sale. Only then does its release count against the
+    /// mint's emission ceiling.
+    pub genesis_funded: bool,
+}
+
+impl LiquidityLock {
+    pub fn record_lp_deposit(&mut self, lp_mint: Pubkey, amount: u64) -> Result<()> {
+        require!(!self.released, ErrorCode::LiquidityAlreadyReleased);
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        if self.lp_mint == Pubkey::default() {
+            self.lp_mint = lp_mint;
+        }
+        require!(self.lp_mint == lp_mint, ErrorCode::LpMintMismatch);
+
+        self.lp_amount = self
+            .lp_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+        Ok(())
+    }
+
+    /// Takes the locked SOL off the lock for emergency refunds and returns how
+    /// much it was. Nothing is left to take once the lock has been released.
+    pub fn reclaim_sol(&mut self) -> u64 {
+        if self.released {
+            return 0;
+        }
+        std::mem::take(&mut self.sol_amount)
+    }
+
+    pub fn release(&mut self, now: i64) -> Result<()> {
+        require!(!self.released, ErrorCode::LiquidityAlreadyReleased);
+        require!(now >= self.unlock_at, ErrorCode::LiquidityStillLocked);
+
+        self.released = true;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(Default, InitSpace)]
+pub struct UserInfo {
+    pub version: u8,
+    pub user: Pubkey,
+    /// NLOV allocated to the user, bonus included, less anything refunded or
+    /// transferred away.
+    pub nlov_allocated: u64,
+    pub amount_claimed: u64,
+    /// Lamports paid for `nlov_allocated`: what the user paid through
+    /// `contribute`, plus or minus the share that moved with allocation
+    /// transfers.
+    pub lamports_paid: u64,
+    /// Lamports returned to the user by `claim_refund`.
+    pub lamports_refunded: u64,
+    /// Part of `nlov_allocated` granted as a bonus rather than bought.
+    pub bonus_allocated: u64,
+    pub first_contribution_at: i64,
+    pub last_contribution_at: i64,
+    pub contribution_count: u32,
+}
+
+impl UserInfo {
+    /// Allocation not yet claimed.
+    pub fn unclaimed(&self) -> Result<u64> {
+        self.nlov_allocated
+            .checked_sub(self.amount_claimed)
+            .ok_or(ErrorCode::CalculationError.into())
+    }
+
+    /// Allocation unlocked by `now` under `unlock` from `claim_start`, and
+    /// not yet claimed.
+    pub fn claimable(&self, unlock: &UnlockSchedule, claim_start: i64, now: i64) -> Result<u64> {
+        Ok(unlock
+            .vested_amount(self.nlov_allocated, claim_start, now)?
+            .saturating_sub(self.amount_claimed))
+    }
+
+    /// Records a purchase of `nlov_amount` for `lamports`, plus `bonus` on top.
+    pub fn record_contribution(
+        &mut self,
+        lamports: u64,
+        nlov_amount: u64,
+        bonus: u64,
+        now: i64,
+    ) -> Result<()> {
+        let allocated = nlov_amount
+            .checked_add(bonus)
+            .ok_or(ErrorCode::CalculationError)?;
+        self.nlov_allocated = self
+            .nlov_allocated
+            .checked_add(allocated)
+            .ok_or(ErrorCode::CalculationError)?;
+        self.bonus_allocated = self
+            .bonus_allocated
+            .checked_add(bonus)
+            .ok_or(ErrorCode::CalculationError)?;
+        self.lamports_paid = self
+            .lamports_paid
+            .checked_add(lamports)
+            .ok_or(ErrorCode::CalculationError)?;
+        if self.contribution_count == 0 {
+            self.first_contribution_at = now;
+        }
+        self.last_contribution_at = now;
+        self.contribution_count = self
+            .contribution_count
+            .checked_add(1)
+            .ok_or(ErrorCode::CalculationError)?;
+        Ok(())
+    }
+
+    pub fn record_claim(&mut self, amount: u64) -> Result<()> {
+        require!(amount <= self.unclaimed()?, ErrorCode::NothingToClaim);
+        self.amount_claimed = self
+            .amount_claimed
+            .checked_add(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+        Ok(())
+    }
+
+    /// Drops the unclaimed allocation, returned to the user as `lamports`.
+    pub fn record_refund(&mut self, lamports: u64) -> Result<()> {
+        self.lamports_refunded = self
+            .lamports_refunded
+            .checked_add(lamports)
+            .ok_or(ErrorCode::CalculationError)?;
+        // The bonus is spread evenly over the allocation, so what is left
+        // keeps its claimed share of it.
+        self.bonus_allocated = self.share_of(self.bonus_allocated, self.amount_claimed)?;
+        self.nlov_allocated = self.amount_claimed;
+        Ok(())
+    }
+
+    /// The share of `lamports_paid` that paid for `amount` of the allocation.
+    pub fn lamports_paid_for(&self, amount: u64) -> Result<u64> {
+        self.share_of(self.lamports_paid, amount)
+    }
+
+    /// The part of `value`, spread evenly over `nlov_allocated`, that goes
+    /// with `amount` of the allocation.
+    fn share_of(&self, value: u64, amount: u64) -> Result<u64> {
+        if self.nlov_allocated == 0 {
+            return Ok(0);
+        }
+        let share = value as u128 * amount as u128 / self.nlov_allocated as u128;
+        u64::try_from(share).map_err(|_| ErrorCode::CalculationError.into())
+    }
+
+    /// Moves `amount` of this user's unclaimed allocation to `to`.
+    pub fn move_allocation(&mut self, to: &mut UserInfo, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            amount <= self.unclaimed()?,
+            ErrorCode::InsufficientAllocation
+        );
+
+        // A slice of the whole position moves, claimed part included, so the
+        // recipient vests it no faster than the sender would have. Rounding
+        // up keeps the claimed share from coming out short.
+        let claimed =
+            (self.amount_claimed as u128 * amount as u128).div_ceil(self.unclaimed()? as u128);
+        let claimed = u64::try_from(claimed).map_err(|_| ErrorCode::CalculationError)?;
+        let allocated = amount
+            .checked_add(claimed)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        // What was paid for the allocation moves with it, so it is refunded
+        // to whoever holds the allocation if the sale is cancelled.
+        let lamports = self.lamports_paid_for(allocated)?;
+        let bonus = self.share_of(self.bonus_allocated, allocated)?;
+        self.nlov_allocated -= allocated;
+        self.amount_claimed -= claimed;
+        self.lamports_paid -= lamports;
+        self.bonus_allocated -= bonus;
+        to.nlov_allocated = to
+            .nlov_allocated
+            .checked_add(allocated)
+            .ok_or(ErrorCode::CalculationError)?;
+        to.amount_claimed = to
+            .amount_claimed
+            .checked_add(claimed)
+            .ok_or(ErrorCode::CalculationError)?;
+        to.lamports_paid = to
+            .lamports_paid
+            .checked_add(lamports)
+            .ok_or(ErrorCode::CalculationError)?;
+        to.bonus_allocated = to
+            .bonus_allocated
+            .checked_add(bonus)
+            .ok_or(ErrorCode::CalculationError)?;
+        Ok(())
+    }
+}
+
+/// Common to every presale event. `sequence` goes up by one with each event
+/// of a sale, so an indexer can tell when it has missed one.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EventHeader {
+    pub presale: Pubkey,
+    pub sequence: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct ProtocolConfigUpdated {
+    pub authority: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub listing_fee: u64,
+    pub platform_fee_bps: u16,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct PresaleInitialized {
+    pub header: EventHeader,
+    pub creator: Pubkey,
+    pub token_mint: Pubkey,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub token_amount: u64,
+    pub token_price: u64,
+    pub listing_fee: u64,
+    pub platform_fee_bps: u16,
+}
+
+#[event]
+pub struct PlatformFeePaid {
+    pub header: EventHeader,
+    pub fee_recipient: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ContributionMade {
+    pub header: EventHeader,
+    pub user: Pubkey,
+    pub sol_amount: u64,
+    pub nlov_amount: u64,
+    pub total_contributed: u64,
+}
+
+#[event]
+pub struct TokensClaimed {
+    pub header: EventHeader,
+    pub user: Pubkey,
+    /// Wallet whose allocation was claimed. Differs from `user` when
+    /// transferable receipts were redeemed against it.
+    pub allocation: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct UnsoldTokensSwept {
+    pub header: EventHeader,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct TgeScheduled {
+    pub header: EventHeader,
+    pub tge_timestamp: i64,
+    pub previous_tge_timestamp: i64,
+    pub postponements: u8,
+}
+
+#[event]
+pub struct EmergencyDeclared {
+    pub header: EventHeader,
+    pub owner: Pubkey,
+    pub vault_balance: u64,
+    pub outstanding_allocation: u64,
+    /// Lamports taken back from the liquidity lock, included in `vault_balance`.
+    pub liquidity_returned: u64,
+    pub declared_at: i64,
+}
+
+#[event]
+pub struct RefundClaimed {
+    pub header: EventHeader,
+    pub user: Pubkey,
+    /// Wallet whose allocation was refunded. Differs from `user` when
+    /// transferable receipts were redeemed against it.
+    pub allocation: Pubkey,
+    pub sol_amount: u64,
+    pub nlov_amount: u64,
+}
+
+#[event]
+pub struct PhaseChanged {
+    pub header: EventHeader,
+    pub from: SalePhase,
+    pub to: SalePhase,
+}
+
+#[event]
+pub struct PresalePaused {
+    pub header: EventHeader,
+    pub actor: Pubkey,
+    pub reason: String,
+}
+
+#[event]
+pub struct PresaleUnpaused {
+    pub header: EventHeader,
+    pub actor: Pubkey,
+    pub reason: String,
+}
+
+#[event]
+pub struct PresaleFinalized {
+    pub header: EventHeader,
+    pub total_contributed: u64,
+    pub end_time: i64,
+}
+
+#[event]
+pub struct InvariantReport {
+    pub header: EventHeader,
+    pub phase: SalePhase,
+    pub vault_balance: u64,
+    pub required_vault_balance: u64,
+    pub token_balance: u64,
+    pub required_token_balance: u64,
+    pub total_contributed: u64,
+    pub total_claimed: u64,
+    pub total_withdrawn: u64,
+    pub unsold_supply: u64,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub header: EventHeader,
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+#[event]
+pub struct ProceedsConfigured {
+    pub header: EventHeader,
+    pub legs: [ProceedsLeg; PROCEEDS_LEGS],
+}
+
+#[event]
+pub struct ProceedsDistributed {
+    pub header: EventHeader,
+    pub destination: ProceedsDestination,
+    pub wallet: Pubkey,
+    pub bps: u16,
+    pub amount: u64,
+}
+
+#[event]
+pub struct LiquidityLockConfigured {
+    pub header: EventHeader,
+    pub sol_bps: u16,
+    pub nlov_amount: u64,
+    pub lock_duration: i64,
+}
+
+#[event]
+pub struct LiquidityLocked {
+    pub header: EventHeader,
+    pub lock: Pubkey,
+    pub beneficiary: Pubkey,
+    pub sol_amount: u64,
+    pub nlov_amount: u64,
+    pub unlock_at: i64,
+}
+
+#[event]
+pub struct LpTokensDeposited {
+    pub header: EventHeader,
+    pub lock: Pubkey,
+    pub lp_mint: Pubkey,
+    pub amount: u64,
+    pub lp_amount: u64,
+}
+
+#[event]
+pub struct LiquidityReleased {
+    pub header: EventHeader,
+    pub lock: Pubkey,
+    pub beneficiary: Pubkey,
+    pub sol_amount: u64,
+    pub nlov_amount: u64,
+    pub lp_amount: u64,
+}
+
+#[event]
+pub struct FundsWithdrawn {
+    pub header: EventHeader,
+    pub owner: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AllocationTransfersConfigured {
+    pub header: EventHeader,
+    pub enabled: bool,
+    pub transfer_agent: Pubkey,
+}
+
+#[event]
+pub struct AllocationTransferred {
+    pub header: EventHeader,
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub amount: u64,
+    pub transfer_agent: Option<Pubkey>,
+}
+
+#[event]
+pub struct ReceiptsEnabled {
+    pub header: EventHeader,
+    pub receipt_mint: Pubkey,
+    pub transferable: bool,
+}
+
+#[event]
+pub struct CouncilCreated {
+    pub header: EventHeader,
+    pub council: Pubkey,
+    pub members: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+#[event]
+pub struct ProposalCreated {
+    pub header: EventHeader,
+    pub proposal: Pubkey,
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub action: AdminAction,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct ProposalApproved {
+    pub header: EventHeader,
+    pub proposal: Pubkey,
+    pub member: Pubkey,
+    pub approvals: u8,
+}
+
+#[event]
+pub struct ApprovalRevoked {
+    pub header: EventHeader,
+    pub proposal: Pubkey,
+    pub member: Pubkey,
+    pub approvals: u8,
+}
+
+#[event]
+pub struct ProposalExecuted {
+    pub header: EventHeader,
+    pub proposal: Pubkey,
+    pub id: u64,
+    pub executor: Pubkey,
+    pub action: AdminAction,
+}
+
+#[event]
+pub struct OwnershipTransferred {
+    pub header: EventHeader,
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+#[event]
+pub struct TimelockDelayChanged {
+    pub header: EventHeader,
+    pub previous_delay: i64,
+    pub delay: i64,
+    /// When `delay` takes effect; later than now for a reduction.
+    pub effective_at: i64,
+}
+
+#[event]
+pub struct ActionQueued {
+    pub header: EventHeader,
+    pub queued_action: Pubkey,
+    pub id: u64,
+    pub action: AdminAction,
+    pub queued_by: Pubkey,
+    pub eta: i64,
+}
+
+#[event]
+pub struct QueuedActionExecuted {
+    pub header: EventHeader,
+    pub queued_action: Pubkey,
+    pub id: u64,
+    pub executor: Pubkey,
+    pub action: AdminAction,
+}
+
+#[event]
+pub struct QueuedActionCancelled {
+    pub header: EventHeader,
+    pub queued_action: Pubkey,
+    pub id: u64,
+    pub cancelled_by: Pubkey,
+}
+
+#[event]
+pub struct WithdrawalLimitConfigured {
+    pub header: EventHeader,
+    pub previous_limit: WithdrawalLimit,
+    pub limit: WithdrawalLimit,
+}
+
+#[event]
+pub struct VestingCreated {
+    pub schedule: Pubkey,
+    pub id: u64,
+    pub beneficiary: Pubkey,
+    pub category: VestingCategory,
+    pub total_amount: u64,
+    pub revocable: bool,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct VestingReleased {
+    pub schedule: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub released_amount: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct GrantRevoked {
+    pub schedule: Pubkey,
+    pub beneficiary: Pubkey,
+    pub category: VestingCategory,
+    /// Tokens the beneficiary keeps, released or not.
+    pub vested_amount: u64,
+    /// Unvested tokens sent back to the category vault.
+    pub returned_amount: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct SaleFunded {
+    pub header: EventHeader,
+    pub category: VestingCategory,
+    /// Presale token account or liquidity lock token account.
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct MilestoneVestingCreated {
+    pub schedule: Pubkey,
+    pub id: u64,
+    pub beneficiary: Pubkey,
+    pub category: VestingCategory,
+    pub total_amount: u64,
+    pub attester: Pubkey,
+    pub milestones: Vec<Milestone>,
+    pub revocable: bool,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct MilestoneAchieved {
+    pub schedule: Pubkey,
+    pub index: u8,
+    pub bps: u16,
+    pub attested_by: Pubkey,
+    pub by_fallback: bool,
+    pub evidence: [u8; 32],
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct DistributorCreated {
+    pub distributor: Pubkey,
+    pub id: u64,
+    pub category: VestingCategory,
+    pub root: [u8; 32],
+    pub total_amount: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct DistributionClaimed {
+    pub distributor: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    /// Tokens the beneficiary has claimed from the distributor so far.
+    pub claimed_amount: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct EmissionScheduleUpdated {
+    pub emission_schedule: Pubkey,
+    pub mint: Pubkey,
+    pub tge_timestamp: i64,
+    /// All ceilings after the update, in base units.
+    pub ceilings: Vec<u64>,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct TokenBootstrapped {
+    pub mint: Pubkey,
+    /// Base units minted, the whole supply.
+    pub supply: u64,
+    /// Category vaults, in `VestingCategory::ALL` order.
+    pub vaults: Vec<Pubkey>,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct TokenMetadataUpdated {
+    pub mint: Pubkey,
+    pub field: MetadataField,
+    pub value: String,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct MetadataAuthorityChanged {
+    pub mint: Pubkey,
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[event]
+pub struct LockedStakeChanged {
+    pub stake_position: Pubkey,
+    pub vesting_schedule: Pubkey,
+    pub beneficiary: Pubkey,
+    pub staked_amount: u64,
+    /// `StakePosition::voting_weight` right after the change.
+    pub voting_weight: u64,
+    pub slot: u64,
+    pub unix_timestamp: i64,
 }
 
 #[error_code]
@@ -380,4 +5669,170 @@ pub enum ErrorCode {
     InsufficientFunds,
     #[msg("Calculation error")]
     CalculationError,
+    #[msg("Instruction is not allowed in the current sale phase")]
+    InvalidPhase,
+    #[msg("Illegal sale phase transition")]
+    InvalidPhaseTransition,
+    #[msg("Nothing to refund")]
+    NothingToRefund,
+    #[msg("TGE has not been scheduled")]
+    TgeNotSet,
+    #[msg("TGE has already been scheduled")]
+    TgeAlreadySet,
+    #[msg("TGE has already been reached")]
+    TgeAlreadyReached,
+    #[msg("Invalid TGE timestamp")]
+    InvalidTgeTimestamp,
+    #[msg("TGE cannot be postponed again")]
+    TgePostponementLimit,
+    #[msg("An emergency has been declared for this presale")]
+    EmergencyDeclared,
+    #[msg("Proceeds split has not been configured")]
+    ProceedsNotConfigured,
+    #[msg("Proceeds split has already been configured")]
+    ProceedsAlreadyConfigured,
+    #[msg("Proceeds shares must add up to 10000 basis points")]
+    InvalidProceedsSplit,
+    #[msg("Invalid proceeds wallet")]
+    InvalidProceedsWallet,
+    #[msg("Liquidity lock has not been configured")]
+    LiquidityLockNotConfigured,
+    #[msg("Liquidity lock has already been configured")]
+    LiquidityLockAlreadyConfigured,
+    #[msg("Invalid liquidity lock parameters")]
+    InvalidLiquidityLock,
+    #[msg("Liquidity is still locked")]
+    LiquidityStillLocked,
+    #[msg("Liquidity has already been released")]
+    LiquidityAlreadyReleased,
+    #[msg("LP token mint does not match the lock")]
+    LpMintMismatch,
+    #[msg("LP token accounts are required to release LP tokens")]
+    MissingLpTokenAccount,
+    #[msg("Account data does not match any known layout")]
+    InvalidAccountLayout,
+    #[msg("Account is already on the current layout")]
+    AccountAlreadyMigrated,
+    #[msg("Unsupported account version")]
+    UnsupportedAccountVersion,
+    #[msg("Account must be migrated to the current layout")]
+    AccountNeedsMigration,
+    #[msg("Invalid protocol fee recipient")]
+    InvalidFeeRecipient,
+    #[msg("Platform fee exceeds the protocol maximum")]
+    InvalidPlatformFee,
+    #[msg("Token account mint does not match the presale token")]
+    InvalidTokenMint,
+    #[msg("Token account is not owned by the expected authority")]
+    InvalidTokenAccountOwner,
+    #[msg("Token account is not the presale token account")]
+    InvalidPresaleTokenAccount,
+    #[msg("User info does not belong to the signer")]
+    UserInfoMismatch,
+    #[msg("Presale accounting does not reconcile")]
+    InvariantViolation,
+    #[msg("Pause reason is too long")]
+    PauseReasonTooLong,
+    #[msg("Allocation transfers are disabled for this presale")]
+    AllocationTransfersDisabled,
+    #[msg("Allocation transfers must be co-signed by the transfer agent")]
+    TransferAgentRequired,
+    #[msg("Signer is not the presale's transfer agent")]
+    InvalidTransferAgent,
+    #[msg("Allocation cannot be transferred to the sender")]
+    InvalidTransferRecipient,
+    #[msg("Amount exceeds the unclaimed allocation")]
+    InsufficientAllocation,
+    #[msg("Allocation receipts are already enabled")]
+    ReceiptsAlreadyEnabled,
+    #[msg("Receipt mint does not match the presale")]
+    InvalidReceiptMint,
+    #[msg("Receipt mint, token account and Token-2022 program are required")]
+    MissingReceiptAccounts,
+    #[msg("Council members or threshold are invalid")]
+    InvalidCouncil,
+    #[msg("Presale already has a council")]
+    CouncilAlreadyExists,
+    #[msg("Signer is not a council member")]
+    NotCouncilMember,
+    #[msg("Presale is governed by a council and needs an approved proposal")]
+    ProposalRequired,
+    #[msg("Proposal does not match this presale or action")]
+    ProposalMismatch,
+    #[msg("Proposal has expired")]
+    ProposalExpired,
+    #[msg("Proposal has already been executed")]
+    ProposalAlreadyExecuted,
+    #[msg("Proposal has not reached the approval threshold")]
+    ProposalNotApproved,
+    #[msg("Member has already approved this proposal")]
+    AlreadyApproved,
+    #[msg("Member has not approved this proposal")]
+    NotApproved,
+    #[msg("Proposal lifetime is out of range")]
+    InvalidProposalTtl,
+    #[msg("Council account is required")]
+    MissingCouncil,
+    #[msg("New owner cannot be the default key")]
+    InvalidNewOwner,
+    #[msg("Timelock delay is out of range")]
+    InvalidTimelockDelay,
+    #[msg("Presale has no timelock")]
+    TimelockDisabled,
+    #[msg("Presale is timelocked and needs a queued action")]
+    QueuedActionRequired,
+    #[msg("Queued action does not match this presale or action")]
+    QueuedActionMismatch,
+    #[msg("Queued action is not ready yet")]
+    TimelockNotExpired,
+    #[msg("Queued action has already been executed")]
+    QueuedActionAlreadyExecuted,
+    #[msg("Queued action has been cancelled")]
+    QueuedActionCancelled,
+    #[msg("Withdrawal limit is invalid")]
+    InvalidWithdrawalLimit,
+    #[msg("Withdrawal exceeds the rolling withdrawal limit")]
+    WithdrawalLimitExceeded,
+    #[msg("Withdrawal limit can only be loosened by the council")]
+    WithdrawalLimitLocked,
+    #[msg("Action requires a council")]
+    CouncilRequired,
+    #[msg("Vesting schedule does not follow its category terms")]
+    InvalidVestingSchedule,
+    #[msg("Vesting category allocation exceeded")]
+    VestingAllocationExceeded,
+    #[msg("Milestones are invalid")]
+    InvalidMilestones,
+    #[msg("Milestone has already been achieved")]
+    MilestoneAlreadyAchieved,
+    #[msg("Milestone needs the attester until its fallback time")]
+    MilestoneNotReached,
+    #[msg("Vesting grant is not revocable")]
+    GrantNotRevocable,
+    #[msg("Vesting grant has already been revoked")]
+    GrantAlreadyRevoked,
+    #[msg("Merkle proof is invalid")]
+    InvalidProof,
+    #[msg("Emission schedule is invalid")]
+    InvalidEmissionSchedule,
+    #[msg("Release would exceed the emission ceiling")]
+    EmissionCeilingExceeded,
+    #[msg("Stake exceeds the locked balance")]
+    StakeExceedsLocked,
+    #[msg("Allocations are still outstanding")]
+    AllocationsOutstanding,
+    #[msg("Funding vault belongs to another category")]
+    CategoryVaultMismatch,
+    #[msg("Genesis allocation does not match the total supply")]
+    GenesisSupplyMismatch,
+    #[msg("Token price must be above zero")]
+    InvalidTokenPrice,
+    #[msg("Liquidity lock account is required once the sale is finalized")]
+    MissingLiquidityLock,
+    #[msg("Category vault cannot fund this destination")]
+    InvalidFundingCategory,
+    #[msg("Emission schedule is required for genesis tokens")]
+    MissingEmissionSchedule,
+    #[msg("Not enough receipts to redeem this allocation")]
+    InsufficientReceipts,
 }
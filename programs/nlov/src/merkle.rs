@@ -0,0 +1,38 @@
+//! Merkle tree hashing shared by the on-chain distributor and the off-chain
+//! tree builder.
+//!
+//! Leaves and inner nodes are keccak-256 hashes with distinct prefixes, so an
+//! inner node can never pass as a leaf. Pairs are hashed in sorted order, so
+//! a proof is just the list of siblings from leaf to root.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::keccak;
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// Leaf for `beneficiary` vesting `total_amount` under distributor
+/// `schedule_id`.
+pub fn leaf(beneficiary: &Pubkey, total_amount: u64, schedule_id: u64) -> [u8; 32] {
+    keccak::hashv(&[
+        LEAF_PREFIX,
+        beneficiary.as_ref(),
+        &total_amount.to_le_bytes(),
+        &schedule_id.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Parent of two nodes, independent of their order.
+pub fn parent(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    keccak::hashv(&[NODE_PREFIX, first, second]).to_bytes()
+}
+
+/// Whether `proof` leads from `leaf` to `root`.
+pub fn verify(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    proof
+        .iter()
+        .fold(leaf, |node, sibling| parent(&node, sibling))
+        == *root
+}
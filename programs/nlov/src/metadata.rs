@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::spl_token_metadata_interface::state::Field;
+
+/// Token-2022 metadata `bootstrap_token` gives the NLOV mint.
+pub const NLOV_NAME: &str = "NEUROLOV";
+pub const NLOV_SYMBOL: &str = "NLOV";
+
+/// The metadata role of a mint, at `[b"metadata_authority", mint]`. The PDA
+/// is the update authority of the mint's metadata and metadata pointer, and
+/// only signs for `authority`, which can be handed to governance.
+#[account]
+#[derive(InitSpace)]
+pub struct MetadataAuthority {
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub bump: u8,
+}
+
+impl MetadataAuthority {
+    pub fn signer_seeds(&self) -> [&[u8]; 3] {
+        [
+            b"metadata_authority",
+            self.mint.as_ref(),
+            std::slice::from_ref(&self.bump),
+        ]
+    }
+}
+
+/// A metadata field, mirroring `spl_token_metadata_interface::state::Field`
+/// for the IDL.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum MetadataField {
+    Name,
+    Symbol,
+    Uri,
+    /// An additional field under this key.
+    Key(String),
+}
+
+impl From<MetadataField> for Field {
+    fn from(field: MetadataField) -> Self {
+        match field {
+            MetadataField::Name => Field::Name,
+            MetadataField::Symbol => Field::Symbol,
+            MetadataField::Uri => Field::Uri,
+            MetadataField::Key(key) => Field::Key(key),
+        }
+    }
+}
+
+/// An additional metadata field set at bootstrap.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MetadataEntry {
+    pub key: String,
+    pub value: String,
+}
@@ -0,0 +1,181 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+use crate::emission::NLOV_DECIMALS;
+use crate::{
+    ErrorCode, Presale, SalePhase, UserInfo, NLOV_PER_LAMPORT, PRESALE_VERSION, USER_INFO_VERSION,
+};
+
+/// Bytes allocated for a v1 `Presale`, after the discriminator.
+pub const PRESALE_V1_SPACE: usize = 256;
+
+/// Bytes allocated for a v1 `UserInfo`, after the discriminator.
+pub const USER_INFO_V1_SPACE: usize = 32 + 8 + 8;
+
+/// `token_price` of the v1 rate, in lamports per whole NLOV.
+pub const V1_TOKEN_PRICE: u64 = 10u64.pow(NLOV_DECIMALS as u32) / NLOV_PER_LAMPORT;
+
+// v1 accounts carry no version byte, so they are told apart by their size.
+const _: () = assert!(Presale::INIT_SPACE != PRESALE_V1_SPACE);
+const _: () = assert!(UserInfo::INIT_SPACE != USER_INFO_V1_SPACE);
+
+/// `Presale` as laid out before accounts were versioned.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct PresaleV1 {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub public_sale_end_time: i64,
+    pub token_mint: Pubkey,
+    pub presale_token_account: Pubkey,
+    pub presale_supply: u64,
+    pub token_price: u64,
+    pub total_contributed: u64,
+    pub is_active: bool,
+    pub is_paused: bool,
+    pub owner: Pubkey,
+}
+
+/// `UserInfo` as laid out before accounts were versioned.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct UserInfoV1 {
+    pub user: Pubkey,
+    pub amount_contributed: u64,
+    pub amount_claimed: u64,
+}
+
+/// v1 opened claims once `public_sale_end_time` had passed, which is what
+/// `tge_timestamp` now gates. A sale still marked active becomes `Live`;
+/// `finalize_presale` was the only thing that cleared `is_active`, so an
+/// inactive one is `Finalized`. v1 did not track claims in aggregate, so
+/// `total_claimed` starts at zero. v1 sold NLOV at `NLOV_PER_LAMPORT` whatever
+/// its stored `token_price`, so the sale is repriced at that rate.
+impl From<PresaleV1> for Presale {
+    fn from(v1: PresaleV1) -> Self {
+        Presale {
+            version: PRESALE_VERSION,
+            start_time: v1.start_time,
+            end_time: v1.end_time,
+            tge_timestamp: v1.public_sale_end_time,
+            token_mint: v1.token_mint,
+            presale_token_account: v1.presale_token_account,
+            presale_supply: v1.presale_supply,
+            token_price: V1_TOKEN_PRICE,
+            token_decimals: NLOV_DECIMALS,
+            total_contributed: v1.total_contributed,
+            phase: if v1.is_active {
+                SalePhase::Live
+            } else {
+                SalePhase::Finalized
+            },
+            is_paused: v1.is_paused,
+            owner: v1.owner,
+            ..Presale::default()
+        }
+    }
+}
+
+impl From<UserInfoV1> for UserInfo {
+    fn from(v1: UserInfoV1) -> Self {
+        UserInfo {
+            version: USER_INFO_VERSION,
+            user: v1.user,
+            nlov_allocated: v1.amount_contributed,
+            amount_claimed: v1.amount_claimed,
+            ..UserInfo::default()
+        }
+    }
+}
+
+/// Returns the on-disk version of an account body, where `v1_space` is the
+/// size v1 accounts were allocated with.
+fn stored_version(data: &[u8], discriminator: [u8; 8], v1_space: usize) -> Result<u8> {
+    require!(
+        data.len() > 8 && data[..8] == discriminator,
+        ErrorCode::InvalidAccountLayout
+    );
+    if data.len() == 8 + v1_space {
+        return Ok(1);
+    }
+    Ok(data[8])
+}
+
+/// Decodes a `Presale` account of any supported version into the current layout.
+pub fn upgrade_presale(data: &[u8]) -> Result<(u8, Presale)> {
+    match stored_version(data, Presale::DISCRIMINATOR, PRESALE_V1_SPACE)? {
+        1 => {
+            let v1 = PresaleV1::deserialize(&mut &data[8..])
+                .map_err(|_| ErrorCode::InvalidAccountLayout)?;
+            Ok((1, v1.into()))
+        }
+        version if (2..PRESALE_VERSION).contains(&version) => {
+            let mut presale: Presale = upgrade_appended(data, Presale::INIT_SPACE)?;
+            presale.version = PRESALE_VERSION;
+            Ok((version, presale))
+        }
+        PRESALE_VERSION => err!(ErrorCode::AccountAlreadyMigrated),
+        _ => err!(ErrorCode::UnsupportedAccountVersion),
+    }
+}
+
+/// Decodes a `UserInfo` account of any supported version into the current layout.
+pub fn upgrade_user_info(data: &[u8]) -> Result<(u8, UserInfo)> {
+    match stored_version(data, UserInfo::DISCRIMINATOR, USER_INFO_V1_SPACE)? {
+        1 => {
+            let v1 = UserInfoV1::deserialize(&mut &data[8..])
+                .map_err(|_| ErrorCode::InvalidAccountLayout)?;
+            Ok((1, seed_user_ledger(v1.into())))
+        }
+        version if (2..USER_INFO_VERSION).contains(&version) => {
+            let mut user_info: UserInfo = upgrade_appended(data, UserInfo::INIT_SPACE)?;
+            user_info.version = USER_INFO_VERSION;
+            Ok((version, seed_user_ledger(user_info)))
+        }
+        USER_INFO_VERSION => err!(ErrorCode::AccountAlreadyMigrated),
+        _ => err!(ErrorCode::UnsupportedAccountVersion),
+    }
+}
+
+/// Before v2 only the allocation was stored. At the fixed rate it gives the
+/// lamports paid for what the user still holds; when and how often they
+/// contributed is unknown and stays zero.
+fn seed_user_ledger(mut user_info: UserInfo) -> UserInfo {
+    user_info.lamports_paid = user_info.nlov_allocated / NLOV_PER_LAMPORT;
+    user_info
+}
+
+/// Since v2, layouts only ever grow by appending fields that start out
+/// zeroed, so an older account decodes once padded to the current size.
+fn upgrade_appended<T: AccountDeserialize>(data: &[u8], space: usize) -> Result<T> {
+    require!(data.len() < 8 + space, ErrorCode::InvalidAccountLayout);
+    let mut padded = data.to_vec();
+    padded.resize(8 + space, 0);
+    T::try_deserialize(&mut &padded[..])
+}
+
+/// Resizes `account` to `space` bytes, topping up rent from `payer`, and
+/// writes `value` over it.
+pub fn rewrite_account<'info, T: AccountSerialize>(
+    account: &AccountInfo<'info>,
+    value: &T,
+    space: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let rent_due = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(account.lamports());
+    if rent_due > 0 {
+        let cpi_ctx = CpiContext::new(
+            system_program.clone(),
+            anchor_lang::system_program::Transfer {
+                from: payer.clone(),
+                to: account.clone(),
+            },
+        );
+        anchor_lang::system_program::transfer(cpi_ctx, rent_due)?;
+    }
+
+    account.realloc(space, true)?;
+    let mut data = account.try_borrow_mut_data()?;
+    value.try_serialize(&mut &mut data[..])
+}
@@ -0,0 +1,208 @@
+use anchor_lang::prelude::*;
+
+use crate::vesting::{UnlockSchedule, VestingCategory};
+use crate::{ErrorCode, BPS_DENOMINATOR};
+
+/// Most tranches a milestone schedule may have.
+pub const MAX_MILESTONES: usize = 8;
+
+/// A tranche as passed to `create_milestone_vesting`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MilestoneTerms {
+    /// Share of the schedule the tranche unlocks, in basis points.
+    pub bps: u16,
+    /// Seconds after the mint's TGE from which the tranche may be marked
+    /// achieved without the attester.
+    pub fallback_after: i64,
+}
+
+/// One tranche of a `MilestoneSchedule`.
+#[derive(
+    AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq,
+)]
+pub struct Milestone {
+    pub bps: u16,
+    pub fallback_after: i64,
+    /// When the tranche was marked achieved, or 0 while pending.
+    pub achieved_at: i64,
+}
+
+impl Milestone {
+    pub fn is_achieved(&self) -> bool {
+        self.achieved_at != 0
+    }
+}
+
+/// Tokens released to one beneficiary as milestones are achieved, at
+/// `[b"milestone_vesting", mint, id]`. The id comes from the same
+/// `VestingLedger` counter as linear schedules.
+#[account]
+#[derive(InitSpace)]
+pub struct MilestoneSchedule {
+    pub mint: Pubkey,
+    pub id: u64,
+    pub beneficiary: Pubkey,
+    pub category: VestingCategory,
+    pub escrow: Pubkey,
+    /// Wallet that may mark tranches achieved before their fallback.
+    pub attester: Pubkey,
+    pub total_amount: u64,
+    pub released_amount: u64,
+    #[max_len(MAX_MILESTONES)]
+    pub milestones: Vec<Milestone>,
+    pub bump: u8,
+    /// Whether the protocol authority may revoke the unvested part.
+    pub revocable: bool,
+    /// When vesting was frozen by `revoke_milestone_grant`, or 0.
+    pub revoked_at: i64,
+}
+
+impl MilestoneSchedule {
+    /// Checks `terms` against the category and turns them into pending
+    /// tranches. The tranches must add up to the whole schedule, and each
+    /// must fall back within the category's vesting period.
+    pub fn milestones_from(
+        category: VestingCategory,
+        terms: &[MilestoneTerms],
+    ) -> Result<Vec<Milestone>> {
+        let category_terms = category.terms();
+        require!(category_terms.milestones, ErrorCode::InvalidVestingSchedule);
+        require!(
+            !terms.is_empty() && terms.len() <= MAX_MILESTONES,
+            ErrorCode::InvalidMilestones
+        );
+        let latest_fallback = category_terms.vesting_period();
+        let mut total_bps = 0u32;
+        for milestone in terms {
+            require!(
+                milestone.bps > 0
+                    && milestone.fallback_after > 0
+                    && milestone.fallback_after <= latest_fallback,
+                ErrorCode::InvalidMilestones
+            );
+            total_bps += milestone.bps as u32;
+        }
+        require!(
+            total_bps == BPS_DENOMINATOR as u32,
+            ErrorCode::InvalidMilestones
+        );
+
+        Ok(terms
+            .iter()
+            .map(|milestone| Milestone {
+                bps: milestone.bps,
+                fallback_after: milestone.fallback_after,
+                achieved_at: 0,
+            })
+            .collect())
+    }
+
+    /// Marks tranche `index` achieved at `now`, either by the attester or,
+    /// once its fallback after the TGE at `tge_timestamp` has passed, by
+    /// anyone. Returns whether the fallback was used.
+    pub fn achieve(
+        &mut self,
+        index: usize,
+        signer: &Pubkey,
+        tge_timestamp: i64,
+        now: i64,
+    ) -> Result<bool> {
+        require!(!self.is_revoked(), ErrorCode::GrantAlreadyRevoked);
+        let attester = self.attester;
+        let milestone = self
+            .milestones
+            .get_mut(index)
+            .ok_or(ErrorCode::InvalidMilestones)?;
+        require!(
+            !milestone.is_achieved(),
+            ErrorCode::MilestoneAlreadyAchieved
+        );
+        let by_fallback = *signer != attester;
+        require!(
+            !by_fallback || now >= tge_timestamp.saturating_add(milestone.fallback_after),
+            ErrorCode::MilestoneNotReached
+        );
+        milestone.achieved_at = now;
+        Ok(by_fallback)
+    }
+
+    /// Tokens vested by `now`: the achieved tranches, from the TGE at
+    /// `tge_timestamp` on. Vesting stops at `revoked_at` once the grant is
+    /// revoked.
+    pub fn vested_amount(&self, tge_timestamp: i64, now: i64) -> Result<u64> {
+        let now = if self.is_revoked() {
+            now.min(self.revoked_at)
+        } else {
+            now
+        };
+        self.unlock()
+            .vested_amount(self.total_amount, tge_timestamp, now)
+    }
+
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at != 0
+    }
+
+    /// Freezes vesting at `now` and returns the tokens that will now never
+    /// vest: the pending tranches, or everything before TGE.
+    pub fn revoke(&mut self, tge_timestamp: i64, now: i64) -> Result<u64> {
+        require!(self.revocable, ErrorCode::GrantNotRevocable);
+        require!(!self.is_revoked(), ErrorCode::GrantAlreadyRevoked);
+        let vested = self.vested_amount(tge_timestamp, now)?;
+        self.revoked_at = now;
+        self.total_amount
+            .checked_sub(vested)
+            .ok_or(ErrorCode::CalculationError.into())
+    }
+
+    /// The achieved tranches unlock as the TGE share, and the pending ones
+    /// sit behind a cliff that never ends.
+    pub fn unlock(&self) -> UnlockSchedule<'static> {
+        let achieved_bps = self
+            .milestones
+            .iter()
+            .filter(|milestone| milestone.is_achieved())
+            .map(|milestone| milestone.bps)
+            .sum();
+        UnlockSchedule {
+            tge_bps: achieved_bps,
+            cliff_duration: i64::MAX,
+            linear_duration: 0,
+            step_period: 0,
+            step_bps: &[],
+        }
+    }
+
+    /// Vested tokens not yet released.
+    pub fn releasable(&self, tge_timestamp: i64, now: i64) -> Result<u64> {
+        self.vested_amount(tge_timestamp, now)?
+            .checked_sub(self.released_amount)
+            .ok_or(ErrorCode::CalculationError.into())
+    }
+
+    /// PDA signer seeds; `id` is `self.id.to_le_bytes()`.
+    pub fn signer_seeds<'a>(&'a self, id: &'a [u8; 8]) -> [&'a [u8]; 4] {
+        [
+            b"milestone_vesting",
+            self.mint.as_ref(),
+            id,
+            std::slice::from_ref(&self.bump),
+        ]
+    }
+}
+
+/// Record of a tranche being marked achieved, at
+/// `[b"milestone_attestation", schedule, index]`.
+#[account]
+#[derive(InitSpace)]
+pub struct MilestoneAttestation {
+    pub schedule: Pubkey,
+    pub index: u8,
+    /// Attester, or whoever invoked the fallback.
+    pub attested_by: Pubkey,
+    pub by_fallback: bool,
+    /// Hash of the off-chain evidence, or zeros.
+    pub evidence: [u8; 32],
+    pub attested_at: i64,
+    pub bump: u8,
+}
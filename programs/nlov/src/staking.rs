@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+
+use crate::vesting::VestingSchedule;
+
+/// Locked tokens of a vesting schedule staked for governance, at
+/// `[b"stake_position", vesting_schedule]`. The tokens never leave the
+/// schedule's escrow; only the amount counted as staked is recorded here.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct StakePosition {
+    pub vesting_schedule: Pubkey,
+    pub beneficiary: Pubkey,
+    /// Tokens the beneficiary has staked, which may exceed what is still
+    /// locked once vesting catches up with it.
+    pub staked_amount: u64,
+    pub bump: u8,
+}
+
+impl StakePosition {
+    /// Voting weight at `now`: the staked tokens that are still locked.
+    /// Vested tokens never count, so the weight shrinks as `schedule` vests
+    /// from the TGE at `tge_timestamp` and nothing `release` can pay out is
+    /// counted twice.
+    pub fn voting_weight(
+        &self,
+        schedule: &VestingSchedule,
+        tge_timestamp: i64,
+        now: i64,
+    ) -> Result<u64> {
+        Ok(self
+            .staked_amount
+            .min(schedule.locked_amount(tge_timestamp, now)?))
+    }
+}
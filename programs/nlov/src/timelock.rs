@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+
+use crate::council::AdminAction;
+use crate::ErrorCode;
+
+/// Shortest delay a sale may put between queueing and running an action (1 day).
+pub const MIN_TIMELOCK_DELAY: i64 = 24 * 60 * 60;
+
+/// Longest delay a sale may put between queueing and running an action (30 days).
+pub const MAX_TIMELOCK_DELAY: i64 = 30 * 24 * 60 * 60;
+
+/// A privileged action waiting out the sale's timelock, at
+/// `[b"queued_action", presale, id]`.
+#[account]
+#[derive(InitSpace)]
+pub struct QueuedAction {
+    pub presale: Pubkey,
+    pub id: u64,
+    pub action: AdminAction,
+    pub queued_by: Pubkey,
+    /// Council in charge when the action was queued. The action lapses if the
+    /// sale comes under a different one.
+    pub council: Pubkey,
+    pub eta: i64,
+    pub executed: bool,
+    pub cancelled: bool,
+    pub bump: u8,
+}
+
+impl QueuedAction {
+    fn require_pending(&self) -> Result<()> {
+        require!(!self.cancelled, ErrorCode::QueuedActionCancelled);
+        require!(!self.executed, ErrorCode::QueuedActionAlreadyExecuted);
+        Ok(())
+    }
+
+    /// Spends the queued action on `action`, which must be exactly the one
+    /// queued, once its ETA has passed.
+    pub fn execute(&mut self, action: &AdminAction, council: Pubkey, now: i64) -> Result<()> {
+        self.require_pending()?;
+        require!(
+            self.action == *action && self.council == council,
+            ErrorCode::QueuedActionMismatch
+        );
+        require!(now >= self.eta, ErrorCode::TimelockNotExpired);
+        self.executed = true;
+        Ok(())
+    }
+
+    pub fn cancel(&mut self) -> Result<()> {
+        self.require_pending()?;
+        self.cancelled = true;
+        Ok(())
+    }
+}
@@ -0,0 +1,385 @@
+use anchor_lang::prelude::*;
+
+use crate::{ErrorCode, BPS_DENOMINATOR};
+
+/// Length of a vesting month (30 days).
+pub const VESTING_MONTH: i64 = 30 * 24 * 60 * 60;
+
+/// Number of `VestingCategory` variants.
+pub const VESTING_CATEGORIES: usize = 14;
+
+/// Most steps a step schedule may have, one per month of the longest
+/// category.
+pub const MAX_UNLOCK_STEPS: usize = 48;
+
+/// Allocation categories of the NLOV supply.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VestingCategory {
+    SeedSale,
+    PrivateSale,
+    Presale,
+    PublicSale,
+    Team,
+    Treasury,
+    Development,
+    Advisors,
+    Ecosystem,
+    NodeRewards,
+    Liquidity,
+    Partnerships,
+    Marketing,
+    Community,
+}
+
+/// Vesting terms of a category, as set out in `Documents/NLOV_Structure.md`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CategoryTerms {
+    /// Category allocation in whole NLOV.
+    pub tokens: u64,
+    /// Share unlocked at TGE, in basis points.
+    pub tge_bps: u16,
+    /// Months after TGE before the linear release starts.
+    pub cliff_months: u8,
+    /// Months the rest is released over once the cliff ends.
+    pub linear_months: u8,
+    /// Whether `linear_months` is only an upper bound, set per schedule.
+    pub case_by_case: bool,
+    /// Whether the category may vest on milestones instead; see
+    /// `create_milestone_vesting`.
+    pub milestones: bool,
+}
+
+impl VestingCategory {
+    pub const ALL: [VestingCategory; VESTING_CATEGORIES] = [
+        VestingCategory::SeedSale,
+        VestingCategory::PrivateSale,
+        VestingCategory::Presale,
+        VestingCategory::PublicSale,
+        VestingCategory::Team,
+        VestingCategory::Treasury,
+        VestingCategory::Development,
+        VestingCategory::Advisors,
+        VestingCategory::Ecosystem,
+        VestingCategory::NodeRewards,
+        VestingCategory::Liquidity,
+        VestingCategory::Partnerships,
+        VestingCategory::Marketing,
+        VestingCategory::Community,
+    ];
+
+    pub const fn terms(self) -> CategoryTerms {
+        const fn fixed(
+            tokens: u64,
+            tge_bps: u16,
+            cliff_months: u8,
+            linear_months: u8,
+        ) -> CategoryTerms {
+            CategoryTerms {
+                tokens,
+                tge_bps,
+                cliff_months,
+                linear_months,
+                case_by_case: false,
+                milestones: false,
+            }
+        }
+        match self {
+            VestingCategory::SeedSale => fixed(10_000_000, 500, 3, 12),
+            VestingCategory::PrivateSale => fixed(25_000_000, 800, 2, 10),
+            VestingCategory::Presale => fixed(20_000_000, 1_000, 0, 8),
+            VestingCategory::PublicSale => fixed(35_000_000, 1_500, 0, 6),
+            // Four 25% milestone tranches, or linear.
+            VestingCategory::Team => CategoryTerms {
+                milestones: true,
+                ..fixed(75_000_000, 0, 12, 36)
+            },
+            VestingCategory::Treasury => fixed(60_000_000, 1_000, 3, 36),
+            VestingCategory::Development => fixed(25_000_000, 1_000, 2, 24),
+            VestingCategory::Advisors => fixed(15_000_000, 0, 6, 24),
+            VestingCategory::Ecosystem => fixed(75_000_000, 500, 1, 48),
+            // Performance-based release over at most 48 months.
+            VestingCategory::NodeRewards => CategoryTerms {
+                case_by_case: true,
+                ..fixed(40_000_000, 500, 0, 48)
+            },
+            VestingCategory::Liquidity => fixed(40_000_000, 3_000, 0, 24),
+            // Agreed per partner, over at most 36 months.
+            VestingCategory::Partnerships => CategoryTerms {
+                case_by_case: true,
+                ..fixed(25_000_000, 0, 3, 36)
+            },
+            VestingCategory::Marketing => fixed(15_000_000, 1_000, 0, 24),
+            VestingCategory::Community => fixed(40_000_000, 500, 0, 48),
+        }
+    }
+}
+
+impl CategoryTerms {
+    /// Fails unless a schedule with these parameters follows the terms.
+    pub fn check(&self, tge_bps: u16, cliff_duration: i64, linear_duration: i64) -> Result<()> {
+        let max_linear = self.linear_months as i64 * VESTING_MONTH;
+        require!(
+            tge_bps == self.tge_bps
+                && cliff_duration == self.cliff_months as i64 * VESTING_MONTH
+                && if self.case_by_case {
+                    linear_duration > 0 && linear_duration <= max_linear
+                } else {
+                    linear_duration == max_linear
+                },
+            ErrorCode::InvalidVestingSchedule
+        );
+        Ok(())
+    }
+
+    /// Fails unless `unlock` follows the terms. A step schedule has the same
+    /// TGE share, cliff and duration as the linear release, its steps add up
+    /// to the rest, and it never unlocks more than the linear release would
+    /// have by then.
+    pub fn check_unlock(&self, unlock: &UnlockSchedule) -> Result<()> {
+        if unlock.step_period == 0 {
+            require!(
+                unlock.step_bps.is_empty(),
+                ErrorCode::InvalidVestingSchedule
+            );
+            return self.check(
+                unlock.tge_bps,
+                unlock.cliff_duration,
+                unlock.linear_duration,
+            );
+        }
+        let steps = unlock.step_bps.len();
+        require!(
+            unlock.step_period > 0
+                && (1..=MAX_UNLOCK_STEPS).contains(&steps)
+                && unlock.step_bps.iter().all(|bps| *bps > 0)
+                && unlock.step_period.checked_mul(steps as i64) == Some(unlock.linear_duration),
+            ErrorCode::InvalidVestingSchedule
+        );
+        self.check(
+            unlock.tge_bps,
+            unlock.cliff_duration,
+            unlock.linear_duration,
+        )?;
+
+        let tge_bps = unlock.tge_bps as u128;
+        let rest = BPS_DENOMINATOR as u128 - tge_bps;
+        let duration = unlock.linear_duration as u128;
+        let mut unlocked = tge_bps;
+        for (i, bps) in unlock.step_bps.iter().enumerate() {
+            unlocked += *bps as u128;
+            let elapsed = (i as u128 + 1) * unlock.step_period as u128;
+            require!(
+                unlocked * duration <= tge_bps * duration + rest * elapsed,
+                ErrorCode::InvalidVestingSchedule
+            );
+        }
+        require!(
+            unlocked == BPS_DENOMINATOR as u128,
+            ErrorCode::InvalidVestingSchedule
+        );
+        Ok(())
+    }
+
+    /// Seconds from TGE until the category is fully vested.
+    pub fn vesting_period(&self) -> i64 {
+        (self.cliff_months as i64 + self.linear_months as i64) * VESTING_MONTH
+    }
+
+    /// Category allocation in base units of a mint with `decimals`.
+    pub fn allocation(&self, decimals: u8) -> Result<u64> {
+        10u64
+            .checked_pow(decimals as u32)
+            .and_then(|unit| self.tokens.checked_mul(unit))
+            .ok_or(ErrorCode::CalculationError.into())
+    }
+}
+
+/// How a schedule unlocks: nothing before TGE, `tge_bps` of the total at
+/// TGE, then the rest from the end of the cliff, either continuously over
+/// `linear_duration` or in steps of `step_period`. A cliff of `i64::MAX`
+/// never ends, so only the TGE share unlocks. This is the one vesting
+/// calculator behind `claim_tokens`, `release`, `release_milestones` and
+/// `claim_distribution`. The TGE itself is the mint's, passed in from its
+/// `EmissionSchedule`, so postponing it moves every schedule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnlockSchedule<'a> {
+    pub tge_bps: u16,
+    pub cliff_duration: i64,
+    /// Seconds from the end of the cliff until everything is unlocked.
+    pub linear_duration: i64,
+    /// Seconds per step, or 0 for a continuous release.
+    pub step_period: i64,
+    /// Basis points unlocked at the end of each step.
+    pub step_bps: &'a [u16],
+}
+
+impl UnlockSchedule<'_> {
+    /// Tokens of `total_amount` unlocked by `now` for a TGE at
+    /// `tge_timestamp`.
+    pub fn vested_amount(&self, total_amount: u64, tge_timestamp: i64, now: i64) -> Result<u64> {
+        if now < tge_timestamp {
+            return Ok(0);
+        }
+        let total = total_amount as u128;
+        let at_tge = total * self.tge_bps as u128 / BPS_DENOMINATOR as u128;
+        let cliff_end = tge_timestamp.saturating_add(self.cliff_duration);
+        let vested = if now < cliff_end {
+            at_tge
+        } else {
+            let elapsed = (now - cliff_end) as u128;
+            let duration = self.linear_duration as u128;
+            if elapsed >= duration {
+                total
+            } else if self.step_period > 0 {
+                let steps = (elapsed / self.step_period as u128) as usize;
+                let bps = self
+                    .step_bps
+                    .iter()
+                    .take(steps)
+                    .fold(self.tge_bps as u128, |sum, bps| sum + *bps as u128);
+                total * bps / BPS_DENOMINATOR as u128
+            } else {
+                at_tge + (total - at_tge) * elapsed / duration
+            }
+        };
+        u64::try_from(vested).map_err(|_| ErrorCode::CalculationError.into())
+    }
+}
+
+/// Amounts put under vesting so far for one mint, at
+/// `[b"vesting_ledger", mint]`.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct VestingLedger {
+    pub mint: Pubkey,
+    /// Base units vested per category, indexed by `VestingCategory`.
+    pub allocated: [u64; VESTING_CATEGORIES],
+    /// Schedules created so far, which is also the id of the next one.
+    pub schedule_count: u64,
+    pub bump: u8,
+}
+
+impl VestingLedger {
+    /// Counts `amount` against the allocation of `category`.
+    pub fn allocate(&mut self, category: VestingCategory, amount: u64, decimals: u8) -> Result<()> {
+        let allocated = &mut self.allocated[category as usize];
+        let total = allocated
+            .checked_add(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+        require!(
+            total <= category.terms().allocation(decimals)?,
+            ErrorCode::VestingAllocationExceeded
+        );
+        *allocated = total;
+        Ok(())
+    }
+
+    /// Gives `amount` back to the allocation of `category`.
+    pub fn deallocate(&mut self, category: VestingCategory, amount: u64) -> Result<()> {
+        let allocated = &mut self.allocated[category as usize];
+        *allocated = allocated
+            .checked_sub(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+        Ok(())
+    }
+}
+
+/// Tokens released to one beneficiary over time, at
+/// `[b"vesting", mint, id]`. The tokens sit in `escrow`, a token account
+/// owned by this schedule.
+#[account]
+#[derive(InitSpace)]
+pub struct VestingSchedule {
+    pub mint: Pubkey,
+    pub id: u64,
+    pub beneficiary: Pubkey,
+    pub category: VestingCategory,
+    pub escrow: Pubkey,
+    pub total_amount: u64,
+    pub released_amount: u64,
+    /// Share unlocked at the mint's TGE, in basis points.
+    pub tge_bps: u16,
+    /// Seconds after TGE before the linear release starts.
+    pub cliff_duration: i64,
+    /// Seconds the rest is released over once the cliff ends.
+    pub linear_duration: i64,
+    pub bump: u8,
+    /// Whether the protocol authority may revoke the unvested part.
+    pub revocable: bool,
+    /// When vesting was frozen by `revoke_grant`, or 0.
+    pub revoked_at: i64,
+    /// Seconds per step, or 0 for a continuous release.
+    pub step_period: i64,
+    /// Basis points unlocked at the end of each step after the cliff.
+    #[max_len(MAX_UNLOCK_STEPS)]
+    pub step_bps: Vec<u16>,
+}
+
+impl VestingSchedule {
+    /// Tokens vested by `now` for a TGE at `tge_timestamp` (see
+    /// `UnlockSchedule`). Vesting stops at `revoked_at` once the grant is
+    /// revoked.
+    pub fn vested_amount(&self, tge_timestamp: i64, now: i64) -> Result<u64> {
+        let now = if self.is_revoked() {
+            now.min(self.revoked_at)
+        } else {
+            now
+        };
+        self.unlock()
+            .vested_amount(self.total_amount, tge_timestamp, now)
+    }
+
+    pub fn unlock(&self) -> UnlockSchedule<'_> {
+        UnlockSchedule {
+            tge_bps: self.tge_bps,
+            cliff_duration: self.cliff_duration,
+            linear_duration: self.linear_duration,
+            step_period: self.step_period,
+            step_bps: &self.step_bps,
+        }
+    }
+
+    /// Vested tokens not yet released.
+    pub fn releasable(&self, tge_timestamp: i64, now: i64) -> Result<u64> {
+        self.vested_amount(tge_timestamp, now)?
+            .checked_sub(self.released_amount)
+            .ok_or(ErrorCode::CalculationError.into())
+    }
+
+    /// Tokens not vested by `now`. Nothing is locked once the grant is
+    /// revoked, since the unvested tokens went back to the category vault.
+    pub fn locked_amount(&self, tge_timestamp: i64, now: i64) -> Result<u64> {
+        if self.is_revoked() {
+            return Ok(0);
+        }
+        self.total_amount
+            .checked_sub(self.vested_amount(tge_timestamp, now)?)
+            .ok_or(ErrorCode::CalculationError.into())
+    }
+
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at != 0
+    }
+
+    /// Freezes vesting at `now` and returns the tokens that will now never
+    /// vest.
+    pub fn revoke(&mut self, tge_timestamp: i64, now: i64) -> Result<u64> {
+        require!(self.revocable, ErrorCode::GrantNotRevocable);
+        require!(!self.is_revoked(), ErrorCode::GrantAlreadyRevoked);
+        let vested = self.vested_amount(tge_timestamp, now)?;
+        self.revoked_at = now;
+        self.total_amount
+            .checked_sub(vested)
+            .ok_or(ErrorCode::CalculationError.into())
+    }
+
+    /// PDA signer seeds; `id` is `self.id.to_le_bytes()`.
+    pub fn signer_seeds<'a>(&'a self, id: &'a [u8; 8]) -> [&'a [u8]; 4] {
+        [
+            b"vesting",
+            self.mint.as_ref(),
+            id,
+            std::slice::from_ref(&self.bump),
+        ]
+    }
+}
@@ -0,0 +1,133 @@
+use anchor_lang::prelude::*;
+
+use crate::{ErrorCode, BPS_DENOMINATOR};
+
+/// Length of the rolling window `withdraw` is capped over (24 hours).
+pub const WITHDRAWAL_WINDOW: i64 = 24 * 60 * 60;
+
+/// Granularity of the window. A withdrawal stops counting 23 to 24 hours
+/// after it was made.
+pub const WITHDRAWAL_BUCKET: i64 = 60 * 60;
+
+const BUCKETS: usize = (WITHDRAWAL_WINDOW / WITHDRAWAL_BUCKET) as usize;
+
+/// Limit every new sale starts with: 10% of the vault per window.
+pub const DEFAULT_WITHDRAWAL_LIMIT: WithdrawalLimit = WithdrawalLimit {
+    max_lamports: 0,
+    max_bps: 1_000,
+};
+
+/// Ceiling on how fast `withdraw` may empty the vault. A zero field is not
+/// enforced; when both are set the lower cap applies.
+#[derive(
+    AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq,
+)]
+pub struct WithdrawalLimit {
+    /// Most lamports per window.
+    pub max_lamports: u64,
+    /// Most basis points of the vault per window, measured against the vault
+    /// as it stood before the window's withdrawals.
+    pub max_bps: u16,
+}
+
+impl WithdrawalLimit {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.max_bps <= BPS_DENOMINATOR,
+            ErrorCode::InvalidWithdrawalLimit
+        );
+        Ok(())
+    }
+
+    /// Whether `new` allows more than `self` for some vault.
+    pub fn is_loosened_by(&self, new: &WithdrawalLimit) -> bool {
+        fn looser(current: u64, new: u64) -> bool {
+            current > 0 && (new == 0 || new > current)
+        }
+        looser(self.max_lamports, new.max_lamports)
+            || looser(self.max_bps as u64, new.max_bps as u64)
+    }
+
+    /// Lamports the window may hold in total, given the vault balance and what
+    /// the window already holds, or `None` without a limit.
+    pub fn cap(&self, vault_balance: u64, withdrawn: u64) -> Option<u64> {
+        let by_lamports = (self.max_lamports > 0).then_some(self.max_lamports);
+        let by_bps = (self.max_bps > 0).then(|| {
+            let base = vault_balance as u128 + withdrawn as u128;
+            (base * self.max_bps as u128 / BPS_DENOMINATOR as u128) as u64
+        });
+        match (by_lamports, by_bps) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+/// Lamports withdrawn over the last `WITHDRAWAL_WINDOW`, in hourly buckets.
+#[derive(
+    AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq,
+)]
+pub struct WithdrawalWindow {
+    pub buckets: [u64; BUCKETS],
+    /// Hour (UNIX time / `WITHDRAWAL_BUCKET`) of the newest bucket.
+    pub last_hour: i64,
+}
+
+impl WithdrawalWindow {
+    /// Clears the buckets that fell out of the window by `now`.
+    fn roll(&mut self, now: i64) {
+        let hour = now.div_euclid(WITHDRAWAL_BUCKET);
+        let elapsed = hour.saturating_sub(self.last_hour);
+        if elapsed <= 0 {
+            return;
+        }
+        if elapsed >= BUCKETS as i64 {
+            self.buckets = [0; BUCKETS];
+        } else {
+            for h in self.last_hour + 1..=hour {
+                self.buckets[h.rem_euclid(BUCKETS as i64) as usize] = 0;
+            }
+        }
+        self.last_hour = hour;
+    }
+
+    /// Lamports withdrawn in the window ending at `now`.
+    pub fn withdrawn(&self, now: i64) -> u64 {
+        let mut window = *self;
+        window.roll(now);
+        window
+            .buckets
+            .iter()
+            .fold(0u64, |sum, b| sum.saturating_add(*b))
+    }
+
+    /// Checks `amount` against `limit` and counts it in the window.
+    pub fn record(
+        &mut self,
+        limit: &WithdrawalLimit,
+        amount: u64,
+        vault_balance: u64,
+        now: i64,
+    ) -> Result<()> {
+        let withdrawn = self.withdrawn(now);
+        if let Some(cap) = limit.cap(vault_balance, withdrawn) {
+            require!(
+                withdrawn
+                    .checked_add(amount)
+                    .is_some_and(|total| total <= cap),
+                ErrorCode::WithdrawalLimitExceeded
+            );
+        }
+        self.count(amount, now)
+    }
+
+    /// Counts `amount` in the window without checking a limit.
+    pub fn count(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.roll(now);
+        let bucket = &mut self.buckets[self.last_hour.rem_euclid(BUCKETS as i64) as usize];
+        *bucket = bucket
+            .checked_add(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+        Ok(())
+    }
+}
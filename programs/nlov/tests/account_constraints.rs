@@ -0,0 +1,266 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::solana_program::program_pack::Pack;
+    use anchor_lang::{Accounts, Space};
+    use anchor_spl::token::spl_token;
+    use nlov::{
+        ClaimRefund, ClaimRefundBumps, ClaimTokens, ClaimTokensBumps, CreatePresale,
+        CreatePresaleBumps, ErrorCode, Presale, ProtocolConfig, SalePhase,
+    };
+
+    use crate::common::{
+        event_cpi_accounts, infos, install_stubs, presale, user_info, user_info_account,
+        user_info_key, TestAccount,
+    };
+
+    const PRESALE: usize = 0;
+
+    /// Accounts for `claim_tokens`, in declaration order.
+    fn claim_tokens_accounts() -> Vec<TestAccount> {
+        let presale_key = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let presale_token_account = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let presale = Presale {
+            token_mint: mint,
+            presale_token_account,
+            ..presale(SalePhase::Pending)
+        };
+        let mut accounts = vec![
+            TestAccount::anchor(presale_key, &presale, Presale::INIT_SPACE),
+            TestAccount::token_account(presale_token_account, mint, presale_key),
+            TestAccount::token_account(Pubkey::new_unique(), mint, user),
+            user_info_account(&presale_key, &user_info(user, 1_000)),
+            TestAccount::signer(user),
+            TestAccount::program(spl_token::ID),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::emission_schedule(&mint),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    /// Checks the `claim_tokens` accounts without running the handler.
+    fn validate_claim_tokens(accounts: &mut [TestAccount]) -> anchor_lang::Result<()> {
+        let infos = infos(accounts);
+        ClaimTokens::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut ClaimTokensBumps::default(),
+            &mut BTreeSet::new(),
+        )
+        .map(|_| ())
+    }
+
+    /// Accounts for `claim_refund`, in declaration order.
+    fn claim_refund_accounts() -> Vec<TestAccount> {
+        let presale_key = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let presale = presale(SalePhase::Pending);
+        let vault = Pubkey::find_program_address(&[b"vault", presale_key.as_ref()], &nlov::ID).0;
+        let mut accounts = vec![
+            TestAccount::anchor(presale_key, &presale, Presale::INIT_SPACE),
+            TestAccount::wallet(vault),
+            user_info_account(&presale_key, &user_info(user, 1_000)),
+            TestAccount::signer(user),
+            TestAccount::program(anchor_lang::system_program::ID),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::absent(),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    /// Checks the `claim_refund` accounts without running the handler.
+    fn validate_claim_refund(accounts: &mut [TestAccount]) -> anchor_lang::Result<()> {
+        let infos = infos(accounts);
+        ClaimRefund::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut ClaimRefundBumps::default(),
+            &mut BTreeSet::new(),
+        )
+        .map(|_| ())
+    }
+
+    const OWNER_TOKEN_ACCOUNT: usize = 5;
+    const PRESALE_TOKEN_ACCOUNT: usize = 6;
+
+    /// Accounts for `create_presale`, in declaration order.
+    fn create_presale_accounts() -> Vec<TestAccount> {
+        let owner = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let fee_recipient = Pubkey::new_unique();
+        let presale =
+            Pubkey::find_program_address(&[b"presale", owner.as_ref(), mint.as_ref()], &nlov::ID).0;
+        let (config_key, bump) = Pubkey::find_program_address(&[b"protocol_config"], &nlov::ID);
+        let config = ProtocolConfig {
+            fee_recipient,
+            bump,
+            ..ProtocolConfig::default()
+        };
+        let mut accounts = vec![
+            TestAccount::uninitialized(presale, Presale::INIT_SPACE),
+            TestAccount::anchor(config_key, &config, ProtocolConfig::INIT_SPACE),
+            TestAccount::wallet(fee_recipient),
+            TestAccount::signer(owner),
+            TestAccount::mint(mint),
+            TestAccount::token_account(Pubkey::new_unique(), mint, owner),
+            TestAccount::token_account(Pubkey::new_unique(), mint, presale),
+            TestAccount::program(spl_token::ID),
+            TestAccount::program(anchor_lang::system_program::ID),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn create_presale(accounts: &mut [TestAccount]) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        CreatePresale::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut CreatePresaleBumps::default(),
+            &mut BTreeSet::new(),
+        )
+        .map(|_| ())
+    }
+
+    #[test]
+    fn test_claim_tokens_accepts_matching_accounts() {
+        validate_claim_tokens(&mut claim_tokens_accounts()).unwrap();
+    }
+
+    #[test]
+    fn test_claim_tokens_rejects_substituted_presale_token_account() {
+        let mut accounts = claim_tokens_accounts();
+        let mint = spl_token::state::Account::unpack_from_slice(&accounts[1].data)
+            .unwrap()
+            .mint;
+        // Same mint and authority, but not the account the presale recorded.
+        accounts[1] = TestAccount::token_account(Pubkey::new_unique(), mint, accounts[PRESALE].key);
+        assert_eq!(
+            validate_claim_tokens(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::InvalidPresaleTokenAccount)
+        );
+    }
+
+    #[test]
+    fn test_claim_tokens_rejects_user_token_account_with_wrong_mint() {
+        let mut accounts = claim_tokens_accounts();
+        accounts[2] =
+            TestAccount::token_account(accounts[2].key, Pubkey::new_unique(), accounts[4].key);
+        assert_eq!(
+            validate_claim_tokens(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::InvalidTokenMint)
+        );
+    }
+
+    #[test]
+    fn test_claim_tokens_rejects_user_token_account_of_another_owner() {
+        let mut accounts = claim_tokens_accounts();
+        let mint = spl_token::state::Account::unpack_from_slice(&accounts[2].data)
+            .unwrap()
+            .mint;
+        accounts[2] = TestAccount::token_account(accounts[2].key, mint, Pubkey::new_unique());
+        assert_eq!(
+            validate_claim_tokens(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::InvalidTokenAccountOwner)
+        );
+    }
+
+    #[test]
+    fn test_claim_tokens_rejects_user_info_of_another_user() {
+        let mut accounts = claim_tokens_accounts();
+        let (presale, user) = (accounts[PRESALE].key, accounts[4].key);
+        // Someone else's record, without transferable receipts to redeem it.
+        accounts[3] = user_info_account(&presale, &user_info(Pubkey::new_unique(), 1_000));
+        assert_eq!(
+            validate_claim_tokens(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::UserInfoMismatch)
+        );
+
+        // The signer's address, but the record inside names someone else.
+        let mut forged = user_info_account(&presale, &user_info(Pubkey::new_unique(), 1_000));
+        forged.key = user_info_key(&presale, &user);
+        accounts[3] = forged;
+        assert_eq!(
+            validate_claim_tokens(&mut accounts).unwrap_err(),
+            Error::from(anchor_lang::error::ErrorCode::ConstraintSeeds)
+        );
+    }
+
+    #[test]
+    fn test_claim_refund_rejects_user_info_of_another_user() {
+        let mut accounts = claim_refund_accounts();
+        validate_claim_refund(&mut accounts).unwrap();
+
+        let (presale, user) = (accounts[PRESALE].key, accounts[3].key);
+        accounts[2] = user_info_account(&presale, &user_info(Pubkey::new_unique(), 1_000));
+        assert_eq!(
+            validate_claim_refund(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::UserInfoMismatch)
+        );
+
+        let mut forged = user_info_account(&presale, &user_info(Pubkey::new_unique(), 1_000));
+        forged.key = user_info_key(&presale, &user);
+        accounts[2] = forged;
+        assert_eq!(
+            validate_claim_refund(&mut accounts).unwrap_err(),
+            Error::from(anchor_lang::error::ErrorCode::ConstraintSeeds)
+        );
+    }
+
+    #[test]
+    fn test_create_presale_accepts_matching_accounts() {
+        create_presale(&mut create_presale_accounts()).unwrap();
+    }
+
+    #[test]
+    fn test_create_presale_rejects_presale_token_account_not_owned_by_presale() {
+        let mut accounts = create_presale_accounts();
+        let (owner, mint) = (accounts[3].key, accounts[4].key);
+        accounts[PRESALE_TOKEN_ACCOUNT] =
+            TestAccount::token_account(Pubkey::new_unique(), mint, owner);
+        assert_eq!(
+            create_presale(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::InvalidTokenAccountOwner)
+        );
+    }
+
+    #[test]
+    fn test_create_presale_rejects_presale_token_account_with_wrong_mint() {
+        let mut accounts = create_presale_accounts();
+        let presale = accounts[PRESALE].key;
+        accounts[PRESALE_TOKEN_ACCOUNT] =
+            TestAccount::token_account(Pubkey::new_unique(), Pubkey::new_unique(), presale);
+        assert_eq!(
+            create_presale(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::InvalidTokenMint)
+        );
+    }
+
+    #[test]
+    fn test_create_presale_rejects_owner_token_account_with_wrong_mint() {
+        let mut accounts = create_presale_accounts();
+        let owner = accounts[3].key;
+        accounts[OWNER_TOKEN_ACCOUNT] =
+            TestAccount::token_account(Pubkey::new_unique(), Pubkey::new_unique(), owner);
+        assert_eq!(
+            create_presale(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::InvalidTokenMint)
+        );
+    }
+}
@@ -0,0 +1,275 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::{Context, Pubkey};
+    use anchor_lang::{AccountDeserialize, Accounts, AccountsExit, Space};
+    use anchor_spl::token::spl_token;
+    use nlov::neurolov_presale;
+    use nlov::{
+        AllocationTransferred, ErrorCode, Presale, SalePhase, TokensClaimed, TransferAllocation,
+        TransferAllocationBumps, UserInfo,
+    };
+
+    use crate::common::{
+        claim_tokens, emitted, event_cpi_accounts, infos, install_stubs, presale, user_info,
+        user_info_account, TestAccount, NOW,
+    };
+
+    const FROM_USER_INFO: usize = 1;
+    const TO_USER_INFO: usize = 2;
+    const RECIPIENT: usize = 4;
+    const TRANSFER_AGENT: usize = 5;
+
+    fn transferable_presale(transfer_agent: Pubkey) -> Presale {
+        Presale {
+            allocation_transfers_enabled: true,
+            transfer_agent,
+            tge_timestamp: NOW - 1,
+            token_mint: Pubkey::new_unique(),
+            presale_token_account: Pubkey::new_unique(),
+            ..presale(SalePhase::Claiming)
+        }
+    }
+
+    /// Accounts for `transfer_allocation` from a seller holding 1_000 NLOV, 200
+    /// of it claimed, to a buyer who already holds 50.
+    fn transfer_accounts(agent_signs: bool) -> Vec<TestAccount> {
+        let key = Pubkey::new_unique();
+        let (from, recipient, agent) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut accounts = vec![
+            TestAccount::anchor(key, &transferable_presale(agent), Presale::INIT_SPACE),
+            user_info_account(
+                &key,
+                &UserInfo {
+                    amount_claimed: 200,
+                    ..user_info(from, 1_000)
+                },
+            ),
+            user_info_account(&key, &user_info(recipient, 50)),
+            TestAccount::signer(from),
+            TestAccount::wallet(recipient),
+            if agent_signs {
+                TestAccount::signer(agent)
+            } else {
+                // An optional account left out is passed as the program id.
+                TestAccount::program(nlov::ID)
+            },
+            TestAccount::program(anchor_lang::system_program::ID),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::absent(),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn transfer(accounts: &mut [TestAccount], amount: u64) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = TransferAllocationBumps::default();
+        let mut ix = TransferAllocation::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::transfer_allocation(
+            Context::new(&nlov::ID, &mut ix, &[], bumps),
+            amount,
+        )?;
+        ix.exit(&nlov::ID)
+    }
+
+    fn stored_user_info(account: &TestAccount) -> UserInfo {
+        UserInfo::try_deserialize(&mut &account.data[..]).unwrap()
+    }
+
+    #[test]
+    fn test_move_allocation() {
+        let mut from = UserInfo {
+            amount_claimed: 200,
+            ..user_info(Pubkey::new_unique(), 1_000)
+        };
+        let mut to = user_info(Pubkey::new_unique(), 0);
+
+        // A quarter of the position moves: 300 unclaimed and 75 claimed.
+        from.move_allocation(&mut to, 300).unwrap();
+        assert_eq!((from.nlov_allocated, from.amount_claimed), (625, 125));
+        assert_eq!((to.nlov_allocated, to.amount_claimed), (375, 75));
+
+        // Only the unclaimed remainder can move.
+        from.move_allocation(&mut to, 500).unwrap();
+        assert_eq!((from.nlov_allocated, from.amount_claimed), (0, 0));
+        assert_eq!((to.nlov_allocated, to.amount_claimed), (1_000, 200));
+    }
+
+    #[test]
+    fn test_move_allocation_rounds_claimed_share_up() {
+        let mut from = UserInfo {
+            amount_claimed: 1,
+            ..user_info(Pubkey::new_unique(), 1_000)
+        };
+        let mut to = user_info(Pubkey::new_unique(), 0);
+
+        from.move_allocation(&mut to, 1).unwrap();
+        assert_eq!((to.nlov_allocated, to.amount_claimed), (2, 1));
+        assert_eq!(from.unclaimed().unwrap(), 998);
+    }
+
+    #[test]
+    fn test_move_allocation_rejects_bad_amounts() {
+        let mut from = UserInfo {
+            amount_claimed: 200,
+            ..user_info(Pubkey::new_unique(), 1_000)
+        };
+        let mut to = user_info(Pubkey::new_unique(), 0);
+
+        assert_eq!(
+            from.move_allocation(&mut to, 0).unwrap_err(),
+            Error::from(ErrorCode::InvalidAmount)
+        );
+        assert_eq!(
+            from.move_allocation(&mut to, 801).unwrap_err(),
+            Error::from(ErrorCode::InsufficientAllocation)
+        );
+        assert_eq!(from.nlov_allocated, 1_000);
+        assert_eq!(to.nlov_allocated, 0);
+    }
+
+    #[test]
+    fn test_authorize_allocation_transfer() {
+        let agent = Pubkey::new_unique();
+
+        let mut open = transferable_presale(Pubkey::default());
+        open.authorize_allocation_transfer(None).unwrap();
+        open.allocation_transfers_enabled = false;
+        assert_eq!(
+            open.authorize_allocation_transfer(None).unwrap_err(),
+            Error::from(ErrorCode::AllocationTransfersDisabled)
+        );
+
+        let agented = transferable_presale(agent);
+        assert_eq!(
+            agented.authorize_allocation_transfer(None).unwrap_err(),
+            Error::from(ErrorCode::TransferAgentRequired)
+        );
+        agented.authorize_allocation_transfer(Some(agent)).unwrap();
+    }
+
+    #[test]
+    fn test_transfer_allocation() {
+        let mut accounts = transfer_accounts(true);
+        transfer(&mut accounts, 600).unwrap();
+
+        let from = stored_user_info(&accounts[FROM_USER_INFO]);
+        let to = stored_user_info(&accounts[TO_USER_INFO]);
+        assert_eq!((from.nlov_allocated, from.amount_claimed), (250, 50));
+        assert_eq!((to.nlov_allocated, to.amount_claimed), (800, 150));
+        assert_eq!(to.user, accounts[RECIPIENT].key);
+
+        let events = emitted::<AllocationTransferred>();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].to, accounts[RECIPIENT].key);
+        assert_eq!(events[0].amount, 600);
+        assert_eq!(events[0].transfer_agent, Some(accounts[TRANSFER_AGENT].key));
+    }
+
+    #[test]
+    fn test_transfer_allocation_requires_agent_signature() {
+        let mut accounts = transfer_accounts(false);
+        assert_eq!(
+            transfer(&mut accounts, 600).unwrap_err(),
+            Error::from(ErrorCode::TransferAgentRequired)
+        );
+    }
+
+    #[test]
+    fn test_transfer_allocation_rejects_other_agent() {
+        let mut accounts = transfer_accounts(true);
+        accounts[TRANSFER_AGENT] = TestAccount::signer(Pubkey::new_unique());
+        assert_eq!(
+            transfer(&mut accounts, 600).unwrap_err(),
+            Error::from(ErrorCode::InvalidTransferAgent)
+        );
+    }
+
+    #[test]
+    fn test_transfer_allocation_rejects_self_transfer() {
+        let mut accounts = transfer_accounts(true);
+        let from = accounts[3].key;
+        let from_user_info = stored_user_info(&accounts[FROM_USER_INFO]);
+        accounts[TO_USER_INFO] = user_info_account(&accounts[0].key, &from_user_info);
+        accounts[RECIPIENT] = TestAccount::wallet(from);
+        assert_eq!(
+            transfer(&mut accounts, 600).unwrap_err(),
+            Error::from(ErrorCode::InvalidTransferRecipient)
+        );
+    }
+
+    /// Accounts for `claim_tokens` by the holder of `user_info`, in
+    /// declaration order.
+    fn claim_tokens_accounts(presale: &TestAccount, user_info: &TestAccount) -> Vec<TestAccount> {
+        let stored = Presale::try_deserialize(&mut &presale.data[..]).unwrap();
+        let info = stored_user_info(user_info);
+        let user = info.user;
+        let mut accounts = vec![
+            TestAccount::anchor(presale.key, &stored, Presale::INIT_SPACE),
+            TestAccount::token_account(
+                stored.presale_token_account,
+                stored.token_mint,
+                presale.key,
+            ),
+            TestAccount::token_account(Pubkey::new_unique(), stored.token_mint, user),
+            user_info_account(&presale.key, &info),
+            TestAccount::signer(user),
+            TestAccount::program(spl_token::ID),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::emission_schedule(&stored.token_mint),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    #[test]
+    fn test_claim_right_after_transfer_follows_sender_vesting() {
+        // Just past TGE the presale's 10% has unlocked, and the seller has
+        // already claimed it.
+        let mut accounts = transfer_accounts(true);
+        let (presale, from) = (accounts[0].key, accounts[3].key);
+        accounts[FROM_USER_INFO] = user_info_account(
+            &presale,
+            &UserInfo {
+                amount_claimed: 100,
+                ..user_info(from, 1_000)
+            },
+        );
+        transfer(&mut accounts, 900).unwrap();
+
+        let to = stored_user_info(&accounts[TO_USER_INFO]);
+        assert_eq!((to.nlov_allocated, to.amount_claimed), (1_050, 100));
+
+        // Only the 10% of the buyer's own 50 is claimable; the bought
+        // allocation unlocks on the seller's schedule.
+        let mut claim = claim_tokens_accounts(&accounts[0], &accounts[TO_USER_INFO]);
+        claim_tokens(&mut claim).unwrap();
+        assert_eq!(emitted::<TokensClaimed>()[0].amount, 5);
+
+        let mut claim = claim_tokens_accounts(&accounts[0], &accounts[FROM_USER_INFO]);
+        assert_eq!(
+            claim_tokens(&mut claim).unwrap_err(),
+            Error::from(ErrorCode::NothingToClaim)
+        );
+    }
+}
@@ -0,0 +1,198 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::{Context, Pubkey};
+    use anchor_lang::solana_program::program_option::COption;
+    use anchor_lang::solana_program::program_pack::Pack;
+    use anchor_lang::AccountDeserialize;
+    use anchor_lang::{Accounts, AccountsExit, Space};
+    use anchor_spl::token::spl_token;
+    use anchor_spl::token_2022::spl_token_2022;
+    use anchor_spl::token_2022::spl_token_2022::instruction::{AuthorityType, TokenInstruction};
+    use anchor_spl::token_interface::spl_token_metadata_interface::instruction::TokenMetadataInstruction;
+    use anchor_spl::token_interface::spl_token_metadata_interface::state::Field;
+    use nlov::emission::{NLOV_DECIMALS, TOTAL_SUPPLY};
+    use nlov::metadata::{MetadataAuthority, MetadataEntry};
+    use nlov::vesting::VestingCategory;
+    use nlov::{
+        neurolov_presale, BootstrapToken, BootstrapTokenBumps, ErrorCode, ProtocolConfig,
+        TokenBootstrapped,
+    };
+
+    use crate::common::{emitted, event_cpi_accounts, infos, install_stubs, invoked, TestAccount};
+
+    const NLOV: u64 = 1_000_000_000;
+
+    fn vault_key(mint: &Pubkey, category: VestingCategory) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"category_vault", mint.as_ref(), &[category as u8]],
+            &nlov::ID,
+        )
+        .0
+    }
+
+    /// Accounts for `bootstrap_token`, in declaration order. `init` does not
+    /// run off-chain, so the mint and vaults are passed as it leaves them.
+    fn bootstrap_accounts() -> Vec<TestAccount> {
+        let authority = Pubkey::new_unique();
+        let mint = Pubkey::find_program_address(&[b"nlov_mint"], &nlov::ID).0;
+        let metadata_authority =
+            Pubkey::find_program_address(&[b"metadata_authority", mint.as_ref()], &nlov::ID).0;
+        let vault_authority =
+            Pubkey::find_program_address(&[b"category_vault_authority", mint.as_ref()], &nlov::ID)
+                .0;
+        let (config_key, bump) = Pubkey::find_program_address(&[b"protocol_config"], &nlov::ID);
+        let config = ProtocolConfig {
+            authority,
+            bump,
+            ..ProtocolConfig::default()
+        };
+
+        let state = spl_token::state::Mint {
+            mint_authority: COption::Some(authority),
+            supply: 0,
+            decimals: NLOV_DECIMALS,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        let mut mint_account = TestAccount::mint(mint).owned_by(spl_token_2022::ID);
+        state.pack_into_slice(&mut mint_account.data);
+
+        let mut accounts = vec![
+            mint_account,
+            TestAccount::uninitialized(metadata_authority, MetadataAuthority::INIT_SPACE),
+            TestAccount::wallet(vault_authority),
+        ];
+        accounts.extend(VestingCategory::ALL.map(|category| {
+            TestAccount::token_account(vault_key(&mint, category), mint, vault_authority)
+                .owned_by(spl_token_2022::ID)
+        }));
+        accounts.extend([
+            TestAccount::anchor(config_key, &config, ProtocolConfig::INIT_SPACE),
+            TestAccount::signer(authority),
+            TestAccount::program(spl_token_2022::ID),
+            TestAccount::program(anchor_lang::system_program::ID),
+        ]);
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn bootstrap(accounts: &mut [TestAccount]) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = BootstrapTokenBumps::default();
+        let mut ix = BootstrapToken::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::bootstrap_token(
+            Context::new(&nlov::ID, &mut ix, &[], bumps),
+            "https://neurolov.ai/nlov.json".to_string(),
+            vec![MetadataEntry {
+                key: "website".to_string(),
+                value: "https://neurolov.ai".to_string(),
+            }],
+        )?;
+        ix.exit(&nlov::ID)
+    }
+
+    #[test]
+    fn test_bootstrap_token() {
+        let mut accounts = bootstrap_accounts();
+        bootstrap(&mut accounts).unwrap();
+        let mint = accounts[0].key;
+
+        let token_ixs: Vec<_> = invoked()
+            .into_iter()
+            .filter(|ix| ix.program_id == spl_token_2022::ID)
+            .collect();
+        let mut minted = 0;
+        for category in VestingCategory::ALL {
+            let amount = token_ixs
+                .iter()
+                .find_map(|ix| match TokenInstruction::unpack(&ix.data) {
+                    Ok(TokenInstruction::MintTo { amount })
+                        if ix.accounts[1].pubkey == vault_key(&mint, category) =>
+                    {
+                        Some(amount)
+                    }
+                    _ => None,
+                })
+                .unwrap();
+            assert_eq!(amount, category.terms().tokens * NLOV);
+            minted += amount;
+        }
+        assert_eq!(minted, TOTAL_SUPPLY * NLOV);
+
+        // Then the mint authority is revoked.
+        let revoke = token_ixs.last().unwrap();
+        assert_eq!(revoke.accounts[0].pubkey, mint);
+        assert!(matches!(
+            TokenInstruction::unpack(&revoke.data).unwrap(),
+            TokenInstruction::SetAuthority {
+                authority_type: AuthorityType::MintTokens,
+                new_authority: COption::None,
+            }
+        ));
+
+        // The metadata is set up while the authority can still sign as mint
+        // authority, and the role goes to the protocol authority.
+        let metadata: Vec<_> = token_ixs
+            .iter()
+            .filter_map(|ix| TokenMetadataInstruction::unpack(&ix.data).ok())
+            .collect();
+        let [TokenMetadataInstruction::Initialize(initialize), TokenMetadataInstruction::UpdateField(update)] =
+            &metadata[..]
+        else {
+            panic!("unexpected metadata instructions: {metadata:?}");
+        };
+        assert_eq!(initialize.name, "NEUROLOV");
+        assert_eq!(initialize.symbol, "NLOV");
+        assert_eq!(initialize.uri, "https://neurolov.ai/nlov.json");
+        assert_eq!(update.field, Field::Key("website".to_string()));
+        assert_eq!(update.value, "https://neurolov.ai");
+        let role = MetadataAuthority::try_deserialize(&mut &accounts[1].data[..]).unwrap();
+        assert_eq!(role.mint, mint);
+        assert_eq!(role.authority, accounts[accounts.len() - 5].key);
+
+        let event = &emitted::<TokenBootstrapped>()[0];
+        assert_eq!(event.mint, mint);
+        assert_eq!(event.supply, TOTAL_SUPPLY * NLOV);
+        assert_eq!(
+            event.vaults,
+            VestingCategory::ALL.map(|category| vault_key(&mint, category))
+        );
+    }
+
+    #[test]
+    fn test_bootstrap_token_requires_protocol_authority() {
+        let mut accounts = bootstrap_accounts();
+        let authority = accounts.len() - 5;
+        accounts[authority] = TestAccount::signer(Pubkey::new_unique());
+        assert_eq!(
+            bootstrap(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::Unauthorized)
+        );
+    }
+
+    #[test]
+    fn test_bootstrap_token_rejects_misplaced_vault() {
+        // The team's tokens cannot be routed to the treasury vault.
+        let mut accounts = bootstrap_accounts();
+        accounts.swap(7, 8);
+        let Error::AnchorError(error) = bootstrap(&mut accounts).unwrap_err() else {
+            panic!("expected an Anchor error");
+        };
+        assert_eq!(
+            error.error_code_number,
+            anchor_lang::error::ErrorCode::ConstraintSeeds as u32
+        );
+    }
+}
@@ -0,0 +1,412 @@
+//! Fabricated accounts, fixtures and instruction runners shared by the
+//! handler tests, driving `#[derive(Accounts)]` validation off-chain.
+
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::{ProgramResult, SUCCESS};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use anchor_lang::solana_program::system_program;
+use anchor_lang::Discriminator;
+use anchor_spl::token::spl_token;
+use nlov::emission::EmissionSchedule;
+use nlov::vesting::{VestingCategory, VestingSchedule, VESTING_MONTH};
+use nlov::{
+    neurolov_presale, ClaimRefund, ClaimRefundBumps, ClaimTokens, ClaimTokensBumps, PauseUnpause,
+    PauseUnpauseBumps, Presale, Release, ReleaseBumps, SalePhase, SetTge, SetTgeBumps, UserInfo,
+    PRESALE_VERSION, USER_INFO_VERSION,
+};
+
+/// Slot and time served by the `Clock` stub.
+pub const SLOT: u64 = 42;
+pub const NOW: i64 = 1_700_000_000;
+
+thread_local! {
+    static INVOKED: RefCell<Vec<Instruction>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Serves the sysvars the program reads and records CPIs instead of running
+/// them.
+struct SysvarStubs;
+
+impl SyscallStubs for SysvarStubs {
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            slot: SLOT,
+            unix_timestamp: NOW,
+            ..Clock::default()
+        };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        _account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        INVOKED.with(|invoked| invoked.borrow_mut().push(instruction.clone()));
+        Ok(())
+    }
+}
+
+pub fn install_stubs() {
+    set_syscall_stubs(Box::new(SysvarStubs));
+}
+
+/// Events of type `T` the program has sent itself through `emit_cpi!` on
+/// this thread.
+pub fn emitted<T: AnchorDeserialize + Discriminator>() -> Vec<T> {
+    INVOKED.with(|invoked| {
+        invoked
+            .borrow()
+            .iter()
+            .filter(|ix| ix.program_id == nlov::ID)
+            .filter_map(|ix| {
+                let data = ix.data.strip_prefix(&EVENT_IX_TAG_LE[..])?;
+                let data = data.strip_prefix(&T::DISCRIMINATOR[..])?;
+                T::deserialize(&mut &data[..]).ok()
+            })
+            .collect()
+    })
+}
+
+/// Every CPI the program has made on this thread, event self-CPIs included.
+pub fn invoked() -> Vec<Instruction> {
+    INVOKED.with(|invoked| invoked.borrow().clone())
+}
+
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    pub executable: bool,
+}
+
+impl TestAccount {
+    fn new(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+        TestAccount {
+            key,
+            lamports: 1_000_000_000,
+            data,
+            owner,
+            is_signer: false,
+            is_writable: true,
+            executable: false,
+        }
+    }
+
+    /// A wallet owned by the system program.
+    pub fn wallet(key: Pubkey) -> Self {
+        Self::new(key, system_program::ID, Vec::new())
+    }
+
+    pub fn signer(key: Pubkey) -> Self {
+        TestAccount {
+            is_signer: true,
+            ..Self::wallet(key)
+        }
+    }
+
+    pub fn program(id: Pubkey) -> Self {
+        TestAccount {
+            is_writable: false,
+            executable: true,
+            ..Self::new(id, Pubkey::default(), Vec::new())
+        }
+    }
+
+    /// A program-owned account holding `value` in a `space`-byte body.
+    pub fn anchor<T: AccountSerialize>(key: Pubkey, value: &T, space: usize) -> Self {
+        let mut data = vec![0; 8 + space];
+        value.try_serialize(&mut &mut data[..]).unwrap();
+        Self::new(key, nlov::ID, data)
+    }
+
+    /// A funded, program-owned account that `init` has not written yet.
+    pub fn uninitialized(key: Pubkey, space: usize) -> Self {
+        Self::new(key, nlov::ID, vec![0; 8 + space])
+    }
+
+    pub fn mint(key: Pubkey) -> Self {
+        let mint = spl_token::state::Mint {
+            mint_authority: COption::None,
+            supply: 500_000_000,
+            decimals: 9,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        mint.pack_into_slice(&mut data);
+        Self::new(key, spl_token::ID, data)
+    }
+
+    pub fn token_account(key: Pubkey, mint: Pubkey, owner: Pubkey) -> Self {
+        let account = spl_token::state::Account {
+            mint,
+            owner,
+            amount: 1_000_000,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        account.pack_into_slice(&mut data);
+        Self::new(key, spl_token::ID, data)
+    }
+
+    /// The same account, owned by `program` instead.
+    pub fn owned_by(self, program: Pubkey) -> Self {
+        TestAccount {
+            owner: program,
+            ..self
+        }
+    }
+
+    /// An optional account left out, which clients pass as the program id.
+    pub fn absent() -> Self {
+        Self::program(nlov::ID)
+    }
+
+    /// The emission schedule of `mint`, with the TGE long past and room for
+    /// the whole supply from the start.
+    pub fn emission_schedule(mint: &Pubkey) -> Self {
+        Self::emission_schedule_with_tge(mint, 1)
+    }
+
+    /// The emission schedule of `mint` with its TGE at `tge_timestamp`, or
+    /// unscheduled for 0, and room for the whole supply from the start.
+    pub fn emission_schedule_with_tge(mint: &Pubkey, tge_timestamp: i64) -> Self {
+        Self::sale_emission_schedule(mint, &Pubkey::default(), tge_timestamp)
+    }
+
+    /// The emission schedule of `mint` following the TGE of `presale`,
+    /// currently at `tge_timestamp`.
+    pub fn sale_emission_schedule(mint: &Pubkey, presale: &Pubkey, tge_timestamp: i64) -> Self {
+        let (key, bump) =
+            Pubkey::find_program_address(&[b"emission_schedule", mint.as_ref()], &nlov::ID);
+        let schedule = EmissionSchedule {
+            mint: *mint,
+            presale: *presale,
+            tge_timestamp,
+            ceilings: vec![u64::MAX],
+            emitted: 0,
+            bump,
+        };
+        Self::anchor(key, &schedule, EmissionSchedule::INIT_SPACE)
+    }
+
+    /// The emission schedule address of `mint` while none is set up.
+    pub fn no_emission_schedule(mint: &Pubkey) -> Self {
+        let key = Pubkey::find_program_address(&[b"emission_schedule", mint.as_ref()], &nlov::ID).0;
+        TestAccount {
+            lamports: 0,
+            ..Self::wallet(key)
+        }
+    }
+
+    /// The PDA that signs for the category vaults of `mint`.
+    pub fn category_vault_authority(mint: &Pubkey) -> Self {
+        let key =
+            Pubkey::find_program_address(&[b"category_vault_authority", mint.as_ref()], &nlov::ID)
+                .0;
+        Self::wallet(key)
+    }
+
+    pub fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            self.is_signer,
+            self.is_writable,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            self.executable,
+            0,
+        )
+    }
+}
+
+/// The `event_authority` and `program` accounts `#[event_cpi]` appends.
+pub fn event_cpi_accounts() -> [TestAccount; 2] {
+    let authority = Pubkey::find_program_address(&[b"__event_authority"], &nlov::ID).0;
+    [
+        TestAccount {
+            is_writable: false,
+            ..TestAccount::wallet(authority)
+        },
+        TestAccount::program(nlov::ID),
+    ]
+}
+
+pub fn infos(accounts: &mut [TestAccount]) -> Vec<AccountInfo<'_>> {
+    accounts.iter_mut().map(TestAccount::info).collect()
+}
+
+/// A current-version sale in `phase`, everything else at its default.
+pub fn presale(phase: SalePhase) -> Presale {
+    Presale {
+        version: PRESALE_VERSION,
+        phase,
+        ..Presale::default()
+    }
+}
+
+/// The `Presale` an account holds now.
+pub fn stored_presale(account: &TestAccount) -> Presale {
+    Presale::try_deserialize(&mut &account.data[..]).unwrap()
+}
+
+/// A current-version record of `user` with `nlov_allocated` and nothing
+/// claimed yet.
+pub fn user_info(user: Pubkey, nlov_allocated: u64) -> UserInfo {
+    UserInfo {
+        version: USER_INFO_VERSION,
+        user,
+        nlov_allocated,
+        ..UserInfo::default()
+    }
+}
+
+pub fn user_info_key(presale: &Pubkey, user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"user_info", presale.as_ref(), user.as_ref()], &nlov::ID).0
+}
+
+/// `info` stored at its address for `presale`.
+pub fn user_info_account(presale: &Pubkey, info: &UserInfo) -> TestAccount {
+    TestAccount::anchor(
+        user_info_key(presale, &info.user),
+        info,
+        UserInfo::INIT_SPACE,
+    )
+}
+
+/// A grant of `total_amount` on the standard terms of `category`, nothing
+/// released yet.
+pub fn vesting_schedule(category: VestingCategory, total_amount: u64) -> VestingSchedule {
+    let terms = category.terms();
+    VestingSchedule {
+        mint: Pubkey::new_unique(),
+        id: 0,
+        beneficiary: Pubkey::new_unique(),
+        category,
+        escrow: Pubkey::new_unique(),
+        total_amount,
+        released_amount: 0,
+        tge_bps: terms.tge_bps,
+        cliff_duration: terms.cliff_months as i64 * VESTING_MONTH,
+        linear_duration: terms.linear_months as i64 * VESTING_MONTH,
+        bump: 0,
+        revocable: false,
+        revoked_at: 0,
+        step_period: 0,
+        step_bps: Vec::new(),
+    }
+}
+
+pub fn claim_tokens(accounts: &mut [TestAccount]) -> Result<()> {
+    install_stubs();
+    let infos = infos(accounts);
+    let mut bumps = ClaimTokensBumps::default();
+    let mut ix = ClaimTokens::try_accounts(
+        &nlov::ID,
+        &mut &infos[..],
+        &[],
+        &mut bumps,
+        &mut BTreeSet::new(),
+    )?;
+    neurolov_presale::claim_tokens(Context::new(&nlov::ID, &mut ix, &[], bumps))?;
+    ix.exit(&nlov::ID)
+}
+
+pub fn claim_refund(accounts: &mut [TestAccount]) -> Result<()> {
+    install_stubs();
+    let infos = infos(accounts);
+    let mut bumps = ClaimRefundBumps::default();
+    let mut ix = ClaimRefund::try_accounts(
+        &nlov::ID,
+        &mut &infos[..],
+        &[],
+        &mut bumps,
+        &mut BTreeSet::new(),
+    )?;
+    neurolov_presale::claim_refund(Context::new(&nlov::ID, &mut ix, &[], bumps))?;
+    ix.exit(&nlov::ID)
+}
+
+/// Runs `release` on a vesting schedule.
+pub fn release(accounts: &mut [TestAccount]) -> Result<()> {
+    install_stubs();
+    let infos = infos(accounts);
+    let mut bumps = ReleaseBumps::default();
+    let mut ix = Release::try_accounts(
+        &nlov::ID,
+        &mut &infos[..],
+        &[],
+        &mut bumps,
+        &mut BTreeSet::new(),
+    )?;
+    neurolov_presale::release(Context::new(&nlov::ID, &mut ix, &[], bumps))?;
+    ix.exit(&nlov::ID)
+}
+
+pub fn pause(accounts: &mut [TestAccount]) -> Result<()> {
+    install_stubs();
+    let infos = infos(accounts);
+    let mut bumps = PauseUnpauseBumps::default();
+    let mut ix = PauseUnpause::try_accounts(
+        &nlov::ID,
+        &mut &infos[..],
+        &[],
+        &mut bumps,
+        &mut BTreeSet::new(),
+    )?;
+    neurolov_presale::pause(
+        Context::new(&nlov::ID, &mut ix, &[], bumps),
+        "incident".to_string(),
+    )?;
+    ix.exit(&nlov::ID)
+}
+
+pub fn set_tge(accounts: &mut [TestAccount], tge_timestamp: i64) -> Result<()> {
+    schedule_tge(accounts, neurolov_presale::set_tge, tge_timestamp)
+}
+
+pub fn postpone_tge(accounts: &mut [TestAccount], tge_timestamp: i64) -> Result<()> {
+    schedule_tge(accounts, neurolov_presale::postpone_tge, tge_timestamp)
+}
+
+/// Runs `handler`, which takes the `SetTge` accounts.
+fn schedule_tge(
+    accounts: &mut [TestAccount],
+    handler: fn(Context<SetTge>, i64) -> Result<()>,
+    tge_timestamp: i64,
+) -> Result<()> {
+    install_stubs();
+    let infos = infos(accounts);
+    let mut bumps = SetTgeBumps::default();
+    let mut ix = SetTge::try_accounts(
+        &nlov::ID,
+        &mut &infos[..],
+        &[],
+        &mut bumps,
+        &mut BTreeSet::new(),
+    )?;
+    handler(Context::new(&nlov::ID, &mut ix, &[], bumps), tge_timestamp)?;
+    ix.exit(&nlov::ID)
+}
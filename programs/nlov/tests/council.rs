@@ -0,0 +1,506 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::{Context, Pubkey};
+    use anchor_lang::{AccountDeserialize, Accounts, AccountsExit, Space};
+    use anchor_spl::token::spl_token;
+    use nlov::council::{AdminAction, AdminProposal, Council, MAX_COUNCIL_MEMBERS};
+    use nlov::emission::EmissionSchedule;
+    use nlov::neurolov_presale;
+    use nlov::{
+        AdminUpdate, AdminUpdateBumps, ErrorCode, PhaseTransition, PhaseTransitionBumps, Presale,
+        ProposalCreated, ProposalExecuted, Propose, ProposeBumps, SalePhase, SweepUnsoldTokens,
+        SweepUnsoldTokensBumps, UnsoldTokensSwept,
+    };
+
+    use crate::common::{
+        emitted, event_cpi_accounts, infos, install_stubs, pause, presale, set_tge, TestAccount,
+        NOW,
+    };
+
+    const PRESALE: usize = 0;
+    const TGE: i64 = NOW + 86_400;
+
+    fn council(members: &[Pubkey], threshold: u8) -> Council {
+        let mut council = Council::default();
+        council.configure(members.to_vec(), threshold).unwrap();
+        council
+    }
+
+    fn proposal(council: Pubkey, action: AdminAction, approvals: u16) -> AdminProposal {
+        AdminProposal {
+            council,
+            id: 0,
+            proposer: Pubkey::new_unique(),
+            action,
+            approvals,
+            expires_at: NOW + 3_600,
+            executed: false,
+            bump: 0,
+        }
+    }
+
+    fn members(count: usize) -> Vec<Pubkey> {
+        (0..count).map(|_| Pubkey::new_unique()).collect()
+    }
+
+    /// Accounts for `set_tge` on a sale governed by a 2-of-3 council, in
+    /// declaration order. The first member signs; `approvals` is the proposal's
+    /// approval bitmap.
+    fn set_tge_accounts(action: AdminAction, approvals: u16) -> Vec<TestAccount> {
+        let key = Pubkey::new_unique();
+        let council_key = Pubkey::new_unique();
+        let members = members(3);
+        let presale = Presale {
+            end_time: NOW,
+            owner: Pubkey::new_unique(),
+            council: council_key,
+            ..presale(SalePhase::Pending)
+        };
+        let mut accounts = vec![
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+            TestAccount::signer(members[0]),
+            TestAccount::anchor(council_key, &council(&members, 2), Council::INIT_SPACE),
+            TestAccount::anchor(
+                Pubkey::new_unique(),
+                &proposal(council_key, action, approvals),
+                AdminProposal::INIT_SPACE,
+            ),
+            TestAccount::absent(),
+            TestAccount::sale_emission_schedule(&presale.token_mint, &key, 0),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    /// Accounts for `cancel_presale` on a pending sale governed by a 2-of-3
+    /// council, signed by the owner without a proposal.
+    fn cancel_accounts() -> Vec<TestAccount> {
+        let owner = Pubkey::new_unique();
+        let presale = Presale {
+            owner,
+            council: Pubkey::new_unique(),
+            ..presale(SalePhase::Pending)
+        };
+        let mut accounts = vec![
+            TestAccount::anchor(Pubkey::new_unique(), &presale, Presale::INIT_SPACE),
+            TestAccount::signer(owner),
+            TestAccount::anchor(
+                presale.council,
+                &council(&members(3), 2),
+                Council::INIT_SPACE,
+            ),
+            TestAccount::absent(),
+            TestAccount::absent(),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn cancel_presale(accounts: &mut [TestAccount]) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = AdminUpdateBumps::default();
+        let mut ix = AdminUpdate::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::cancel_presale(Context::new(&nlov::ID, &mut ix, &[], bumps))?;
+        ix.exit(&nlov::ID)
+    }
+
+    /// Accounts for `start_sale` or `pause` on an open, pending sale governed
+    /// by a 2-of-3 council whose first member signs.
+    fn phase_accounts(action: AdminAction, approvals: u16) -> Vec<TestAccount> {
+        let council_key = Pubkey::new_unique();
+        let members = members(3);
+        let presale = Presale {
+            start_time: NOW - 60,
+            end_time: NOW + 60,
+            owner: Pubkey::new_unique(),
+            council: council_key,
+            ..presale(SalePhase::Pending)
+        };
+        let mut accounts = vec![
+            TestAccount::anchor(Pubkey::new_unique(), &presale, Presale::INIT_SPACE),
+            TestAccount::signer(members[0]),
+            TestAccount::anchor(council_key, &council(&members, 2), Council::INIT_SPACE),
+            TestAccount::anchor(
+                Pubkey::new_unique(),
+                &proposal(council_key, action, approvals),
+                AdminProposal::INIT_SPACE,
+            ),
+            TestAccount::absent(),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn start_sale(accounts: &mut [TestAccount]) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = PhaseTransitionBumps::default();
+        let mut ix = PhaseTransition::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::start_sale(Context::new(&nlov::ID, &mut ix, &[], bumps))?;
+        ix.exit(&nlov::ID)
+    }
+
+    /// Accounts for `sweep_unsold_tokens` on a finalized sale with 600_000 of
+    /// its 1_000_000 tokens sold, governed by a 2-of-3 council whose first
+    /// member signs. The presale token account holds the full supply.
+    fn sweep_accounts(action: AdminAction, approvals: u16) -> Vec<TestAccount> {
+        let key = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let council_key = Pubkey::new_unique();
+        let members = members(3);
+        let presale = Presale {
+            owner: Pubkey::new_unique(),
+            council: council_key,
+            token_mint: mint,
+            presale_token_account: Pubkey::new_unique(),
+            presale_supply: 1_000_000,
+            total_contributed: 600_000,
+            ..presale(SalePhase::Finalized)
+        };
+        let mut accounts = vec![
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+            TestAccount::token_account(presale.presale_token_account, mint, key),
+            TestAccount::token_account(Pubkey::new_unique(), mint, presale.owner),
+            TestAccount::signer(members[0]),
+            TestAccount::program(spl_token::ID),
+            TestAccount::anchor(council_key, &council(&members, 2), Council::INIT_SPACE),
+            TestAccount::anchor(
+                Pubkey::new_unique(),
+                &proposal(council_key, action, approvals),
+                AdminProposal::INIT_SPACE,
+            ),
+            TestAccount::absent(),
+            TestAccount::absent(),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn sweep(accounts: &mut [TestAccount], amount: u64) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = SweepUnsoldTokensBumps::default();
+        let mut ix = SweepUnsoldTokens::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::sweep_unsold_tokens(
+            Context::new(&nlov::ID, &mut ix, &[], bumps),
+            amount,
+        )?;
+        ix.exit(&nlov::ID)
+    }
+
+    #[test]
+    fn test_configure_council_rejects_bad_members() {
+        let mut council = Council::default();
+        let three = members(3);
+        for (members, threshold) in [
+            (Vec::new(), 1),
+            (three.clone(), 0),
+            (three.clone(), 4),
+            (vec![three[0], three[1], three[0]], 2),
+            (vec![three[0], Pubkey::default()], 1),
+            (members(MAX_COUNCIL_MEMBERS + 1), 1),
+        ] {
+            assert_eq!(
+                council.configure(members, threshold).unwrap_err(),
+                Error::from(ErrorCode::InvalidCouncil)
+            );
+        }
+
+        council.configure(three.clone(), 3).unwrap();
+        assert_eq!(council.member_index(&three[2]).unwrap(), 2);
+        assert_eq!(
+            council.member_index(&Pubkey::new_unique()).unwrap_err(),
+            Error::from(ErrorCode::NotCouncilMember)
+        );
+    }
+
+    #[test]
+    fn test_approve_and_revoke() {
+        let action = AdminAction::Withdraw { amount: 1_000 };
+        let mut proposal = proposal(Pubkey::new_unique(), action, 0);
+
+        proposal.approve(0, NOW).unwrap();
+        proposal.approve(2, NOW).unwrap();
+        assert_eq!(proposal.approval_count(), 2);
+        assert_eq!(
+            proposal.approve(2, NOW).unwrap_err(),
+            Error::from(ErrorCode::AlreadyApproved)
+        );
+
+        proposal.revoke(2).unwrap();
+        assert_eq!(proposal.approval_count(), 1);
+        assert_eq!(
+            proposal.revoke(1).unwrap_err(),
+            Error::from(ErrorCode::NotApproved)
+        );
+        assert_eq!(
+            proposal.approve(1, proposal.expires_at).unwrap_err(),
+            Error::from(ErrorCode::ProposalExpired)
+        );
+    }
+
+    #[test]
+    fn test_execute_proposal() {
+        let council = council(&members(3), 2);
+        let action = AdminAction::Withdraw { amount: 1_000 };
+        let mut proposal = proposal(Pubkey::new_unique(), action, 0b001);
+
+        assert_eq!(
+            proposal.execute(&council, &action, NOW).unwrap_err(),
+            Error::from(ErrorCode::ProposalNotApproved)
+        );
+        proposal.approve(1, NOW).unwrap();
+        assert_eq!(
+            proposal
+                .execute(&council, &AdminAction::Withdraw { amount: 1_001 }, NOW)
+                .unwrap_err(),
+            Error::from(ErrorCode::ProposalMismatch)
+        );
+        assert_eq!(
+            proposal
+                .execute(&council, &action, proposal.expires_at)
+                .unwrap_err(),
+            Error::from(ErrorCode::ProposalExpired)
+        );
+
+        proposal.execute(&council, &action, NOW).unwrap();
+        assert!(proposal.executed);
+        assert_eq!(
+            proposal.execute(&council, &action, NOW).unwrap_err(),
+            Error::from(ErrorCode::ProposalAlreadyExecuted)
+        );
+        assert_eq!(
+            proposal.revoke(0).unwrap_err(),
+            Error::from(ErrorCode::ProposalAlreadyExecuted)
+        );
+    }
+
+    #[test]
+    fn test_set_tge_spends_approved_proposal() {
+        let action = AdminAction::SetTge { tge_timestamp: TGE };
+        let mut accounts = set_tge_accounts(action, 0b011);
+        set_tge(&mut accounts, TGE).unwrap();
+
+        let presale = Presale::try_deserialize(&mut &accounts[PRESALE].data[..]).unwrap();
+        assert_eq!(presale.tge_timestamp, TGE);
+        let proposal = AdminProposal::try_deserialize(&mut &accounts[3].data[..]).unwrap();
+        assert!(proposal.executed);
+
+        let events = emitted::<ProposalExecuted>();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].proposal, accounts[3].key);
+        assert_eq!(events[0].executor, accounts[1].key);
+        assert_eq!(events[0].action, action);
+    }
+
+    #[test]
+    fn test_set_tge_needs_threshold() {
+        let mut accounts = set_tge_accounts(AdminAction::SetTge { tge_timestamp: TGE }, 0b001);
+        assert_eq!(
+            set_tge(&mut accounts, TGE).unwrap_err(),
+            Error::from(ErrorCode::ProposalNotApproved)
+        );
+    }
+
+    #[test]
+    fn test_set_tge_rejects_other_action() {
+        let action = AdminAction::SetTge {
+            tge_timestamp: TGE + 1,
+        };
+        let mut accounts = set_tge_accounts(action, 0b111);
+        assert_eq!(
+            set_tge(&mut accounts, TGE).unwrap_err(),
+            Error::from(ErrorCode::ProposalMismatch)
+        );
+    }
+
+    #[test]
+    fn test_owner_alone_cannot_act_under_council() {
+        let mut accounts = set_tge_accounts(AdminAction::SetTge { tge_timestamp: TGE }, 0b111);
+        let presale = Presale::try_deserialize(&mut &accounts[PRESALE].data[..]).unwrap();
+        accounts[1] = TestAccount::signer(presale.owner);
+        accounts[2] = TestAccount::absent();
+        accounts[3] = TestAccount::absent();
+        assert_eq!(
+            set_tge(&mut accounts, TGE).unwrap_err(),
+            Error::from(ErrorCode::ProposalRequired)
+        );
+    }
+
+    #[test]
+    fn test_propose_counts_proposer_approval() {
+        install_stubs();
+        let key = Pubkey::new_unique();
+        let members = members(3);
+        let (council_key, _) = Pubkey::find_program_address(&[b"council", key.as_ref()], &nlov::ID);
+        let presale = Presale {
+            council: council_key,
+            ..presale(SalePhase::Pending)
+        };
+        let proposal_key = Pubkey::find_program_address(
+            &[b"proposal", council_key.as_ref(), &0u64.to_le_bytes()],
+            &nlov::ID,
+        )
+        .0;
+        let mut accounts = vec![
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+            TestAccount::anchor(council_key, &council(&members, 2), Council::INIT_SPACE),
+            TestAccount::uninitialized(proposal_key, AdminProposal::INIT_SPACE),
+            TestAccount::signer(members[1]),
+            TestAccount::program(anchor_lang::system_program::ID),
+        ];
+        accounts.extend(event_cpi_accounts());
+
+        let action = AdminAction::Withdraw { amount: 1_000 };
+        {
+            let infos = infos(&mut accounts);
+            let mut bumps = ProposeBumps::default();
+            let mut ix = Propose::try_accounts(
+                &nlov::ID,
+                &mut &infos[..],
+                &[],
+                &mut bumps,
+                &mut BTreeSet::new(),
+            )
+            .unwrap();
+            neurolov_presale::propose(Context::new(&nlov::ID, &mut ix, &[], bumps), action, 3_600)
+                .unwrap();
+            ix.exit(&nlov::ID).unwrap();
+        }
+
+        let proposal = AdminProposal::try_deserialize(&mut &accounts[2].data[..]).unwrap();
+        assert_eq!(proposal.action, action);
+        assert_eq!(proposal.approvals, 0b010);
+        assert_eq!(proposal.expires_at, NOW + 3_600);
+        let council = Council::try_deserialize(&mut &accounts[1].data[..]).unwrap();
+        assert_eq!(council.proposal_count, 1);
+        assert_eq!(emitted::<ProposalCreated>()[0].proposal, proposal_key);
+    }
+
+    #[test]
+    fn test_cancel_presale_needs_proposal() {
+        let mut accounts = cancel_accounts();
+        assert_eq!(
+            cancel_presale(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::ProposalRequired)
+        );
+    }
+
+    #[test]
+    fn test_start_sale_spends_approved_proposal() {
+        let mut accounts = phase_accounts(AdminAction::StartSale, 0b011);
+        start_sale(&mut accounts).unwrap();
+
+        let presale = Presale::try_deserialize(&mut &accounts[PRESALE].data[..]).unwrap();
+        assert_eq!(presale.phase, SalePhase::Live);
+        assert_eq!(
+            emitted::<ProposalExecuted>()[0].action,
+            AdminAction::StartSale
+        );
+    }
+
+    #[test]
+    fn test_start_sale_rejects_other_action() {
+        let mut accounts = phase_accounts(AdminAction::EndSale, 0b011);
+        assert_eq!(
+            start_sale(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::ProposalMismatch)
+        );
+    }
+
+    #[test]
+    fn test_pause_needs_proposal() {
+        let mut accounts = phase_accounts(AdminAction::Pause, 0b011);
+        let presale = Presale::try_deserialize(&mut &accounts[PRESALE].data[..]).unwrap();
+        accounts[1] = TestAccount::signer(presale.owner);
+        accounts[3] = TestAccount::absent();
+        assert_eq!(
+            pause(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::ProposalRequired)
+        );
+
+        let mut accounts = phase_accounts(AdminAction::Pause, 0b011);
+        pause(&mut accounts).unwrap();
+        let presale = Presale::try_deserialize(&mut &accounts[PRESALE].data[..]).unwrap();
+        assert!(presale.is_paused);
+    }
+
+    #[test]
+    fn test_sweep_unsold_tokens() {
+        let mut accounts =
+            sweep_accounts(AdminAction::SweepUnsoldTokens { amount: 400_000 }, 0b011);
+        sweep(&mut accounts, 400_000).unwrap();
+
+        let presale = Presale::try_deserialize(&mut &accounts[PRESALE].data[..]).unwrap();
+        assert_eq!(presale.presale_supply, 600_000);
+        let proposal = AdminProposal::try_deserialize(&mut &accounts[6].data[..]).unwrap();
+        assert!(proposal.executed);
+        let events = emitted::<UnsoldTokensSwept>();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].destination, accounts[2].key);
+        assert_eq!(events[0].amount, 400_000);
+    }
+
+    #[test]
+    fn test_sweep_genesis_tokens_counts_against_ceiling() {
+        let mut accounts =
+            sweep_accounts(AdminAction::SweepUnsoldTokens { amount: 400_000 }, 0b011);
+        let mut presale = Presale::try_deserialize(&mut &accounts[PRESALE].data[..]).unwrap();
+        presale.genesis_funded = true;
+        accounts[PRESALE] =
+            TestAccount::anchor(accounts[PRESALE].key, &presale, Presale::INIT_SPACE);
+        assert_eq!(
+            sweep(&mut accounts, 400_000).unwrap_err(),
+            Error::from(ErrorCode::MissingEmissionSchedule)
+        );
+
+        accounts[8] = TestAccount::emission_schedule(&presale.token_mint);
+        sweep(&mut accounts, 400_000).unwrap();
+        let emission = EmissionSchedule::try_deserialize(&mut &accounts[8].data[..]).unwrap();
+        assert_eq!(emission.emitted, 400_000);
+    }
+
+    #[test]
+    fn test_sweep_leaves_allocations() {
+        let action = AdminAction::SweepUnsoldTokens { amount: 400_001 };
+        let mut accounts = sweep_accounts(action, 0b011);
+        assert_eq!(
+            sweep(&mut accounts, 400_001).unwrap_err(),
+            Error::from(ErrorCode::InsufficientFunds)
+        );
+    }
+
+    #[test]
+    fn test_sweep_needs_threshold() {
+        let action = AdminAction::SweepUnsoldTokens { amount: 1 };
+        let mut accounts = sweep_accounts(action, 0b001);
+        assert_eq!(
+            sweep(&mut accounts, 1).unwrap_err(),
+            Error::from(ErrorCode::ProposalNotApproved)
+        );
+    }
+}
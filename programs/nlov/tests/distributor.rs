@@ -0,0 +1,315 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::{Context, Pubkey};
+    use anchor_lang::{AccountDeserialize, Accounts, AccountsExit, Space};
+    use anchor_spl::token::spl_token::instruction::TokenInstruction;
+    use nlov::distributor::{ClaimStatus, MerkleDistributor};
+    use nlov::merkle;
+    use nlov::vesting::{VestingCategory, VestingLedger, VESTING_MONTH};
+    use nlov::{
+        neurolov_presale, ClaimDistribution, ClaimDistributionBumps, CreateDistributor,
+        CreateDistributorBumps, DistributionClaimed, DistributorCreated, ErrorCode, ProtocolConfig,
+    };
+
+    use crate::common::{
+        emitted, event_cpi_accounts, infos, install_stubs, invoked, TestAccount, NOW,
+    };
+
+    const NLOV: u64 = 1_000_000_000;
+    const SCHEDULE_ID: u64 = 5;
+
+    /// Three community beneficiaries. `a` and `b` share a parent; `c` moves
+    /// up to the root unchanged.
+    struct Tree {
+        entries: [(Pubkey, u64); 3],
+        root: [u8; 32],
+    }
+
+    impl Tree {
+        fn new() -> Self {
+            let entries = [
+                (Pubkey::new_unique(), 100 * NLOV),
+                (Pubkey::new_unique(), 200 * NLOV),
+                (Pubkey::new_unique(), 300 * NLOV),
+            ];
+            let [a, b, c] = entries.map(|(key, amount)| merkle::leaf(&key, amount, SCHEDULE_ID));
+            let root = merkle::parent(&merkle::parent(&a, &b), &c);
+            Tree { entries, root }
+        }
+
+        fn leaf(&self, i: usize) -> [u8; 32] {
+            let (key, amount) = self.entries[i];
+            merkle::leaf(&key, amount, SCHEDULE_ID)
+        }
+
+        fn proof(&self, i: usize) -> Vec<[u8; 32]> {
+            match i {
+                0 => vec![self.leaf(1), self.leaf(2)],
+                1 => vec![self.leaf(0), self.leaf(2)],
+                _ => vec![merkle::parent(&self.leaf(0), &self.leaf(1))],
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let tree = Tree::new();
+        for i in 0..3 {
+            assert!(merkle::verify(&tree.proof(i), &tree.root, tree.leaf(i)));
+        }
+        let (key, amount) = tree.entries[0];
+        // Another amount, distributor or proof does not verify.
+        assert!(!merkle::verify(
+            &tree.proof(0),
+            &tree.root,
+            merkle::leaf(&key, amount + 1, SCHEDULE_ID)
+        ));
+        assert!(!merkle::verify(
+            &tree.proof(0),
+            &tree.root,
+            merkle::leaf(&key, amount, SCHEDULE_ID + 1)
+        ));
+        assert!(!merkle::verify(&tree.proof(1), &tree.root, tree.leaf(0)));
+    }
+
+    /// Accounts for `create_distributor` by the protocol authority, as the
+    /// ledger's next schedule.
+    fn create_accounts(ledger: &VestingLedger) -> Vec<TestAccount> {
+        let mint = ledger.mint;
+        let authority = Pubkey::new_unique();
+        let ledger_key =
+            Pubkey::find_program_address(&[b"vesting_ledger", mint.as_ref()], &nlov::ID).0;
+        let distributor_key = Pubkey::find_program_address(
+            &[
+                b"distributor",
+                mint.as_ref(),
+                &ledger.schedule_count.to_le_bytes(),
+            ],
+            &nlov::ID,
+        )
+        .0;
+        let (config_key, bump) = Pubkey::find_program_address(&[b"protocol_config"], &nlov::ID);
+        let config = ProtocolConfig {
+            authority,
+            bump,
+            ..ProtocolConfig::default()
+        };
+        let mut accounts = vec![
+            TestAccount::anchor(ledger_key, ledger, VestingLedger::INIT_SPACE),
+            TestAccount::uninitialized(distributor_key, MerkleDistributor::INIT_SPACE),
+            TestAccount::token_account(Pubkey::new_unique(), mint, distributor_key),
+            TestAccount::mint(mint),
+            TestAccount::token_account(Pubkey::new_unique(), mint, authority),
+            TestAccount::anchor(config_key, &config, ProtocolConfig::INIT_SPACE),
+            TestAccount::signer(authority),
+            TestAccount::program(anchor_spl::token::ID),
+            TestAccount::program(anchor_lang::system_program::ID),
+            TestAccount::category_vault_authority(&mint),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn create(
+        accounts: &mut [TestAccount],
+        root: [u8; 32],
+        category: VestingCategory,
+        total_amount: u64,
+    ) -> anchor_lang::Result<()> {
+        install_stubs();
+        let terms = category.terms();
+        let infos = infos(accounts);
+        let mut bumps = CreateDistributorBumps::default();
+        let mut ix = CreateDistributor::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::create_distributor(
+            Context::new(&nlov::ID, &mut ix, &[], bumps),
+            root,
+            category,
+            total_amount,
+            terms.tge_bps,
+            terms.cliff_months as i64 * VESTING_MONTH,
+            terms.linear_months as i64 * VESTING_MONTH,
+            0,
+            Vec::new(),
+        )?;
+        ix.exit(&nlov::ID)
+    }
+
+    #[test]
+    fn test_create_distributor() {
+        let tree = Tree::new();
+        let ledger = VestingLedger {
+            mint: Pubkey::new_unique(),
+            schedule_count: SCHEDULE_ID,
+            ..VestingLedger::default()
+        };
+        let mut accounts = create_accounts(&ledger);
+        create(
+            &mut accounts,
+            tree.root,
+            VestingCategory::Community,
+            600 * NLOV,
+        )
+        .unwrap();
+
+        let distributor = MerkleDistributor::try_deserialize(&mut &accounts[1].data[..]).unwrap();
+        assert_eq!(distributor.id, SCHEDULE_ID);
+        assert_eq!(distributor.root, tree.root);
+        assert_eq!(distributor.escrow, accounts[2].key);
+        assert_eq!(distributor.tge_bps, 500);
+        let ledger = VestingLedger::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+        assert_eq!(ledger.schedule_count, SCHEDULE_ID + 1);
+        assert_eq!(
+            ledger.allocated[VestingCategory::Community as usize],
+            600 * NLOV
+        );
+
+        let transfers: Vec<_> = invoked()
+            .into_iter()
+            .filter(|ix| ix.program_id == anchor_spl::token::ID)
+            .collect();
+        assert_eq!(
+            TokenInstruction::unpack(&transfers[0].data).unwrap(),
+            TokenInstruction::Transfer { amount: 600 * NLOV }
+        );
+        assert_eq!(emitted::<DistributorCreated>()[0].root, tree.root);
+    }
+
+    #[test]
+    fn test_create_distributor_requires_protocol_authority() {
+        let ledger = VestingLedger {
+            mint: Pubkey::new_unique(),
+            ..VestingLedger::default()
+        };
+        let mut accounts = create_accounts(&ledger);
+        let stranger = Pubkey::new_unique();
+        accounts[4] = TestAccount::token_account(Pubkey::new_unique(), ledger.mint, stranger);
+        accounts[6] = TestAccount::signer(stranger);
+        assert_eq!(
+            create(&mut accounts, [0; 32], VestingCategory::Community, NLOV).unwrap_err(),
+            Error::from(ErrorCode::Unauthorized)
+        );
+    }
+
+    /// Community tokens: 5% at TGE, no cliff, 48 months linear. TGE was
+    /// 12 months ago.
+    fn distributor(tree: &Tree) -> MerkleDistributor {
+        let terms = VestingCategory::Community.terms();
+        MerkleDistributor {
+            mint: Pubkey::new_unique(),
+            id: SCHEDULE_ID,
+            category: VestingCategory::Community,
+            escrow: Pubkey::new_unique(),
+            root: tree.root,
+            total_amount: 600 * NLOV,
+            claimed_amount: 0,
+            tge_bps: terms.tge_bps,
+            cliff_duration: 0,
+            linear_duration: terms.linear_months as i64 * VESTING_MONTH,
+            bump: 0,
+            step_period: 0,
+            step_bps: Vec::new(),
+        }
+    }
+
+    fn claim_accounts(distributor: &MerkleDistributor, beneficiary: Pubkey) -> Vec<TestAccount> {
+        let key = Pubkey::new_unique();
+        let status_key = Pubkey::find_program_address(
+            &[b"claim_status", key.as_ref(), beneficiary.as_ref()],
+            &nlov::ID,
+        )
+        .0;
+        let mut accounts = vec![
+            TestAccount::anchor(key, distributor, MerkleDistributor::INIT_SPACE),
+            // Created on the first claim; init_if_needed takes it as is here.
+            TestAccount::anchor(status_key, &ClaimStatus::default(), ClaimStatus::INIT_SPACE),
+            TestAccount::token_account(distributor.escrow, distributor.mint, key),
+            TestAccount::token_account(Pubkey::new_unique(), distributor.mint, beneficiary),
+            TestAccount::signer(beneficiary),
+            TestAccount::program(anchor_spl::token::ID),
+            TestAccount::program(anchor_lang::system_program::ID),
+            TestAccount::emission_schedule_with_tge(&distributor.mint, NOW - 12 * VESTING_MONTH),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn claim(
+        accounts: &mut [TestAccount],
+        total_amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = ClaimDistributionBumps::default();
+        let mut ix = ClaimDistribution::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::claim_distribution(
+            Context::new(&nlov::ID, &mut ix, &[], bumps),
+            total_amount,
+            proof,
+        )?;
+        ix.exit(&nlov::ID)
+    }
+
+    #[test]
+    fn test_claim_distribution() {
+        let tree = Tree::new();
+        let (beneficiary, total) = tree.entries[2];
+        let mut accounts = claim_accounts(&distributor(&tree), beneficiary);
+        claim(&mut accounts, total, tree.proof(2)).unwrap();
+
+        // 5% at TGE, then 12 of the 48 months of the rest.
+        let vested = 15 * NLOV + 285 * NLOV / 4;
+        let status = ClaimStatus::try_deserialize(&mut &accounts[1].data[..]).unwrap();
+        assert_eq!(status.beneficiary, beneficiary);
+        assert_eq!(status.claimed_amount, vested);
+        let stored = MerkleDistributor::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+        assert_eq!(stored.claimed_amount, vested);
+        let event = &emitted::<DistributionClaimed>()[0];
+        assert_eq!(event.amount, vested);
+        assert_eq!(event.claimed_amount, vested);
+
+        // The claim status now holds what was taken.
+        assert_eq!(
+            claim(&mut accounts, total, tree.proof(2)).unwrap_err(),
+            Error::from(ErrorCode::NothingToClaim)
+        );
+    }
+
+    #[test]
+    fn test_claim_rejects_wrong_leaf() {
+        let tree = Tree::new();
+        let (beneficiary, total) = tree.entries[0];
+        let mut accounts = claim_accounts(&distributor(&tree), beneficiary);
+        for (amount, proof) in [(total * 2, tree.proof(0)), (total, tree.proof(1))] {
+            assert_eq!(
+                claim(&mut accounts, amount, proof).unwrap_err(),
+                Error::from(ErrorCode::InvalidProof)
+            );
+        }
+
+        // Someone else cannot claim with the beneficiary's proof.
+        let mut accounts = claim_accounts(&distributor(&tree), Pubkey::new_unique());
+        assert_eq!(
+            claim(&mut accounts, total, tree.proof(0)).unwrap_err(),
+            Error::from(ErrorCode::InvalidProof)
+        );
+    }
+}
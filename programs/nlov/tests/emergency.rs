@@ -0,0 +1,241 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::{Context, Pubkey};
+    use anchor_lang::{AccountDeserialize, Accounts, AccountsExit, Space};
+    use nlov::{
+        neurolov_presale, DeclareEmergency, DeclareEmergencyBumps, EmergencyDeclared, ErrorCode,
+        LiquidityLock, Presale, SalePhase,
+    };
+
+    use crate::common::{
+        emitted, event_cpi_accounts, infos, install_stubs, pause, presale, TestAccount,
+    };
+
+    const VAULT: usize = 1;
+    const LOCK: usize = 2;
+
+    #[test]
+    fn test_declare_emergency_from_every_open_phase() {
+        for phase in SalePhase::ALL {
+            let mut presale = Presale {
+                total_contributed: 100,
+                ..presale(phase)
+            };
+            let result = presale.declare_emergency(1_000);
+            if phase == SalePhase::Closed {
+                assert_eq!(result.unwrap_err(), Error::from(ErrorCode::InvalidPhase));
+                assert!(!presale.emergency);
+                continue;
+            }
+            assert!(result.is_ok(), "{phase:?}");
+            assert!(presale.emergency);
+            assert_eq!(presale.phase, SalePhase::Refunding);
+            assert_eq!(presale.emergency_vault_snapshot, 1_000);
+            assert_eq!(presale.emergency_outstanding_snapshot, 100);
+        }
+    }
+
+    #[test]
+    fn test_declare_emergency_is_one_way() {
+        let mut presale = Presale {
+            total_contributed: 100,
+            ..presale(SalePhase::Live)
+        };
+        presale.declare_emergency(1_000).unwrap();
+        assert_eq!(
+            presale.declare_emergency(1_000).unwrap_err(),
+            Error::from(ErrorCode::EmergencyDeclared)
+        );
+    }
+
+    #[test]
+    fn test_declare_emergency_while_paused() {
+        let mut presale = Presale {
+            total_contributed: 100,
+            ..presale(SalePhase::Claiming)
+        };
+        presale.is_paused = true;
+        presale.declare_emergency(1_000).unwrap();
+        assert!(!presale.is_paused);
+        assert!(presale.require_phase(&[SalePhase::Refunding]).is_ok());
+    }
+
+    #[test]
+    fn test_emergency_snapshot_excludes_claimed_allocation() {
+        let mut presale = Presale {
+            total_contributed: 1_000,
+            ..presale(SalePhase::Claiming)
+        };
+        presale.total_claimed = 600;
+        presale.declare_emergency(4_000).unwrap();
+        assert_eq!(presale.emergency_outstanding_snapshot, 400);
+        assert_eq!(presale.emergency_refund_amount(100).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn test_emergency_refund_is_pro_rata() {
+        let mut presale = Presale {
+            total_contributed: 300,
+            ..presale(SalePhase::Finalized)
+        };
+        presale.declare_emergency(1_000).unwrap();
+
+        let shares = [100, 150, 50].map(|nlov| presale.emergency_refund_amount(nlov).unwrap());
+        assert_eq!(shares, [333, 500, 166]);
+        assert!(shares.iter().sum::<u64>() <= 1_000);
+    }
+
+    #[test]
+    fn test_emergency_refund_requires_emergency() {
+        let presale = Presale {
+            total_contributed: 300,
+            ..presale(SalePhase::Refunding)
+        };
+        assert_eq!(
+            presale.emergency_refund_amount(100).unwrap_err(),
+            Error::from(ErrorCode::InvalidPhase)
+        );
+    }
+
+    #[test]
+    fn test_emergency_refund_large_values() {
+        let mut presale = Presale {
+            total_contributed: u64::MAX,
+            ..presale(SalePhase::Live)
+        };
+        presale.declare_emergency(u64::MAX).unwrap();
+        assert_eq!(presale.emergency_refund_amount(u64::MAX).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn test_emergency_blocks_normal_transitions() {
+        let mut presale = Presale {
+            total_contributed: 100,
+            ..presale(SalePhase::Live)
+        };
+        presale.declare_emergency(1_000).unwrap();
+        for next in [SalePhase::Live, SalePhase::Ended, SalePhase::Claiming] {
+            assert_eq!(
+                presale.transition(next).unwrap_err(),
+                Error::from(ErrorCode::InvalidPhaseTransition)
+            );
+        }
+    }
+
+    /// Accounts for `pause` by the owner of `presale`.
+    fn pause_accounts(presale: &Presale) -> Vec<TestAccount> {
+        let mut accounts = vec![
+            TestAccount::anchor(Pubkey::new_unique(), presale, Presale::INIT_SPACE),
+            TestAccount::signer(presale.owner),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::absent(),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    #[test]
+    fn test_emergency_cannot_be_paused() {
+        let mut presale = Presale {
+            owner: Pubkey::new_unique(),
+            total_contributed: 100,
+            ..presale(SalePhase::Live)
+        };
+        pause(&mut pause_accounts(&presale)).unwrap();
+
+        presale.declare_emergency(1_000).unwrap();
+        assert_eq!(
+            pause(&mut pause_accounts(&presale)).unwrap_err(),
+            Error::from(ErrorCode::EmergencyDeclared)
+        );
+    }
+
+    /// Accounts for `declare_emergency` by the owner of a sale in `phase`,
+    /// whose liquidity lock holds `lock_sol` lamports on top of its rent.
+    fn declare_accounts(phase: SalePhase, lock_sol: u64) -> Vec<TestAccount> {
+        let key = Pubkey::new_unique();
+        let presale = Presale {
+            owner: Pubkey::new_unique(),
+            total_withdrawn: lock_sol,
+            total_contributed: 100,
+            ..presale(phase)
+        };
+        let vault = Pubkey::find_program_address(&[b"vault", key.as_ref()], &nlov::ID).0;
+        let (lock_key, bump) =
+            Pubkey::find_program_address(&[b"liquidity_lock", key.as_ref()], &nlov::ID);
+        let lock = LiquidityLock {
+            presale: key,
+            sol_amount: lock_sol,
+            bump,
+            ..LiquidityLock::default()
+        };
+        let mut lock_account = TestAccount::anchor(lock_key, &lock, LiquidityLock::INIT_SPACE);
+        lock_account.lamports += lock_sol;
+        let mut accounts = vec![
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+            TestAccount::wallet(vault),
+            lock_account,
+            TestAccount::signer(presale.owner),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::absent(),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn declare_emergency(accounts: &mut [TestAccount]) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = DeclareEmergencyBumps::default();
+        let mut ix = DeclareEmergency::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::declare_emergency(Context::new(&nlov::ID, &mut ix, &[], bumps))?;
+        ix.exit(&nlov::ID)
+    }
+
+    #[test]
+    fn test_declare_emergency_returns_locked_liquidity() {
+        let mut accounts = declare_accounts(SalePhase::Claiming, 300);
+        let (vault_before, lock_before) = (accounts[VAULT].lamports, accounts[LOCK].lamports);
+        declare_emergency(&mut accounts).unwrap();
+
+        assert_eq!(accounts[VAULT].lamports, vault_before + 300);
+        assert_eq!(accounts[LOCK].lamports, lock_before - 300);
+        let lock = LiquidityLock::try_deserialize(&mut &accounts[LOCK].data[..]).unwrap();
+        assert_eq!(lock.sol_amount, 0);
+
+        let presale = Presale::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+        assert_eq!(presale.emergency_vault_snapshot, vault_before + 300);
+        assert_eq!(presale.total_withdrawn, 0);
+        let event = &emitted::<EmergencyDeclared>()[0];
+        assert_eq!(event.vault_balance, vault_before + 300);
+        assert_eq!(event.liquidity_returned, 300);
+    }
+
+    #[test]
+    fn test_declare_emergency_after_finalize_needs_lock() {
+        let mut accounts = declare_accounts(SalePhase::Finalized, 300);
+        accounts[LOCK] = TestAccount::absent();
+        assert_eq!(
+            declare_emergency(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::MissingLiquidityLock)
+        );
+
+        // Before finalization there is no lock to pass.
+        let mut accounts = declare_accounts(SalePhase::Live, 0);
+        accounts[LOCK] = TestAccount::absent();
+        declare_emergency(&mut accounts).unwrap();
+    }
+}
@@ -0,0 +1,308 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::{Context, Pubkey};
+    use anchor_lang::{AccountDeserialize, Accounts, AccountsExit, Space};
+    use nlov::emission::{EmissionSchedule, MAX_EMISSION_MONTHS, PUBLISHED_CEILINGS, TOTAL_SUPPLY};
+    use nlov::vesting::{VestingCategory, VestingSchedule, VESTING_MONTH};
+    use nlov::{
+        neurolov_presale, EmissionScheduleUpdated, ErrorCode, ExtendEmissionSchedule,
+        ExtendEmissionScheduleBumps, InitializeEmissionSchedule, InitializeEmissionScheduleBumps,
+        Presale, ProtocolConfig,
+    };
+
+    use crate::common::{
+        emitted, event_cpi_accounts, infos, install_stubs, release, vesting_schedule, TestAccount,
+        NOW,
+    };
+
+    const NLOV: u64 = 1_000_000_000;
+    const TGE: i64 = NOW - 100;
+
+    fn published() -> Vec<u64> {
+        PUBLISHED_CEILINGS
+            .iter()
+            .map(|tokens| tokens * NLOV)
+            .collect()
+    }
+
+    fn emission_schedule(ceilings: Vec<u64>) -> EmissionSchedule {
+        EmissionSchedule {
+            mint: Pubkey::new_unique(),
+            presale: Pubkey::new_unique(),
+            tge_timestamp: TGE,
+            ceilings,
+            emitted: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_published_curve_matches_categories() {
+        let categories = VestingCategory::ALL.map(|category| category.terms());
+        assert_eq!(
+            categories.iter().map(|terms| terms.tokens).sum::<u64>(),
+            TOTAL_SUPPLY
+        );
+        // The ceiling at TGE is exactly what the categories unlock at TGE.
+        let at_tge: u64 = categories
+            .iter()
+            .map(|terms| terms.tokens * terms.tge_bps as u64 / 10_000)
+            .sum();
+        assert_eq!(PUBLISHED_CEILINGS[0], at_tge);
+        EmissionSchedule::validate(&published(), 9).unwrap();
+    }
+
+    #[test]
+    fn test_ceiling_by_month() {
+        let schedule = emission_schedule(published());
+        assert_eq!(schedule.ceiling(TGE - 1), 0);
+        assert_eq!(schedule.ceiling(TGE), 39_500_000 * NLOV);
+        assert_eq!(schedule.ceiling(TGE + VESTING_MONTH - 1), 39_500_000 * NLOV);
+        assert_eq!(schedule.ceiling(TGE + VESTING_MONTH), 43_000_000 * NLOV);
+        assert_eq!(schedule.ceiling(TGE + 2 * VESTING_MONTH), 47_200_000 * NLOV);
+        assert_eq!(schedule.ceiling(TGE + 6 * VESTING_MONTH), 84_200_000 * NLOV);
+        // The last ceiling holds until the schedule is extended.
+        assert_eq!(schedule.ceiling(i64::MAX), 84_200_000 * NLOV);
+    }
+
+    #[test]
+    fn test_record() {
+        let mut schedule = emission_schedule(vec![100, 150]);
+        schedule.record(60, TGE).unwrap();
+        schedule.record(40, TGE).unwrap();
+        assert_eq!(
+            schedule.record(1, TGE).unwrap_err(),
+            Error::from(ErrorCode::EmissionCeilingExceeded)
+        );
+        assert_eq!(schedule.emitted, 100);
+        schedule.record(50, TGE + VESTING_MONTH).unwrap();
+        assert_eq!(
+            emission_schedule(vec![100]).record(1, TGE - 1).unwrap_err(),
+            Error::from(ErrorCode::EmissionCeilingExceeded)
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let supply = TOTAL_SUPPLY * NLOV;
+        EmissionSchedule::validate(&[0, supply], 9).unwrap();
+        let too_long = vec![1; MAX_EMISSION_MONTHS + 1];
+        for ceilings in [&[][..], &[2, 1], &[supply + 1], &too_long] {
+            assert_eq!(
+                EmissionSchedule::validate(ceilings, 9).unwrap_err(),
+                Error::from(ErrorCode::InvalidEmissionSchedule)
+            );
+        }
+    }
+
+    /// Accounts for `initialize_emission_schedule` by the protocol authority,
+    /// following a sale of `mint` whose TGE is set.
+    fn initialize_accounts(mint: Pubkey) -> Vec<TestAccount> {
+        let authority = Pubkey::new_unique();
+        let key = Pubkey::find_program_address(&[b"emission_schedule", mint.as_ref()], &nlov::ID).0;
+        let (config_key, bump) = Pubkey::find_program_address(&[b"protocol_config"], &nlov::ID);
+        let config = ProtocolConfig {
+            authority,
+            bump,
+            ..ProtocolConfig::default()
+        };
+        let presale = Presale {
+            token_mint: mint,
+            tge_timestamp: TGE,
+            ..Presale::default()
+        };
+        let mut accounts = vec![
+            TestAccount::uninitialized(key, EmissionSchedule::INIT_SPACE),
+            TestAccount::mint(mint),
+            TestAccount::anchor(Pubkey::new_unique(), &presale, Presale::INIT_SPACE),
+            TestAccount::anchor(config_key, &config, ProtocolConfig::INIT_SPACE),
+            TestAccount::signer(authority),
+            TestAccount::program(anchor_lang::system_program::ID),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn initialize(accounts: &mut [TestAccount], ceilings: Vec<u64>) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = InitializeEmissionScheduleBumps::default();
+        let mut ix = InitializeEmissionSchedule::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::initialize_emission_schedule(
+            Context::new(&nlov::ID, &mut ix, &[], bumps),
+            ceilings,
+        )?;
+        ix.exit(&nlov::ID)
+    }
+
+    fn extend(accounts: &mut [TestAccount], ceilings: Vec<u64>) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = ExtendEmissionScheduleBumps::default();
+        let mut ix = ExtendEmissionSchedule::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::extend_emission_schedule(
+            Context::new(&nlov::ID, &mut ix, &[], bumps),
+            ceilings,
+        )?;
+        ix.exit(&nlov::ID)
+    }
+
+    #[test]
+    fn test_initialize_and_extend() {
+        let mint = Pubkey::new_unique();
+        let mut accounts = initialize_accounts(mint);
+        initialize(&mut accounts, published()).unwrap();
+
+        let stored = EmissionSchedule::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+        assert_eq!(stored.mint, mint);
+        assert_eq!(stored.presale, accounts[2].key);
+        assert_eq!(stored.tge_timestamp, TGE);
+        assert_eq!(stored.ceilings, published());
+        let event = &emitted::<EmissionScheduleUpdated>()[0];
+        assert_eq!(event.tge_timestamp, TGE);
+        assert_eq!(event.ceilings, published());
+
+        // Extending reuses the first accounts, minus the sale and the system
+        // program.
+        accounts.remove(5);
+        accounts.remove(2);
+        extend(&mut accounts, vec![90_000_000 * NLOV]).unwrap();
+        let stored = EmissionSchedule::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+        assert_eq!(stored.ceilings.len(), PUBLISHED_CEILINGS.len() + 1);
+        assert_eq!(
+            emitted::<EmissionScheduleUpdated>()[1].ceilings,
+            stored.ceilings
+        );
+
+        // Published months cannot be undercut, and nothing is a no-op.
+        for ceilings in [vec![80_000_000 * NLOV], vec![]] {
+            assert_eq!(
+                extend(&mut accounts, ceilings).unwrap_err(),
+                Error::from(ErrorCode::InvalidEmissionSchedule)
+            );
+        }
+    }
+
+    #[test]
+    fn test_initialize_requires_sale_of_mint() {
+        let mut accounts = initialize_accounts(Pubkey::new_unique());
+        let other_sale = Presale {
+            token_mint: Pubkey::new_unique(),
+            ..Presale::default()
+        };
+        accounts[2] = TestAccount::anchor(accounts[2].key, &other_sale, Presale::INIT_SPACE);
+        assert_eq!(
+            initialize(&mut accounts, published()).unwrap_err(),
+            Error::from(ErrorCode::InvalidTokenMint)
+        );
+    }
+
+    #[test]
+    fn test_follow_tge() {
+        let mut schedule = emission_schedule(published());
+        schedule.tge_timestamp = 0;
+        assert_eq!(schedule.tge(), i64::MAX);
+        assert_eq!(schedule.ceiling(NOW), 0);
+
+        // Only the linked sale moves the mint's TGE.
+        schedule.follow_tge(&Pubkey::new_unique(), TGE);
+        assert_eq!(schedule.tge(), i64::MAX);
+        let presale = schedule.presale;
+        schedule.follow_tge(&presale, TGE);
+        assert_eq!(schedule.tge(), TGE);
+        schedule.follow_tge(&presale, TGE + VESTING_MONTH);
+        assert_eq!(schedule.ceiling(TGE + VESTING_MONTH - 1), 0);
+    }
+
+    #[test]
+    fn test_initialize_requires_protocol_authority() {
+        let mut accounts = initialize_accounts(Pubkey::new_unique());
+        accounts[4] = TestAccount::signer(Pubkey::new_unique());
+        assert_eq!(
+            initialize(&mut accounts, published()).unwrap_err(),
+            Error::from(ErrorCode::Unauthorized)
+        );
+    }
+
+    /// Accounts for `release` of a liquidity schedule whose mint has an
+    /// emission schedule with `emission`.
+    fn release_accounts(emission: &EmissionSchedule) -> Vec<TestAccount> {
+        let schedule = VestingSchedule {
+            mint: emission.mint,
+            ..vesting_schedule(VestingCategory::Liquidity, 1_000 * NLOV)
+        };
+        let (emission_key, bump) = Pubkey::find_program_address(
+            &[b"emission_schedule", emission.mint.as_ref()],
+            &nlov::ID,
+        );
+        let emission = EmissionSchedule {
+            bump,
+            ..emission.clone()
+        };
+        let mut accounts = vec![
+            TestAccount::anchor(Pubkey::new_unique(), &schedule, VestingSchedule::INIT_SPACE),
+            TestAccount::token_account(schedule.escrow, schedule.mint, Pubkey::new_unique()),
+            TestAccount::token_account(Pubkey::new_unique(), schedule.mint, schedule.beneficiary),
+            TestAccount::signer(schedule.beneficiary),
+            TestAccount::program(anchor_spl::token::ID),
+            TestAccount::anchor(emission_key, &emission, EmissionSchedule::INIT_SPACE),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    #[test]
+    fn test_release_counts_against_ceiling() {
+        // 30% of the schedule, plus 100 seconds of linear release, is due.
+        let mut emission = emission_schedule(vec![500 * NLOV]);
+        emission.emitted = 100 * NLOV;
+        let mut accounts = release_accounts(&emission);
+        release(&mut accounts).unwrap();
+
+        let released = VestingSchedule::try_deserialize(&mut &accounts[0].data[..])
+            .unwrap()
+            .released_amount;
+        let stored = EmissionSchedule::try_deserialize(&mut &accounts[5].data[..]).unwrap();
+        assert_eq!(stored.emitted, 100 * NLOV + released);
+    }
+
+    #[test]
+    fn test_release_requires_emission_schedule() {
+        let emission = emission_schedule(vec![500 * NLOV]);
+        let mut accounts = release_accounts(&emission);
+        accounts[5] = TestAccount::no_emission_schedule(&emission.mint);
+        assert_eq!(
+            release(&mut accounts).unwrap_err(),
+            Error::from(anchor_lang::error::ErrorCode::AccountNotInitialized)
+                .with_account_name("emission_schedule")
+        );
+    }
+
+    #[test]
+    fn test_release_blocked_above_ceiling() {
+        let mut emission = emission_schedule(vec![500 * NLOV]);
+        emission.emitted = 300 * NLOV;
+        let mut accounts = release_accounts(&emission);
+        assert_eq!(
+            release(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::EmissionCeilingExceeded)
+        );
+    }
+}
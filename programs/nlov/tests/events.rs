@@ -0,0 +1,173 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::{Clock, Context, Pubkey};
+    use anchor_lang::{Accounts, AccountsExit, Space};
+    use nlov::neurolov_presale;
+    use nlov::{
+        ErrorCode, PauseUnpause, PauseUnpauseBumps, PhaseChanged, PhaseTransition,
+        PhaseTransitionBumps, Presale, PresalePaused, PresaleUnpaused, SalePhase,
+        MAX_PAUSE_REASON_LEN,
+    };
+
+    use crate::common::{
+        emitted, event_cpi_accounts, infos, install_stubs, presale, TestAccount, NOW, SLOT,
+    };
+
+    const OWNER: usize = 1;
+
+    /// `presale` and its signing owner, followed by the event CPI accounts.
+    fn owner_accounts(presale: &Presale) -> Vec<TestAccount> {
+        let mut accounts = vec![
+            TestAccount::anchor(Pubkey::new_unique(), presale, Presale::INIT_SPACE),
+            TestAccount::signer(presale.owner),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::absent(),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn open_presale() -> Presale {
+        Presale {
+            owner: Pubkey::new_unique(),
+            start_time: NOW - 60,
+            end_time: NOW + 60,
+            event_sequence: 7,
+            ..presale(SalePhase::Pending)
+        }
+    }
+
+    #[test]
+    fn test_next_event_header() {
+        let mut presale = open_presale();
+        let key = Pubkey::new_unique();
+        let clock = Clock {
+            slot: 9,
+            unix_timestamp: 1_234,
+            ..Clock::default()
+        };
+
+        let first = presale.next_event_header(key, &clock).unwrap();
+        let second = presale.next_event_header(key, &clock).unwrap();
+        assert_eq!(first.presale, key);
+        assert_eq!(first.slot, 9);
+        assert_eq!(first.unix_timestamp, 1_234);
+        assert_eq!((first.sequence, second.sequence), (8, 9));
+        assert_eq!(presale.event_sequence, 9);
+    }
+
+    #[test]
+    fn test_next_event_header_overflow() {
+        let mut presale = Presale {
+            event_sequence: u64::MAX,
+            ..open_presale()
+        };
+        assert_eq!(
+            presale
+                .next_event_header(Pubkey::new_unique(), &Clock::default())
+                .unwrap_err(),
+            Error::from(ErrorCode::CalculationError)
+        );
+    }
+
+    #[test]
+    fn test_pause_and_unpause_emit_actor_and_reason() {
+        install_stubs();
+        let mut accounts = owner_accounts(&open_presale());
+        let (key, owner) = (accounts[0].key, accounts[OWNER].key);
+        let infos = infos(&mut accounts);
+
+        for paused in [true, false] {
+            let mut bumps = PauseUnpauseBumps::default();
+            let mut ix = PauseUnpause::try_accounts(
+                &nlov::ID,
+                &mut &infos[..],
+                &[],
+                &mut bumps,
+                &mut BTreeSet::new(),
+            )
+            .unwrap();
+            let ctx = Context::new(&nlov::ID, &mut ix, &[], bumps);
+            if paused {
+                neurolov_presale::pause(ctx, "oracle outage".to_string()).unwrap();
+            } else {
+                neurolov_presale::unpause(ctx, "oracle restored".to_string()).unwrap();
+            }
+            assert_eq!(ix.presale.is_paused, paused);
+            // Handlers only write the account back on exit.
+            ix.exit(&nlov::ID).unwrap();
+        }
+
+        let paused = emitted::<PresalePaused>();
+        assert_eq!(paused.len(), 1);
+        assert_eq!(paused[0].actor, owner);
+        assert_eq!(paused[0].reason, "oracle outage");
+        assert_eq!(paused[0].header.presale, key);
+        assert_eq!(paused[0].header.sequence, 8);
+        assert_eq!(paused[0].header.slot, SLOT);
+        assert_eq!(paused[0].header.unix_timestamp, NOW);
+
+        let unpaused = emitted::<PresaleUnpaused>();
+        assert_eq!(unpaused.len(), 1);
+        assert_eq!(unpaused[0].actor, owner);
+        assert_eq!(unpaused[0].reason, "oracle restored");
+        assert_eq!(unpaused[0].header.sequence, 9);
+    }
+
+    #[test]
+    fn test_pause_rejects_long_reason() {
+        install_stubs();
+        let mut accounts = owner_accounts(&open_presale());
+        let infos = infos(&mut accounts);
+        let mut bumps = PauseUnpauseBumps::default();
+        let mut ix = PauseUnpause::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )
+        .unwrap();
+
+        let reason = "x".repeat(MAX_PAUSE_REASON_LEN + 1);
+        assert_eq!(
+            neurolov_presale::pause(Context::new(&nlov::ID, &mut ix, &[], bumps), reason)
+                .unwrap_err(),
+            Error::from(ErrorCode::PauseReasonTooLong)
+        );
+        assert!(emitted::<PresalePaused>().is_empty());
+    }
+
+    #[test]
+    fn test_phase_change_is_emitted_through_cpi() {
+        install_stubs();
+        let mut accounts = owner_accounts(&open_presale());
+        let key = accounts[0].key;
+        let infos = infos(&mut accounts);
+        let mut bumps = PhaseTransitionBumps::default();
+        let mut ix = PhaseTransition::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )
+        .unwrap();
+
+        neurolov_presale::start_sale(Context::new(&nlov::ID, &mut ix, &[], bumps)).unwrap();
+        assert_eq!(ix.presale.event_sequence, 8);
+
+        let events = emitted::<PhaseChanged>();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].header.presale, key);
+        assert_eq!(events[0].header.sequence, 8);
+        assert_eq!(events[0].from, SalePhase::Pending);
+        assert_eq!(events[0].to, SalePhase::Live);
+    }
+}
@@ -0,0 +1,180 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::{Accounts, Space};
+    use nlov::{
+        ErrorCode, EventHeader, Presale, SalePhase, VerifyInvariants, VerifyInvariantsBumps,
+    };
+
+    use crate::common::{event_cpi_accounts, infos, presale, TestAccount};
+
+    const SUPPLY: u64 = 1_000_000;
+
+    /// A sale that sold 100_000 NLOV for 4_000 lamports, of which 10_000 NLOV
+    /// have been claimed.
+    fn claiming_presale() -> Presale {
+        Presale {
+            presale_supply: SUPPLY,
+            token_price: 40_000_000,
+            token_decimals: 9,
+            total_contributed: 100_000,
+            total_claimed: 10_000,
+            ..presale(SalePhase::Claiming)
+        }
+    }
+
+    fn header(presale: Pubkey) -> EventHeader {
+        EventHeader {
+            presale,
+            sequence: 1,
+            slot: 0,
+            unix_timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_reconcile_balanced_books() {
+        let presale = claiming_presale();
+        let key = Pubkey::new_unique();
+        let report = presale
+            .reconcile(header(key), 4_000, SUPPLY - 10_000)
+            .unwrap();
+
+        assert_eq!(report.header.presale, key);
+        assert_eq!(report.phase, SalePhase::Claiming);
+        assert_eq!(report.required_vault_balance, 4_000);
+        assert_eq!(report.required_token_balance, SUPPLY - 10_000);
+        assert_eq!(report.unsold_supply, SUPPLY - 100_000);
+        assert_eq!(report.total_withdrawn, 0);
+    }
+
+    #[test]
+    fn test_reconcile_counts_withdrawals() {
+        let mut presale = claiming_presale();
+        presale.record_withdrawal(3_000).unwrap();
+
+        let report = presale
+            .reconcile(header(Pubkey::new_unique()), 1_000, SUPPLY - 10_000)
+            .unwrap();
+        assert_eq!(report.required_vault_balance, 1_000);
+        assert_eq!(report.total_withdrawn, 3_000);
+
+        assert_eq!(
+            presale
+                .reconcile(header(Pubkey::new_unique()), 999, SUPPLY - 10_000)
+                .map(|_| ())
+                .unwrap_err(),
+            Error::from(ErrorCode::InvariantViolation)
+        );
+    }
+
+    #[test]
+    fn test_reconcile_accepts_surplus() {
+        let mut presale = claiming_presale();
+        // A direct transfer into the vault can be withdrawn on top of the raise.
+        presale.record_withdrawal(5_000).unwrap();
+
+        let report = presale
+            .reconcile(header(Pubkey::new_unique()), 0, SUPPLY)
+            .unwrap();
+        assert_eq!(report.required_vault_balance, 0);
+        assert_eq!(report.token_balance, SUPPLY);
+    }
+
+    #[test]
+    fn test_reconcile_rejects_token_shortfall() {
+        assert_eq!(
+            claiming_presale()
+                .reconcile(header(Pubkey::new_unique()), 4_000, SUPPLY - 10_001)
+                .map(|_| ())
+                .unwrap_err(),
+            Error::from(ErrorCode::InvariantViolation)
+        );
+    }
+
+    #[test]
+    fn test_reconcile_after_emergency_refunds() {
+        let mut presale = claiming_presale();
+        presale.declare_emergency(3_600).unwrap();
+        // Outstanding 90_000 NLOV share 3_600 lamports; 30_000 NLOV were refunded.
+        let refund = presale.emergency_refund_amount(30_000).unwrap();
+        presale.total_contributed -= 30_000;
+
+        let report = presale
+            .reconcile(
+                header(Pubkey::new_unique()),
+                3_600 - refund,
+                SUPPLY - 10_000,
+            )
+            .unwrap();
+        assert_eq!(report.required_vault_balance, 2_400);
+
+        assert_eq!(
+            presale
+                .reconcile(header(Pubkey::new_unique()), 2_399, SUPPLY - 10_000)
+                .map(|_| ())
+                .unwrap_err(),
+            Error::from(ErrorCode::InvariantViolation)
+        );
+    }
+
+    #[test]
+    fn test_reconcile_rejects_inconsistent_counters() {
+        let mut presale = claiming_presale();
+        presale.total_claimed = presale.total_contributed + 1;
+        assert_eq!(
+            presale
+                .reconcile(header(Pubkey::new_unique()), u64::MAX, u64::MAX)
+                .map(|_| ())
+                .unwrap_err(),
+            Error::from(ErrorCode::InvariantViolation)
+        );
+    }
+
+    #[test]
+    fn test_record_withdrawal_overflow() {
+        let mut presale = claiming_presale();
+        presale.record_withdrawal(u64::MAX).unwrap();
+        assert_eq!(
+            presale.record_withdrawal(1).unwrap_err(),
+            Error::from(ErrorCode::CalculationError)
+        );
+        assert_eq!(presale.contributed_lamports().unwrap(), 4_000);
+    }
+
+    #[test]
+    fn test_verify_invariants_rejects_substituted_token_account() {
+        let key = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let presale = Presale {
+            token_mint: mint,
+            presale_token_account: Pubkey::new_unique(),
+            ..claiming_presale()
+        };
+        let vault = Pubkey::find_program_address(&[b"vault", key.as_ref()], &nlov::ID).0;
+        let mut accounts = vec![
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+            TestAccount::wallet(vault),
+            TestAccount::token_account(Pubkey::new_unique(), mint, key),
+        ];
+        accounts.extend(event_cpi_accounts());
+
+        let infos = infos(&mut accounts);
+        let result = VerifyInvariants::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut VerifyInvariantsBumps::default(),
+            &mut BTreeSet::new(),
+        );
+        assert_eq!(
+            result.map(|_| ()).unwrap_err(),
+            Error::from(ErrorCode::InvalidPresaleTokenAccount)
+        );
+    }
+}
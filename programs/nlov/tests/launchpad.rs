@@ -0,0 +1,106 @@
+#[cfg(test)]
+mod tests {
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::Pubkey;
+    use nlov::{ErrorCode, Presale, ProtocolConfig, MAX_PLATFORM_FEE_BPS};
+
+    #[test]
+    fn test_set_fees() {
+        let mut config = ProtocolConfig::default();
+        let fee_recipient = Pubkey::new_unique();
+        config
+            .set_fees(fee_recipient, 2_000_000_000, MAX_PLATFORM_FEE_BPS)
+            .unwrap();
+        assert_eq!(config.fee_recipient, fee_recipient);
+        assert_eq!(config.listing_fee, 2_000_000_000);
+        assert_eq!(config.platform_fee_bps, MAX_PLATFORM_FEE_BPS);
+    }
+
+    #[test]
+    fn test_set_fees_rejects_bad_values() {
+        let mut config = ProtocolConfig::default();
+        assert_eq!(
+            config
+                .set_fees(Pubkey::new_unique(), 0, MAX_PLATFORM_FEE_BPS + 1)
+                .unwrap_err(),
+            Error::from(ErrorCode::InvalidPlatformFee)
+        );
+        assert_eq!(
+            config.set_fees(Pubkey::default(), 0, 100).unwrap_err(),
+            Error::from(ErrorCode::InvalidFeeRecipient)
+        );
+    }
+
+    #[test]
+    fn test_platform_fee() {
+        let presale = Presale {
+            platform_fee_bps: 250,
+            ..Presale::default()
+        };
+        assert_eq!(presale.platform_fee(1_000_000).unwrap(), 25_000);
+        assert_eq!(presale.platform_fee(39).unwrap(), 0);
+        assert_eq!(Presale::default().platform_fee(u64::MAX).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_signer_seeds_derive_presale_address() {
+        let creator = Pubkey::new_unique();
+        let token_mint = Pubkey::new_unique();
+        let (address, bump) = Pubkey::find_program_address(
+            &[b"presale", creator.as_ref(), token_mint.as_ref()],
+            &nlov::ID,
+        );
+        let presale = Presale {
+            creator,
+            token_mint,
+            bump,
+            ..Presale::default()
+        };
+        let seeds = presale.signer_seeds();
+        assert_eq!(
+            Pubkey::create_program_address(&seeds, &nlov::ID).unwrap(),
+            address
+        );
+    }
+
+    #[test]
+    fn test_signer_seeds_for_singleton_presale() {
+        let (address, bump) = Pubkey::find_program_address(&[b"presale"], &nlov::ID);
+        let presale = Presale {
+            token_mint: Pubkey::new_unique(),
+            bump,
+            ..Presale::default()
+        };
+        let seeds = presale.signer_seeds();
+        assert_eq!(
+            Pubkey::create_program_address(&seeds, &nlov::ID).unwrap(),
+            address
+        );
+    }
+
+    #[test]
+    fn test_partner_sale_prices_from_token_price() {
+        // A 6-decimal partner token at 0.5 SOL.
+        let mut presale = Presale {
+            token_price: 500_000_000,
+            token_decimals: 6,
+            ..Presale::default()
+        };
+        assert_eq!(
+            presale.tokens_for_lamports(1_000_000_000).unwrap(),
+            2_000_000
+        );
+        // Allocations round down, never up.
+        assert_eq!(presale.tokens_for_lamports(999).unwrap(), 1);
+        assert_eq!(presale.tokens_for_lamports(499).unwrap(), 0);
+
+        presale.total_contributed = 2_000_001;
+        assert_eq!(presale.contributed_lamports().unwrap(), 1_000_000_500);
+
+        presale.token_price = 0;
+        assert_eq!(
+            presale.tokens_for_lamports(1).unwrap_err(),
+            Error::from(ErrorCode::InvalidTokenPrice)
+        );
+    }
+}
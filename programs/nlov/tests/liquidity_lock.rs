@@ -0,0 +1,258 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::{Context, Pubkey};
+    use anchor_lang::{AccountDeserialize, Accounts, AccountsExit, Space};
+    use anchor_spl::token::spl_token;
+    use nlov::emission::EmissionSchedule;
+    use nlov::{
+        neurolov_presale, ErrorCode, LiquidityLock, LiquidityReleased, Presale, ReleaseLiquidity,
+        ReleaseLiquidityBumps, SalePhase,
+    };
+
+    use crate::common::{
+        emitted, event_cpi_accounts, infos, install_stubs, presale, TestAccount, NOW,
+    };
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    #[test]
+    fn test_configure_liquidity_lock() {
+        let mut presale = Presale::default();
+        presale
+            .configure_liquidity_lock(3_000, 1_000_000, 180 * DAY)
+            .unwrap();
+        assert!(presale.liquidity_lock_configured);
+        assert_eq!(presale.liquidity_lock_sol(1_000_000).unwrap(), 300_000);
+    }
+
+    #[test]
+    fn test_configure_liquidity_lock_only_once() {
+        let mut presale = Presale::default();
+        presale
+            .configure_liquidity_lock(3_000, 1_000_000, 180 * DAY)
+            .unwrap();
+        assert_eq!(
+            presale.configure_liquidity_lock(0, 0, DAY).unwrap_err(),
+            Error::from(ErrorCode::LiquidityLockAlreadyConfigured)
+        );
+    }
+
+    #[test]
+    fn test_configure_liquidity_lock_rejects_bad_parameters() {
+        let mut presale = Presale::default();
+        assert_eq!(
+            presale
+                .configure_liquidity_lock(10_001, 0, DAY)
+                .unwrap_err(),
+            Error::from(ErrorCode::InvalidLiquidityLock)
+        );
+        assert_eq!(
+            presale.configure_liquidity_lock(3_000, 0, 0).unwrap_err(),
+            Error::from(ErrorCode::InvalidLiquidityLock)
+        );
+        assert!(!presale.liquidity_lock_configured);
+    }
+
+    #[test]
+    fn test_liquidity_lock_sol_requires_configuration() {
+        assert_eq!(
+            Presale::default().liquidity_lock_sol(1_000).unwrap_err(),
+            Error::from(ErrorCode::LiquidityLockNotConfigured)
+        );
+    }
+
+    #[test]
+    fn test_liquidity_lock_sol_full_vault() {
+        let mut presale = Presale::default();
+        presale.configure_liquidity_lock(10_000, 0, DAY).unwrap();
+        assert_eq!(presale.liquidity_lock_sol(u64::MAX).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn test_release_after_unlock() {
+        let mut lock = LiquidityLock {
+            unlock_at: 1_000,
+            ..LiquidityLock::default()
+        };
+        assert_eq!(
+            lock.release(999).unwrap_err(),
+            Error::from(ErrorCode::LiquidityStillLocked)
+        );
+        lock.release(1_000).unwrap();
+        assert!(lock.released);
+        assert_eq!(
+            lock.release(2_000).unwrap_err(),
+            Error::from(ErrorCode::LiquidityAlreadyReleased)
+        );
+    }
+
+    #[test]
+    fn test_lp_deposits_share_one_mint() {
+        let mut lock = LiquidityLock::default();
+        let lp_mint = Pubkey::new_unique();
+        lock.record_lp_deposit(lp_mint, 500).unwrap();
+        lock.record_lp_deposit(lp_mint, 250).unwrap();
+        assert_eq!(lock.lp_mint, lp_mint);
+        assert_eq!(lock.lp_amount, 750);
+
+        assert_eq!(
+            lock.record_lp_deposit(Pubkey::new_unique(), 100)
+                .unwrap_err(),
+            Error::from(ErrorCode::LpMintMismatch)
+        );
+        assert_eq!(
+            lock.record_lp_deposit(lp_mint, 0).unwrap_err(),
+            Error::from(ErrorCode::InvalidAmount)
+        );
+    }
+
+    #[test]
+    fn test_lp_deposit_after_release() {
+        let mut lock = LiquidityLock::default();
+        lock.release(0).unwrap();
+        assert_eq!(
+            lock.record_lp_deposit(Pubkey::new_unique(), 100)
+                .unwrap_err(),
+            Error::from(ErrorCode::LiquidityAlreadyReleased)
+        );
+    }
+
+    /// Accounts for `release_liquidity` on a matured lock holding NLOV only.
+    fn release_accounts(emergency: bool) -> Vec<TestAccount> {
+        let presale_key = Pubkey::new_unique();
+        let token_mint = Pubkey::new_unique();
+        let beneficiary = Pubkey::new_unique();
+        let presale = Presale {
+            token_mint,
+            emergency,
+            ..presale(SalePhase::Pending)
+        };
+        let (lock_key, bump) =
+            Pubkey::find_program_address(&[b"liquidity_lock", presale_key.as_ref()], &nlov::ID);
+        let lock = LiquidityLock {
+            presale: presale_key,
+            beneficiary,
+            unlock_at: NOW - 1,
+            bump,
+            genesis_funded: true,
+            ..LiquidityLock::default()
+        };
+        let lock_token_key =
+            Pubkey::find_program_address(&[b"liquidity_lock_nlov", lock_key.as_ref()], &nlov::ID).0;
+        let mut accounts = vec![
+            TestAccount::anchor(presale_key, &presale, Presale::INIT_SPACE),
+            TestAccount::anchor(lock_key, &lock, LiquidityLock::INIT_SPACE),
+            TestAccount::wallet(beneficiary),
+            TestAccount::token_account(lock_token_key, token_mint, lock_key),
+            TestAccount::token_account(Pubkey::new_unique(), token_mint, beneficiary),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::program(spl_token::ID),
+            TestAccount::emission_schedule(&token_mint),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn release_liquidity(accounts: &mut [TestAccount]) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = ReleaseLiquidityBumps::default();
+        let mut ix = ReleaseLiquidity::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::release_liquidity(Context::new(&nlov::ID, &mut ix, &[], bumps))?;
+        ix.exit(&nlov::ID)
+    }
+
+    #[test]
+    fn test_release_liquidity() {
+        let mut accounts = release_accounts(false);
+        release_liquidity(&mut accounts).unwrap();
+        assert_eq!(emitted::<LiquidityReleased>()[0].nlov_amount, 1_000_000);
+
+        let emission = EmissionSchedule::try_deserialize(&mut &accounts[8].data[..]).unwrap();
+        assert_eq!(emission.emitted, 1_000_000);
+    }
+
+    #[test]
+    fn test_release_liquidity_counts_against_ceiling() {
+        let mut accounts = release_accounts(false);
+        let mint = Presale::try_deserialize(&mut &accounts[0].data[..])
+            .unwrap()
+            .token_mint;
+        let mut emission = EmissionSchedule::try_deserialize(&mut &accounts[8].data[..]).unwrap();
+        emission.ceilings = vec![999_999];
+        accounts[8] = TestAccount::anchor(accounts[8].key, &emission, EmissionSchedule::INIT_SPACE);
+        assert_eq!(emission.mint, mint);
+        assert_eq!(
+            release_liquidity(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::EmissionCeilingExceeded)
+        );
+    }
+
+    #[test]
+    fn test_release_partner_liquidity_needs_no_schedule() {
+        let mut accounts = release_accounts(false);
+        accounts[8] = TestAccount::absent();
+        assert_eq!(
+            release_liquidity(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::MissingEmissionSchedule)
+        );
+
+        let mut accounts = release_accounts(false);
+        let mut lock = LiquidityLock::try_deserialize(&mut &accounts[1].data[..]).unwrap();
+        lock.genesis_funded = false;
+        accounts[1] = TestAccount::anchor(accounts[1].key, &lock, LiquidityLock::INIT_SPACE);
+        accounts[8] = TestAccount::absent();
+        release_liquidity(&mut accounts).unwrap();
+        assert_eq!(emitted::<LiquidityReleased>()[0].nlov_amount, 1_000_000);
+    }
+
+    #[test]
+    fn test_release_liquidity_keeps_sol_in_emergency() {
+        let mut accounts = release_accounts(true);
+        let mut lock = LiquidityLock::try_deserialize(&mut &accounts[1].data[..]).unwrap();
+        lock.sol_amount = 1_000;
+        accounts[1] = TestAccount::anchor(accounts[1].key, &lock, LiquidityLock::INIT_SPACE);
+        assert_eq!(
+            release_liquidity(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::EmergencyDeclared)
+        );
+    }
+
+    #[test]
+    fn test_release_liquidity_after_emergency_releases_tokens() {
+        let mut accounts = release_accounts(true);
+        release_liquidity(&mut accounts).unwrap();
+        let event = &emitted::<LiquidityReleased>()[0];
+        assert_eq!((event.sol_amount, event.nlov_amount), (0, 1_000_000));
+    }
+
+    #[test]
+    fn test_reclaim_sol() {
+        let mut lock = LiquidityLock {
+            sol_amount: 1_000,
+            ..LiquidityLock::default()
+        };
+        assert_eq!(lock.reclaim_sol(), 1_000);
+        assert_eq!(lock.sol_amount, 0);
+        assert_eq!(lock.reclaim_sol(), 0);
+
+        let mut released = LiquidityLock {
+            sol_amount: 1_000,
+            released: true,
+            ..LiquidityLock::default()
+        };
+        assert_eq!(released.reclaim_sol(), 0);
+    }
+}
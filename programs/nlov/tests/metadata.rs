@@ -0,0 +1,156 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::{Context, Pubkey};
+    use anchor_lang::{AccountDeserialize, Accounts, AccountsExit, Space};
+    use anchor_spl::token_2022::spl_token_2022;
+    use anchor_spl::token_interface::spl_token_metadata_interface::instruction::{
+        TokenMetadataInstruction, UpdateField,
+    };
+    use anchor_spl::token_interface::spl_token_metadata_interface::state::Field;
+    use nlov::metadata::{MetadataAuthority, MetadataField};
+    use nlov::{
+        neurolov_presale, ErrorCode, MetadataAuthorityChanged, SetMetadataAuthority,
+        SetMetadataAuthorityBumps, TokenMetadataUpdated, UpdateTokenMetadata,
+        UpdateTokenMetadataBumps,
+    };
+
+    use crate::common::{emitted, event_cpi_accounts, infos, install_stubs, invoked, TestAccount};
+
+    /// Accounts for `update_token_metadata`, in declaration order, with the
+    /// role held by a new signer.
+    fn update_accounts() -> Vec<TestAccount> {
+        let mint = Pubkey::new_unique();
+        let (key, bump) =
+            Pubkey::find_program_address(&[b"metadata_authority", mint.as_ref()], &nlov::ID);
+        let role = MetadataAuthority {
+            mint,
+            authority: Pubkey::new_unique(),
+            bump,
+        };
+        let mut accounts = vec![
+            TestAccount::mint(mint).owned_by(spl_token_2022::ID),
+            TestAccount::anchor(key, &role, MetadataAuthority::INIT_SPACE),
+            TestAccount::signer(role.authority),
+            TestAccount::program(spl_token_2022::ID),
+            TestAccount::program(anchor_lang::system_program::ID),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn update(
+        accounts: &mut [TestAccount],
+        field: MetadataField,
+        value: &str,
+    ) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = UpdateTokenMetadataBumps::default();
+        let mut ix = UpdateTokenMetadata::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::update_token_metadata(
+            Context::new(&nlov::ID, &mut ix, &[], bumps),
+            field,
+            value.to_string(),
+        )?;
+        ix.exit(&nlov::ID)
+    }
+
+    /// `update_accounts` as `set_metadata_authority` takes them.
+    fn set_authority_accounts() -> Vec<TestAccount> {
+        let mut accounts = update_accounts();
+        accounts.drain(3..5);
+        accounts.remove(0);
+        accounts
+    }
+
+    fn set_authority(
+        accounts: &mut [TestAccount],
+        new_authority: Pubkey,
+    ) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = SetMetadataAuthorityBumps::default();
+        let mut ix = SetMetadataAuthority::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::set_metadata_authority(
+            Context::new(&nlov::ID, &mut ix, &[], bumps),
+            new_authority,
+        )?;
+        ix.exit(&nlov::ID)
+    }
+
+    #[test]
+    fn test_update_token_metadata() {
+        let mut accounts = update_accounts();
+        update(
+            &mut accounts,
+            MetadataField::Uri,
+            "https://neurolov.ai/v2.json",
+        )
+        .unwrap();
+
+        // Signed by the role's PDA, which is the metadata update authority.
+        let ix = invoked()
+            .into_iter()
+            .find(|ix| ix.program_id == spl_token_2022::ID)
+            .unwrap();
+        assert_eq!(ix.accounts[0].pubkey, accounts[0].key);
+        assert_eq!(ix.accounts[1].pubkey, accounts[1].key);
+        assert!(ix.accounts[1].is_signer);
+        assert_eq!(
+            TokenMetadataInstruction::unpack(&ix.data).unwrap(),
+            TokenMetadataInstruction::UpdateField(UpdateField {
+                field: Field::Uri,
+                value: "https://neurolov.ai/v2.json".to_string(),
+            })
+        );
+        let event = &emitted::<TokenMetadataUpdated>()[0];
+        assert_eq!(event.mint, accounts[0].key);
+        assert_eq!(event.field, MetadataField::Uri);
+    }
+
+    #[test]
+    fn test_update_token_metadata_requires_metadata_authority() {
+        let mut accounts = update_accounts();
+        accounts[2] = TestAccount::signer(Pubkey::new_unique());
+        assert_eq!(
+            update(&mut accounts, MetadataField::Name, "Other").unwrap_err(),
+            Error::from(ErrorCode::Unauthorized)
+        );
+    }
+
+    #[test]
+    fn test_set_metadata_authority() {
+        let mut accounts = set_authority_accounts();
+        let (previous, governance) = (accounts[1].key, Pubkey::new_unique());
+        set_authority(&mut accounts, governance).unwrap();
+
+        let role = MetadataAuthority::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+        assert_eq!(role.authority, governance);
+        let event = &emitted::<MetadataAuthorityChanged>()[0];
+        assert_eq!(event.previous_authority, previous);
+        assert_eq!(event.new_authority, governance);
+
+        // The previous holder has lost the role.
+        assert_eq!(
+            set_authority(&mut accounts, previous).unwrap_err(),
+            Error::from(ErrorCode::Unauthorized)
+        );
+    }
+}
@@ -0,0 +1,171 @@
+#[cfg(test)]
+mod tests {
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::{
+        AccountDeserialize, AccountSerialize, AnchorSerialize, Discriminator, Space,
+    };
+    use nlov::migration::{
+        upgrade_presale, upgrade_user_info, PresaleV1, UserInfoV1, PRESALE_V1_SPACE,
+        USER_INFO_V1_SPACE, V1_TOKEN_PRICE,
+    };
+    use nlov::{
+        ErrorCode, Presale, SalePhase, UserInfo, NLOV_PER_LAMPORT, PRESALE_VERSION,
+        USER_INFO_VERSION,
+    };
+
+    fn v1_account<T: AnchorSerialize>(discriminator: [u8; 8], body: &T, space: usize) -> Vec<u8> {
+        let mut data = discriminator.to_vec();
+        body.serialize(&mut data).unwrap();
+        assert!(data.len() <= 8 + space);
+        data.resize(8 + space, 0);
+        data
+    }
+
+    fn presale_v1() -> PresaleV1 {
+        PresaleV1 {
+            start_time: 1_700_000_000,
+            end_time: 1_700_600_000,
+            public_sale_end_time: 1_702_414_400,
+            token_mint: Pubkey::new_unique(),
+            presale_token_account: Pubkey::new_unique(),
+            presale_supply: 5_000_000,
+            token_price: 40,
+            total_contributed: 1_250_000,
+            is_active: false,
+            is_paused: true,
+            owner: Pubkey::new_unique(),
+        }
+    }
+
+    #[test]
+    fn test_upgrade_presale_from_v1() {
+        let v1 = presale_v1();
+        let data = v1_account(Presale::DISCRIMINATOR, &v1, PRESALE_V1_SPACE);
+        assert_eq!(data.len(), 8 + 256);
+
+        let (from_version, presale) = upgrade_presale(&data).unwrap();
+        assert_eq!(from_version, 1);
+        assert_eq!(presale.version, PRESALE_VERSION);
+        assert_eq!(presale.start_time, v1.start_time);
+        assert_eq!(presale.end_time, v1.end_time);
+        assert_eq!(presale.tge_timestamp, v1.public_sale_end_time);
+        assert_eq!(presale.token_mint, v1.token_mint);
+        assert_eq!(presale.presale_token_account, v1.presale_token_account);
+        assert_eq!(presale.presale_supply, v1.presale_supply);
+        // v1 ignored its stored price and sold at the fixed rate.
+        assert_eq!(presale.token_price, V1_TOKEN_PRICE);
+        assert_eq!(
+            presale.tokens_for_lamports(40).unwrap(),
+            40 * NLOV_PER_LAMPORT
+        );
+        assert_eq!(presale.total_contributed, v1.total_contributed);
+        assert_eq!(presale.phase, SalePhase::Finalized);
+        assert_eq!(presale.is_paused, v1.is_paused);
+        assert_eq!(presale.owner, v1.owner);
+        assert_eq!(presale.total_claimed, 0);
+        assert!(!presale.emergency);
+        assert!(!presale.proceeds_configured);
+        assert!(presale.require_current_version().is_ok());
+    }
+
+    #[test]
+    fn test_upgrade_active_presale_from_v1() {
+        let v1 = PresaleV1 {
+            is_active: true,
+            is_paused: false,
+            ..presale_v1()
+        };
+        let data = v1_account(Presale::DISCRIMINATOR, &v1, PRESALE_V1_SPACE);
+
+        let (_, presale) = upgrade_presale(&data).unwrap();
+        assert_eq!(presale.phase, SalePhase::Live);
+        assert!(!presale.is_paused);
+        assert!(presale.require_phase(&[SalePhase::Live]).is_ok());
+    }
+
+    #[test]
+    fn test_migrated_presale_round_trips() {
+        let data = v1_account(Presale::DISCRIMINATOR, &presale_v1(), PRESALE_V1_SPACE);
+        let (_, presale) = upgrade_presale(&data).unwrap();
+
+        let mut migrated = vec![0u8; 8 + Presale::INIT_SPACE];
+        presale.try_serialize(&mut &mut migrated[..]).unwrap();
+        let decoded = Presale::try_deserialize(&mut &migrated[..]).unwrap();
+        assert_eq!(decoded.version, PRESALE_VERSION);
+        assert_eq!(decoded.owner, presale.owner);
+
+        assert_eq!(
+            upgrade_presale(&migrated).map(|_| ()).unwrap_err(),
+            Error::from(ErrorCode::AccountAlreadyMigrated)
+        );
+    }
+
+    #[test]
+    fn test_upgrade_presale_rejects_unknown_layouts() {
+        let mut data = v1_account(UserInfo::DISCRIMINATOR, &presale_v1(), PRESALE_V1_SPACE);
+        assert_eq!(
+            upgrade_presale(&data).map(|_| ()).unwrap_err(),
+            Error::from(ErrorCode::InvalidAccountLayout)
+        );
+
+        data = vec![0u8; 8 + Presale::INIT_SPACE];
+        data[..8].copy_from_slice(&Presale::DISCRIMINATOR);
+        data[8] = PRESALE_VERSION + 1;
+        assert_eq!(
+            upgrade_presale(&data).map(|_| ()).unwrap_err(),
+            Error::from(ErrorCode::UnsupportedAccountVersion)
+        );
+    }
+
+    #[test]
+    fn test_unmigrated_presale_is_rejected_by_guard() {
+        let presale = Presale {
+            version: 0,
+            phase: SalePhase::Live,
+            ..Presale::default()
+        };
+        assert_eq!(
+            presale.require_phase(&[SalePhase::Live]).unwrap_err(),
+            Error::from(ErrorCode::AccountNeedsMigration)
+        );
+    }
+
+    #[test]
+    fn test_upgrade_user_info_from_v1() {
+        let v1 = UserInfoV1 {
+            user: Pubkey::new_unique(),
+            amount_contributed: 750,
+            amount_claimed: 250,
+        };
+        let data = v1_account(UserInfo::DISCRIMINATOR, &v1, USER_INFO_V1_SPACE);
+        assert_eq!(data.len(), 8 + USER_INFO_V1_SPACE);
+
+        let (from_version, user_info) = upgrade_user_info(&data).unwrap();
+        assert_eq!(from_version, 1);
+        assert_eq!(user_info.version, USER_INFO_VERSION);
+        assert_eq!(user_info.user, v1.user);
+        assert_eq!(user_info.nlov_allocated, 750);
+        assert_eq!(user_info.amount_claimed, 250);
+        assert_eq!(user_info.lamports_paid, 750 / NLOV_PER_LAMPORT);
+        assert_eq!(user_info.contribution_count, 0);
+
+        let mut migrated = vec![0u8; 8 + UserInfo::INIT_SPACE];
+        user_info.try_serialize(&mut &mut migrated[..]).unwrap();
+        assert_eq!(
+            upgrade_user_info(&migrated).map(|_| ()).unwrap_err(),
+            Error::from(ErrorCode::AccountAlreadyMigrated)
+        );
+    }
+
+    #[test]
+    fn test_v1_user_info_does_not_load_as_current() {
+        let v1 = UserInfoV1 {
+            user: Pubkey::new_unique(),
+            amount_contributed: 750,
+            amount_claimed: 0,
+        };
+        let data = v1_account(UserInfo::DISCRIMINATOR, &v1, USER_INFO_V1_SPACE);
+        assert!(UserInfo::try_deserialize(&mut &data[..]).is_err());
+    }
+}
@@ -0,0 +1,551 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::{Context, Pubkey};
+    use anchor_lang::{AccountDeserialize, Accounts, AccountsExit, Space};
+    use anchor_spl::token::spl_token::instruction::TokenInstruction;
+    use nlov::milestone::{MilestoneAttestation, MilestoneSchedule, MilestoneTerms};
+    use nlov::vesting::{VestingCategory, VestingLedger, VESTING_MONTH};
+    use nlov::{
+        neurolov_presale, AttestMilestone, AttestMilestoneBumps, CreateMilestoneVesting,
+        CreateMilestoneVestingBumps, ErrorCode, GrantRevoked, MilestoneAchieved,
+        MilestoneVestingCreated, ProtocolConfig, ReleaseMilestones, ReleaseMilestonesBumps,
+        RevokeMilestoneGrant, RevokeMilestoneGrantBumps, VestingReleased,
+    };
+
+    use crate::common::{
+        emitted, event_cpi_accounts, infos, install_stubs, invoked, TestAccount, NOW,
+    };
+
+    const NLOV: u64 = 1_000_000_000;
+    const TGE: i64 = NOW - 100;
+    const SCHEDULE: usize = 0;
+
+    /// The team allocation: 100k active users, $10M revenue and mainnet
+    /// launch, each falling back to month 36, then month 36 itself.
+    fn team_tranches() -> Vec<MilestoneTerms> {
+        vec![
+            MilestoneTerms {
+                bps: 2_500,
+                fallback_after: 36 * VESTING_MONTH,
+            };
+            4
+        ]
+    }
+
+    fn schedule(attester: Pubkey, tranches: &[MilestoneTerms]) -> MilestoneSchedule {
+        MilestoneSchedule {
+            mint: Pubkey::new_unique(),
+            id: 0,
+            beneficiary: Pubkey::new_unique(),
+            category: VestingCategory::Team,
+            escrow: Pubkey::new_unique(),
+            attester,
+            total_amount: 1_000 * NLOV,
+            released_amount: 0,
+            milestones: MilestoneSchedule::milestones_from(VestingCategory::Team, tranches)
+                .unwrap(),
+            bump: 0,
+            revocable: true,
+            revoked_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_milestones_from() {
+        let tranches = team_tranches();
+        let milestones =
+            MilestoneSchedule::milestones_from(VestingCategory::Team, &tranches).unwrap();
+        assert_eq!(milestones.len(), 4);
+        assert!(milestones.iter().all(|milestone| !milestone.is_achieved()));
+
+        // Only the team allocation vests on milestones.
+        assert_eq!(
+            MilestoneSchedule::milestones_from(VestingCategory::Advisors, &tranches).unwrap_err(),
+            Error::from(ErrorCode::InvalidVestingSchedule)
+        );
+
+        let fallback = 36 * VESTING_MONTH;
+        let too_late = 48 * VESTING_MONTH + 1;
+        let invalid: [&[(u16, i64)]; 5] = [
+            &[],
+            &[(5_000, fallback), (4_999, fallback)],
+            &[(5_000, fallback), (5_000, fallback), (0, fallback)],
+            &[(10_000, 0)],
+            &[(10_000, too_late)],
+        ];
+        for tranches in invalid {
+            let terms: Vec<_> = tranches
+                .iter()
+                .map(|&(bps, fallback_after)| MilestoneTerms {
+                    bps,
+                    fallback_after,
+                })
+                .collect();
+            assert_eq!(
+                MilestoneSchedule::milestones_from(VestingCategory::Team, &terms).unwrap_err(),
+                Error::from(ErrorCode::InvalidMilestones),
+                "{tranches:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_achieve() {
+        let attester = Pubkey::new_unique();
+        let stranger = Pubkey::new_unique();
+        let mut schedule = schedule(attester, &team_tranches());
+        let fallback = TGE + 36 * VESTING_MONTH;
+
+        assert!(!schedule.achieve(0, &attester, TGE, NOW).unwrap());
+        assert_eq!(
+            schedule.achieve(0, &attester, TGE, NOW).unwrap_err(),
+            Error::from(ErrorCode::MilestoneAlreadyAchieved)
+        );
+        assert_eq!(
+            schedule
+                .achieve(1, &stranger, TGE, fallback - 1)
+                .unwrap_err(),
+            Error::from(ErrorCode::MilestoneNotReached)
+        );
+        // Postponing the TGE postpones the fallback.
+        assert_eq!(
+            schedule
+                .achieve(1, &stranger, TGE + 1, fallback)
+                .unwrap_err(),
+            Error::from(ErrorCode::MilestoneNotReached)
+        );
+        assert!(schedule.achieve(1, &stranger, TGE, fallback).unwrap());
+        assert_eq!(
+            schedule.achieve(4, &attester, TGE, NOW).unwrap_err(),
+            Error::from(ErrorCode::InvalidMilestones)
+        );
+    }
+
+    #[test]
+    fn test_vested_amount() {
+        let attester = Pubkey::new_unique();
+        let tranches = [
+            MilestoneTerms {
+                bps: 3_333,
+                fallback_after: VESTING_MONTH,
+            },
+            MilestoneTerms {
+                bps: 6_667,
+                fallback_after: VESTING_MONTH,
+            },
+        ];
+        let mut schedule = schedule(attester, &tranches);
+        schedule.total_amount = 1_000;
+        let tge = NOW + 1;
+
+        schedule.achieve(0, &attester, tge, NOW).unwrap();
+        // Achieved before TGE, so nothing is vested until then.
+        assert_eq!(schedule.vested_amount(tge, NOW).unwrap(), 0);
+        assert_eq!(schedule.vested_amount(tge, NOW + 1).unwrap(), 333);
+        schedule.achieve(1, &attester, tge, NOW).unwrap();
+        assert_eq!(schedule.vested_amount(tge, NOW + 1).unwrap(), 1_000);
+    }
+
+    /// Accounts for `create_milestone_vesting` by the protocol authority,
+    /// funding the ledger's next schedule.
+    fn create_accounts(ledger: &VestingLedger) -> Vec<TestAccount> {
+        let mint = ledger.mint;
+        let authority = Pubkey::new_unique();
+        let ledger_key =
+            Pubkey::find_program_address(&[b"vesting_ledger", mint.as_ref()], &nlov::ID).0;
+        let schedule_key = Pubkey::find_program_address(
+            &[
+                b"milestone_vesting",
+                mint.as_ref(),
+                &ledger.schedule_count.to_le_bytes(),
+            ],
+            &nlov::ID,
+        )
+        .0;
+        let (config_key, bump) = Pubkey::find_program_address(&[b"protocol_config"], &nlov::ID);
+        let config = ProtocolConfig {
+            authority,
+            bump,
+            ..ProtocolConfig::default()
+        };
+        let mut accounts = vec![
+            TestAccount::anchor(ledger_key, ledger, VestingLedger::INIT_SPACE),
+            TestAccount::uninitialized(schedule_key, MilestoneSchedule::INIT_SPACE),
+            TestAccount::token_account(Pubkey::new_unique(), mint, schedule_key),
+            TestAccount::mint(mint),
+            TestAccount::token_account(Pubkey::new_unique(), mint, authority),
+            TestAccount::anchor(config_key, &config, ProtocolConfig::INIT_SPACE),
+            TestAccount::signer(authority),
+            TestAccount::program(anchor_spl::token::ID),
+            TestAccount::program(anchor_lang::system_program::ID),
+            TestAccount::category_vault_authority(&mint),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn create(
+        accounts: &mut [TestAccount],
+        category: VestingCategory,
+        attester: Pubkey,
+    ) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = CreateMilestoneVestingBumps::default();
+        let mut ix = CreateMilestoneVesting::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::create_milestone_vesting(
+            Context::new(&nlov::ID, &mut ix, &[], bumps),
+            Pubkey::new_unique(),
+            category,
+            1_000 * NLOV,
+            attester,
+            team_tranches(),
+            true,
+        )?;
+        ix.exit(&nlov::ID)
+    }
+
+    #[test]
+    fn test_create_milestone_vesting() {
+        let ledger = VestingLedger {
+            mint: Pubkey::new_unique(),
+            schedule_count: 2,
+            ..VestingLedger::default()
+        };
+        let attester = Pubkey::new_unique();
+        let mut accounts = create_accounts(&ledger);
+        create(&mut accounts, VestingCategory::Team, attester).unwrap();
+
+        let schedule = MilestoneSchedule::try_deserialize(&mut &accounts[1].data[..]).unwrap();
+        assert_eq!(schedule.id, 2);
+        assert_eq!(schedule.attester, attester);
+        assert_eq!(schedule.escrow, accounts[2].key);
+        assert_eq!(schedule.milestones.len(), 4);
+        let ledger = VestingLedger::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+        assert_eq!(ledger.schedule_count, 3);
+        assert_eq!(
+            ledger.allocated[VestingCategory::Team as usize],
+            1_000 * NLOV
+        );
+
+        let transfers: Vec<_> = invoked()
+            .into_iter()
+            .filter(|ix| ix.program_id == anchor_spl::token::ID)
+            .collect();
+        assert_eq!(
+            TokenInstruction::unpack(&transfers[0].data).unwrap(),
+            TokenInstruction::Transfer {
+                amount: 1_000 * NLOV
+            }
+        );
+        let event = &emitted::<MilestoneVestingCreated>()[0];
+        assert_eq!(event.schedule, accounts[1].key);
+        assert_eq!(event.milestones, schedule.milestones);
+        assert!(schedule.revocable && event.revocable);
+    }
+
+    #[test]
+    fn test_create_milestone_vesting_rejects_linear_category() {
+        let ledger = VestingLedger {
+            mint: Pubkey::new_unique(),
+            ..VestingLedger::default()
+        };
+        let mut accounts = create_accounts(&ledger);
+        assert_eq!(
+            create(
+                &mut accounts,
+                VestingCategory::Treasury,
+                Pubkey::new_unique()
+            )
+            .unwrap_err(),
+            Error::from(ErrorCode::InvalidVestingSchedule)
+        );
+    }
+
+    fn attest_accounts(
+        schedule: &MilestoneSchedule,
+        index: u8,
+        signer: Pubkey,
+        tge: i64,
+    ) -> Vec<TestAccount> {
+        let schedule_key = Pubkey::new_unique();
+        let attestation_key = Pubkey::find_program_address(
+            &[b"milestone_attestation", schedule_key.as_ref(), &[index]],
+            &nlov::ID,
+        )
+        .0;
+        let mut accounts = vec![
+            TestAccount::anchor(schedule_key, schedule, MilestoneSchedule::INIT_SPACE),
+            TestAccount::uninitialized(attestation_key, MilestoneAttestation::INIT_SPACE),
+            TestAccount::signer(signer),
+            TestAccount::program(anchor_lang::system_program::ID),
+            TestAccount::emission_schedule_with_tge(&schedule.mint, tge),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn attest(accounts: &mut [TestAccount], index: u8) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = AttestMilestoneBumps::default();
+        let mut ix = AttestMilestone::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &index.to_le_bytes(),
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::attest_milestone(
+            Context::new(&nlov::ID, &mut ix, &[], bumps),
+            index,
+            [7; 32],
+        )?;
+        ix.exit(&nlov::ID)
+    }
+
+    #[test]
+    fn test_attester_marks_milestone() {
+        let attester = Pubkey::new_unique();
+        let schedule = schedule(attester, &team_tranches());
+        let mut accounts = attest_accounts(&schedule, 2, attester, TGE);
+        attest(&mut accounts, 2).unwrap();
+
+        let stored = MilestoneSchedule::try_deserialize(&mut &accounts[SCHEDULE].data[..]).unwrap();
+        assert_eq!(stored.milestones[2].achieved_at, NOW);
+        let attestation =
+            MilestoneAttestation::try_deserialize(&mut &accounts[1].data[..]).unwrap();
+        assert_eq!(attestation.schedule, accounts[SCHEDULE].key);
+        assert_eq!(attestation.index, 2);
+        assert_eq!(attestation.attested_by, attester);
+        assert!(!attestation.by_fallback);
+        assert_eq!(attestation.evidence, [7; 32]);
+
+        let event = &emitted::<MilestoneAchieved>()[0];
+        assert_eq!(event.index, 2);
+        assert_eq!(event.bps, 2_500);
+        assert!(!event.by_fallback);
+    }
+
+    #[test]
+    fn test_fallback_opens_to_anyone() {
+        let attester = Pubkey::new_unique();
+        let stranger = Pubkey::new_unique();
+
+        let pending = schedule(attester, &team_tranches());
+        let mut accounts = attest_accounts(&pending, 0, stranger, TGE);
+        assert_eq!(
+            attest(&mut accounts, 0).unwrap_err(),
+            Error::from(ErrorCode::MilestoneNotReached)
+        );
+
+        // Month 36 after the mint's TGE is already behind us.
+        let mut accounts = attest_accounts(&pending, 0, stranger, NOW - 36 * VESTING_MONTH);
+        attest(&mut accounts, 0).unwrap();
+        let attestation =
+            MilestoneAttestation::try_deserialize(&mut &accounts[1].data[..]).unwrap();
+        assert_eq!(attestation.attested_by, stranger);
+        assert!(attestation.by_fallback);
+    }
+
+    fn release_accounts(schedule: &MilestoneSchedule) -> Vec<TestAccount> {
+        let mut accounts = vec![
+            TestAccount::anchor(
+                Pubkey::new_unique(),
+                schedule,
+                MilestoneSchedule::INIT_SPACE,
+            ),
+            TestAccount::token_account(schedule.escrow, schedule.mint, Pubkey::new_unique()),
+            TestAccount::token_account(Pubkey::new_unique(), schedule.mint, schedule.beneficiary),
+            TestAccount::signer(schedule.beneficiary),
+            TestAccount::program(anchor_spl::token::ID),
+            TestAccount::emission_schedule_with_tge(&schedule.mint, TGE),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn release_milestones(accounts: &mut [TestAccount]) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = ReleaseMilestonesBumps::default();
+        let mut ix = ReleaseMilestones::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::release_milestones(Context::new(&nlov::ID, &mut ix, &[], bumps))?;
+        ix.exit(&nlov::ID)
+    }
+
+    #[test]
+    fn test_release_achieved_tranches() {
+        let attester = Pubkey::new_unique();
+        let mut schedule = schedule(attester, &team_tranches());
+        let mut accounts = release_accounts(&schedule);
+        assert_eq!(
+            release_milestones(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::NothingToClaim)
+        );
+
+        schedule.achieve(0, &attester, TGE, NOW).unwrap();
+        schedule.achieve(3, &attester, TGE, NOW).unwrap();
+        let mut accounts = release_accounts(&schedule);
+        release_milestones(&mut accounts).unwrap();
+
+        let stored = MilestoneSchedule::try_deserialize(&mut &accounts[SCHEDULE].data[..]).unwrap();
+        assert_eq!(stored.released_amount, 500 * NLOV);
+        let events = emitted::<VestingReleased>();
+        assert_eq!(events[0].schedule, accounts[SCHEDULE].key);
+        assert_eq!(events[0].amount, 500 * NLOV);
+        assert_eq!(
+            release_milestones(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::NothingToClaim)
+        );
+    }
+
+    #[test]
+    fn test_revoke_keeps_achieved_tranches() {
+        let attester = Pubkey::new_unique();
+        let mut schedule = schedule(attester, &team_tranches());
+        schedule.achieve(0, &attester, TGE, NOW).unwrap();
+
+        assert_eq!(schedule.revoke(TGE, NOW).unwrap(), 750 * NLOV);
+        assert_eq!(schedule.revoked_at, NOW);
+        assert_eq!(schedule.vested_amount(TGE, i64::MAX).unwrap(), 250 * NLOV);
+        assert_eq!(
+            schedule.achieve(1, &attester, TGE, NOW).unwrap_err(),
+            Error::from(ErrorCode::GrantAlreadyRevoked)
+        );
+        assert_eq!(
+            schedule.revoke(TGE, NOW).unwrap_err(),
+            Error::from(ErrorCode::GrantAlreadyRevoked)
+        );
+
+        schedule.revocable = false;
+        schedule.revoked_at = 0;
+        assert_eq!(
+            schedule.revoke(TGE, NOW).unwrap_err(),
+            Error::from(ErrorCode::GrantNotRevocable)
+        );
+    }
+
+    /// Accounts for `revoke_milestone_grant` by the protocol authority, with
+    /// the whole schedule still in escrow and counted in the ledger.
+    fn revoke_accounts(schedule: &MilestoneSchedule) -> Vec<TestAccount> {
+        let authority = Pubkey::new_unique();
+        let (ledger_key, ledger_bump) =
+            Pubkey::find_program_address(&[b"vesting_ledger", schedule.mint.as_ref()], &nlov::ID);
+        let mut ledger = VestingLedger {
+            mint: schedule.mint,
+            schedule_count: 1,
+            bump: ledger_bump,
+            ..VestingLedger::default()
+        };
+        ledger.allocated[schedule.category as usize] = schedule.total_amount;
+        let (config_key, bump) = Pubkey::find_program_address(&[b"protocol_config"], &nlov::ID);
+        let config = ProtocolConfig {
+            authority,
+            bump,
+            ..ProtocolConfig::default()
+        };
+        let vault_authority = TestAccount::category_vault_authority(&schedule.mint);
+        let category_vault = Pubkey::find_program_address(
+            &[
+                b"category_vault",
+                schedule.mint.as_ref(),
+                &[schedule.category as u8],
+            ],
+            &nlov::ID,
+        )
+        .0;
+        let mut accounts = vec![
+            TestAccount::anchor(ledger_key, &ledger, VestingLedger::INIT_SPACE),
+            TestAccount::anchor(
+                Pubkey::new_unique(),
+                schedule,
+                MilestoneSchedule::INIT_SPACE,
+            ),
+            TestAccount::token_account(schedule.escrow, schedule.mint, Pubkey::new_unique()),
+            TestAccount::token_account(category_vault, schedule.mint, vault_authority.key),
+            TestAccount::anchor(config_key, &config, ProtocolConfig::INIT_SPACE),
+            TestAccount::signer(authority),
+            TestAccount::program(anchor_spl::token::ID),
+            TestAccount::emission_schedule_with_tge(&schedule.mint, TGE),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn revoke(accounts: &mut [TestAccount]) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = RevokeMilestoneGrantBumps::default();
+        let mut ix = RevokeMilestoneGrant::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::revoke_milestone_grant(Context::new(&nlov::ID, &mut ix, &[], bumps))?;
+        ix.exit(&nlov::ID)
+    }
+
+    #[test]
+    fn test_revoke_milestone_grant() {
+        let attester = Pubkey::new_unique();
+        let mut schedule = schedule(attester, &team_tranches());
+        schedule.achieve(0, &attester, TGE, NOW).unwrap();
+        let mut accounts = revoke_accounts(&schedule);
+        revoke(&mut accounts).unwrap();
+
+        let stored = MilestoneSchedule::try_deserialize(&mut &accounts[1].data[..]).unwrap();
+        assert_eq!(stored.revoked_at, NOW);
+        let ledger = VestingLedger::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+        assert_eq!(ledger.allocated[VestingCategory::Team as usize], 250 * NLOV);
+
+        // The pending tranches go back to the Team vault they were drawn from.
+        let transfer = invoked()
+            .into_iter()
+            .find(|ix| ix.program_id == anchor_spl::token::ID)
+            .unwrap();
+        assert_eq!(
+            TokenInstruction::unpack(&transfer.data).unwrap(),
+            TokenInstruction::Transfer { amount: 750 * NLOV }
+        );
+        assert_eq!(transfer.accounts[1].pubkey, accounts[3].key);
+        let event = &emitted::<GrantRevoked>()[0];
+        assert_eq!(event.vested_amount, 250 * NLOV);
+        assert_eq!(event.returned_amount, 750 * NLOV);
+    }
+
+    #[test]
+    fn test_revoke_milestone_grant_rejects_other_vault() {
+        let schedule = schedule(Pubkey::new_unique(), &team_tranches());
+        let mut accounts = revoke_accounts(&schedule);
+        accounts[3] = TestAccount::token_account(
+            Pubkey::new_unique(),
+            schedule.mint,
+            TestAccount::category_vault_authority(&schedule.mint).key,
+        );
+        assert_eq!(
+            revoke(&mut accounts).unwrap_err(),
+            Error::from(anchor_lang::error::ErrorCode::ConstraintSeeds)
+                .with_account_name("category_vault")
+        );
+    }
+}
@@ -0,0 +1,170 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::{Context, Pubkey};
+    use anchor_lang::{
+        AccountDeserialize, Accounts, AccountsExit, AnchorSerialize, Discriminator, Space,
+    };
+    use nlov::migration::{upgrade_presale, PresaleV1, PRESALE_V1_SPACE};
+    use nlov::{
+        neurolov_presale, ConfigureProceeds, ConfigureProceedsBumps, ErrorCode, Presale,
+        ProceedsDestination, ProceedsLeg, SalePhase, PROCEEDS_LEGS,
+    };
+
+    use crate::common::{event_cpi_accounts, infos, install_stubs, TestAccount};
+
+    fn legs(bps: [u16; PROCEEDS_LEGS]) -> [ProceedsLeg; PROCEEDS_LEGS] {
+        bps.map(|bps| ProceedsLeg {
+            wallet: Pubkey::new_unique(),
+            bps,
+        })
+    }
+
+    #[test]
+    fn test_configure_proceeds() {
+        let mut presale = Presale::default();
+        let legs = legs([4_000, 3_000, 2_000, 1_000]);
+        presale.configure_proceeds(legs).unwrap();
+        assert!(presale.proceeds_configured);
+        assert_eq!(presale.proceeds, legs);
+    }
+
+    #[test]
+    fn test_configure_proceeds_only_once() {
+        let mut presale = Presale::default();
+        presale
+            .configure_proceeds(legs([4_000, 3_000, 2_000, 1_000]))
+            .unwrap();
+        assert_eq!(
+            presale
+                .configure_proceeds(legs([2_500, 2_500, 2_500, 2_500]))
+                .unwrap_err(),
+            Error::from(ErrorCode::ProceedsAlreadyConfigured)
+        );
+    }
+
+    #[test]
+    fn test_configure_proceeds_rejects_bad_split() {
+        let mut presale = Presale::default();
+        for bps in [[4_000, 3_000, 2_000, 999], [4_000, 3_000, 2_000, 1_001]] {
+            assert_eq!(
+                presale.configure_proceeds(legs(bps)).unwrap_err(),
+                Error::from(ErrorCode::InvalidProceedsSplit)
+            );
+        }
+        assert!(!presale.proceeds_configured);
+    }
+
+    #[test]
+    fn test_configure_proceeds_rejects_default_wallet() {
+        let mut presale = Presale::default();
+        let mut legs = legs([4_000, 3_000, 2_000, 1_000]);
+        legs[ProceedsDestination::Marketing as usize].wallet = Pubkey::default();
+        assert_eq!(
+            presale.configure_proceeds(legs).unwrap_err(),
+            Error::from(ErrorCode::InvalidProceedsWallet)
+        );
+    }
+
+    #[test]
+    fn test_split_requires_configuration() {
+        assert_eq!(
+            Presale::default().split_proceeds(1_000).unwrap_err(),
+            Error::from(ErrorCode::ProceedsNotConfigured)
+        );
+    }
+
+    #[test]
+    fn test_split_proceeds() {
+        let mut presale = Presale::default();
+        presale
+            .configure_proceeds(legs([4_000, 3_000, 2_000, 1_000]))
+            .unwrap();
+        assert_eq!(
+            presale.split_proceeds(1_000_000).unwrap(),
+            [400_000, 300_000, 200_000, 100_000]
+        );
+    }
+
+    #[test]
+    fn test_split_proceeds_dust_goes_to_treasury() {
+        let mut presale = Presale::default();
+        presale
+            .configure_proceeds(legs([3_333, 3_333, 3_333, 1]))
+            .unwrap();
+        let shares = presale.split_proceeds(10_001).unwrap();
+        assert_eq!(shares, [3_333, 3_334, 3_333, 1]);
+        assert_eq!(shares.iter().sum::<u64>(), 10_001);
+    }
+
+    #[test]
+    fn test_split_proceeds_full_range() {
+        let mut presale = Presale::default();
+        presale
+            .configure_proceeds(legs([2_500, 2_500, 2_500, 2_500]))
+            .unwrap();
+        let shares = presale.split_proceeds(u64::MAX).unwrap();
+        assert_eq!(
+            shares.iter().map(|&s| s as u128).sum::<u128>(),
+            u64::MAX as u128
+        );
+    }
+
+    fn configure(
+        accounts: &mut [TestAccount],
+        legs: [ProceedsLeg; PROCEEDS_LEGS],
+    ) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = ConfigureProceedsBumps::default();
+        let mut ix = ConfigureProceeds::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::configure_proceeds(Context::new(&nlov::ID, &mut ix, &[], bumps), legs)?;
+        ix.exit(&nlov::ID)
+    }
+
+    #[test]
+    fn test_configure_proceeds_after_v1_migration() {
+        // A v1 sale that was already finalized migrates without a split.
+        let owner = Pubkey::new_unique();
+        let v1 = PresaleV1 {
+            owner,
+            is_active: false,
+            ..PresaleV1::default()
+        };
+        let mut data = Presale::DISCRIMINATOR.to_vec();
+        v1.serialize(&mut data).unwrap();
+        data.resize(8 + PRESALE_V1_SPACE, 0);
+        let (_, migrated) = upgrade_presale(&data).unwrap();
+        assert_eq!(migrated.phase, SalePhase::Finalized);
+
+        let mut accounts = vec![
+            TestAccount::anchor(Pubkey::new_unique(), &migrated, Presale::INIT_SPACE),
+            TestAccount::signer(owner),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::absent(),
+        ];
+        accounts.extend(event_cpi_accounts());
+        let split = legs([4_000, 3_000, 2_000, 1_000]);
+        configure(&mut accounts, split).unwrap();
+
+        let stored = Presale::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+        assert!(stored.proceeds_configured);
+        assert_eq!(stored.proceeds, split);
+
+        assert_eq!(
+            configure(&mut accounts, legs([2_500, 2_500, 2_500, 2_500])).unwrap_err(),
+            Error::from(ErrorCode::ProceedsAlreadyConfigured)
+        );
+    }
+}
@@ -0,0 +1,443 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::{Context, Pubkey};
+    use anchor_lang::solana_program::instruction::Instruction;
+    use anchor_lang::solana_program::program_pack::Pack;
+    use anchor_lang::{AccountDeserialize, Accounts, AccountsExit, Space};
+    use anchor_spl::token::spl_token;
+    use anchor_spl::token_2022::spl_token_2022;
+    use anchor_spl::token_2022::spl_token_2022::instruction::TokenInstruction;
+    use nlov::neurolov_presale;
+    use nlov::{
+        Contribute, ContributeBumps, EnableReceipts, EnableReceiptsBumps, ErrorCode, Presale,
+        ReceiptsEnabled, RefundClaimed, SalePhase, TokensClaimed, UserInfo,
+    };
+
+    use crate::common::{
+        claim_refund, claim_tokens, emitted, event_cpi_accounts, infos, install_stubs, invoked,
+        presale, stored_presale, user_info, user_info_account, TestAccount, NOW,
+    };
+
+    const PRESALE: usize = 0;
+
+    fn receipt_presale(phase: SalePhase, receipt_mint: Pubkey) -> Presale {
+        Presale {
+            start_time: NOW - 60,
+            end_time: NOW + 60,
+            tge_timestamp: NOW - 1,
+            token_mint: Pubkey::new_unique(),
+            presale_supply: 1_000_000_000,
+            // 0.001 SOL per whole token: 1 lamport buys 1_000 base units.
+            token_price: 1_000_000,
+            token_decimals: 9,
+            owner: Pubkey::new_unique(),
+            receipt_mint,
+            ..presale(phase)
+        }
+    }
+
+    /// The receipt mint, the user's receipt account and the Token-2022 program.
+    fn receipt_accounts(receipt_mint: Pubkey, user: Pubkey) -> [TestAccount; 3] {
+        [
+            TestAccount::mint(receipt_mint).owned_by(spl_token_2022::ID),
+            TestAccount::token_account(Pubkey::new_unique(), receipt_mint, user)
+                .owned_by(spl_token_2022::ID),
+            TestAccount::program(spl_token_2022::ID),
+        ]
+    }
+
+    /// Token-2022 instructions the program has invoked on this thread.
+    fn token_2022_instructions() -> Vec<Instruction> {
+        invoked()
+            .into_iter()
+            .filter(|ix| ix.program_id == spl_token_2022::ID)
+            .collect()
+    }
+
+    fn keys(ix: &Instruction) -> Vec<Pubkey> {
+        ix.accounts.iter().map(|meta| meta.pubkey).collect()
+    }
+
+    /// Accounts for `enable_receipts` on a pending sale, in declaration order.
+    fn enable_receipts_accounts(receipt_mint: Pubkey) -> Vec<TestAccount> {
+        let key = Pubkey::new_unique();
+        let presale = receipt_presale(SalePhase::Pending, receipt_mint);
+        let address = Pubkey::find_program_address(&[b"receipt_mint", key.as_ref()], &nlov::ID).0;
+        let mut accounts = vec![
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+            TestAccount::mint(presale.token_mint),
+            TestAccount {
+                lamports: 0,
+                ..TestAccount::wallet(address)
+            },
+            TestAccount::signer(presale.owner),
+            TestAccount::program(spl_token_2022::ID),
+            TestAccount::program(anchor_lang::system_program::ID),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::absent(),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn enable_receipts(
+        accounts: &mut [TestAccount],
+        transferable: bool,
+    ) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = EnableReceiptsBumps::default();
+        let mut ix = EnableReceipts::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::enable_receipts(
+            Context::new(&nlov::ID, &mut ix, &[], bumps),
+            transferable,
+        )?;
+        ix.exit(&nlov::ID)
+    }
+
+    /// Accounts for `contribute` to a live sale, in declaration order.
+    fn contribute_accounts(with_receipts: bool) -> Vec<TestAccount> {
+        let key = Pubkey::new_unique();
+        let (user, receipt_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let vault = Pubkey::find_program_address(&[b"vault", key.as_ref()], &nlov::ID).0;
+        let mut accounts = vec![
+            TestAccount::anchor(
+                key,
+                &receipt_presale(SalePhase::Live, receipt_mint),
+                Presale::INIT_SPACE,
+            ),
+            TestAccount::wallet(vault),
+            user_info_account(&key, &user_info(user, 0)),
+            TestAccount::signer(user),
+            TestAccount::program(anchor_lang::system_program::ID),
+        ];
+        if with_receipts {
+            accounts.extend(receipt_accounts(receipt_mint, user));
+        } else {
+            accounts.extend([
+                TestAccount::absent(),
+                TestAccount::absent(),
+                TestAccount::absent(),
+            ]);
+        }
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn contribute(accounts: &mut [TestAccount], amount: u64) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = ContributeBumps::default();
+        let mut ix = Contribute::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::contribute(Context::new(&nlov::ID, &mut ix, &[], bumps), amount)?;
+        ix.exit(&nlov::ID)
+    }
+
+    /// Accounts for `claim_tokens` of a 1_000 NLOV allocation, in declaration
+    /// order.
+    fn claim_tokens_accounts(receipt_mint: Pubkey) -> Vec<TestAccount> {
+        let key = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let mut presale = receipt_presale(SalePhase::Claiming, receipt_mint);
+        presale.presale_token_account = Pubkey::new_unique();
+        let mut accounts = vec![
+            TestAccount::token_account(presale.presale_token_account, presale.token_mint, key),
+            TestAccount::token_account(Pubkey::new_unique(), presale.token_mint, user),
+            user_info_account(&key, &user_info(user, 1_000)),
+            TestAccount::signer(user),
+            TestAccount::program(spl_token::ID),
+        ];
+        accounts.insert(
+            PRESALE,
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+        );
+        accounts.extend(receipt_accounts(receipt_mint, user));
+        accounts.push(TestAccount::emission_schedule(&presale.token_mint));
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    #[test]
+    fn test_enable_receipts_creates_non_transferable_mint() {
+        let mut accounts = enable_receipts_accounts(Pubkey::default());
+        enable_receipts(&mut accounts, false).unwrap();
+
+        let presale = stored_presale(&accounts[PRESALE]);
+        assert_eq!(presale.receipt_mint, accounts[2].key);
+        assert!(presale.receipts_enabled());
+        assert!(!presale.receipts_transferable);
+
+        let instructions = token_2022_instructions();
+        assert_eq!(instructions.len(), 2);
+        assert!(matches!(
+            TokenInstruction::unpack(&instructions[0].data).unwrap(),
+            TokenInstruction::InitializeNonTransferableMint
+        ));
+        match TokenInstruction::unpack(&instructions[1].data).unwrap() {
+            TokenInstruction::InitializeMint2 {
+                decimals,
+                mint_authority,
+                ..
+            } => {
+                assert_eq!(decimals, 9);
+                assert_eq!(mint_authority, accounts[PRESALE].key);
+            }
+            other => panic!("unexpected instruction {other:?}"),
+        }
+
+        let events = emitted::<ReceiptsEnabled>();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].receipt_mint, accounts[2].key);
+        assert!(!events[0].transferable);
+    }
+
+    #[test]
+    fn test_enable_receipts_creates_transferable_mint() {
+        let mut accounts = enable_receipts_accounts(Pubkey::default());
+        enable_receipts(&mut accounts, true).unwrap();
+        assert!(stored_presale(&accounts[PRESALE]).receipts_transferable);
+
+        // The sale is the permanent delegate, so it can burn receipts from
+        // whoever holds them.
+        let instructions = token_2022_instructions();
+        assert_eq!(instructions.len(), 2);
+        match TokenInstruction::unpack(&instructions[0].data).unwrap() {
+            TokenInstruction::InitializePermanentDelegate { delegate } => {
+                assert_eq!(delegate, accounts[PRESALE].key);
+            }
+            other => panic!("unexpected instruction {other:?}"),
+        }
+        assert!(emitted::<ReceiptsEnabled>()[0].transferable);
+    }
+
+    #[test]
+    fn test_enable_receipts_only_once() {
+        let mut accounts = enable_receipts_accounts(Pubkey::new_unique());
+        assert_eq!(
+            enable_receipts(&mut accounts, false).unwrap_err(),
+            Error::from(ErrorCode::ReceiptsAlreadyEnabled)
+        );
+    }
+
+    #[test]
+    fn test_contribute_mints_receipts() {
+        let mut accounts = contribute_accounts(true);
+        contribute(&mut accounts, 40).unwrap();
+
+        let instructions = token_2022_instructions();
+        assert_eq!(instructions.len(), 1);
+        assert!(matches!(
+            TokenInstruction::unpack(&instructions[0].data).unwrap(),
+            TokenInstruction::MintTo { amount } if amount == 40_000
+        ));
+        // Mint, destination, then the presale as mint authority.
+        assert_eq!(
+            keys(&instructions[0]),
+            [accounts[5].key, accounts[6].key, accounts[PRESALE].key]
+        );
+    }
+
+    #[test]
+    fn test_contribute_requires_receipt_accounts() {
+        let mut accounts = contribute_accounts(false);
+        assert_eq!(
+            contribute(&mut accounts, 40).unwrap_err(),
+            Error::from(ErrorCode::MissingReceiptAccounts)
+        );
+    }
+
+    #[test]
+    fn test_contribute_without_receipts() {
+        let mut accounts = contribute_accounts(false);
+        let mut presale = stored_presale(&accounts[PRESALE]);
+        presale.receipt_mint = Pubkey::default();
+        accounts[PRESALE] =
+            TestAccount::anchor(accounts[PRESALE].key, &presale, Presale::INIT_SPACE);
+
+        contribute(&mut accounts, 40).unwrap();
+        assert!(token_2022_instructions().is_empty());
+
+        let user_info = UserInfo::try_deserialize(&mut &accounts[2].data[..]).unwrap();
+        assert_eq!(user_info.nlov_allocated, 40_000);
+        assert_eq!(user_info.lamports_paid, 40);
+        assert_eq!(user_info.first_contribution_at, NOW);
+        assert_eq!(user_info.contribution_count, 1);
+    }
+
+    #[test]
+    fn test_claim_tokens_burns_receipts() {
+        let mut accounts = claim_tokens_accounts(Pubkey::new_unique());
+        claim_tokens(&mut accounts).unwrap();
+
+        // Just past TGE only the presale's 10% share has unlocked.
+        let instructions = token_2022_instructions();
+        assert_eq!(instructions.len(), 1);
+        assert!(matches!(
+            TokenInstruction::unpack(&instructions[0].data).unwrap(),
+            TokenInstruction::Burn { amount } if amount == 100
+        ));
+        // Source, mint, then the user as owner.
+        assert_eq!(
+            keys(&instructions[0]),
+            [accounts[7].key, accounts[6].key, accounts[4].key]
+        );
+    }
+
+    #[test]
+    fn test_claim_tokens_rejects_other_receipt_mint() {
+        let mut accounts = claim_tokens_accounts(Pubkey::new_unique());
+        let user = accounts[4].key;
+        let [mint, receipt_account, _] = receipt_accounts(Pubkey::new_unique(), user);
+        accounts[6] = mint;
+        accounts[7] = receipt_account;
+        assert_eq!(
+            claim_tokens(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::InvalidReceiptMint)
+        );
+    }
+
+    /// `account` holding `amount` receipts instead.
+    fn holding(mut account: TestAccount, amount: u64) -> TestAccount {
+        let mut state = spl_token::state::Account::unpack(&account.data).unwrap();
+        state.amount = amount;
+        state.pack_into_slice(&mut account.data);
+        account
+    }
+
+    /// Accounts for `claim_tokens` by a wallet holding transferable receipts,
+    /// against the allocation of the contributor who sold them.
+    fn redeem_accounts(receipts: u64) -> Vec<TestAccount> {
+        let mut accounts = claim_tokens_accounts(Pubkey::new_unique());
+        let mut presale = stored_presale(&accounts[PRESALE]);
+        presale.receipts_transferable = true;
+        accounts[PRESALE] =
+            TestAccount::anchor(accounts[PRESALE].key, &presale, Presale::INIT_SPACE);
+
+        let holder = Pubkey::new_unique();
+        let [_, receipt_account, _] = receipt_accounts(presale.receipt_mint, holder);
+        accounts[2] = TestAccount::token_account(Pubkey::new_unique(), presale.token_mint, holder);
+        accounts[4] = TestAccount::signer(holder);
+        accounts[7] = holding(receipt_account, receipts);
+        accounts
+    }
+
+    #[test]
+    fn test_transferable_receipts_claim_for_their_holder() {
+        let mut accounts = redeem_accounts(1_000);
+        let contributor = UserInfo::try_deserialize(&mut &accounts[3].data[..])
+            .unwrap()
+            .user;
+        claim_tokens(&mut accounts).unwrap();
+
+        // The sale burns the holder's receipts as permanent delegate.
+        let instructions = token_2022_instructions();
+        assert!(matches!(
+            TokenInstruction::unpack(&instructions[0].data).unwrap(),
+            TokenInstruction::Burn { amount } if amount == 100
+        ));
+        assert_eq!(
+            keys(&instructions[0]),
+            [accounts[7].key, accounts[6].key, accounts[PRESALE].key]
+        );
+        let event = &emitted::<TokensClaimed>()[0];
+        assert_eq!(event.user, accounts[4].key);
+        assert_eq!(event.allocation, contributor);
+        assert_eq!(event.amount, 100);
+    }
+
+    #[test]
+    fn test_transferable_receipts_cap_the_claim() {
+        let mut accounts = redeem_accounts(40);
+        claim_tokens(&mut accounts).unwrap();
+        assert_eq!(emitted::<TokensClaimed>().pop().unwrap().amount, 40);
+
+        let mut accounts = redeem_accounts(0);
+        assert_eq!(
+            claim_tokens(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::NothingToClaim)
+        );
+    }
+
+    #[test]
+    fn test_non_transferable_receipts_claim_only_own_allocation() {
+        let mut accounts = redeem_accounts(1_000);
+        let mut presale = stored_presale(&accounts[PRESALE]);
+        presale.receipts_transferable = false;
+        accounts[PRESALE] =
+            TestAccount::anchor(accounts[PRESALE].key, &presale, Presale::INIT_SPACE);
+        assert_eq!(
+            claim_tokens(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::UserInfoMismatch)
+        );
+    }
+
+    /// Accounts for `claim_refund` after `cancel_presale` by a wallet holding
+    /// `receipts` transferable receipts, against a 1_000 NLOV allocation
+    /// bought for 40 lamports.
+    fn refund_accounts(receipts: u64) -> Vec<TestAccount> {
+        let key = Pubkey::new_unique();
+        let receipt_mint = Pubkey::new_unique();
+        let (contributor, holder) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let presale = Presale {
+            total_contributed: 1_000,
+            receipts_transferable: true,
+            ..receipt_presale(SalePhase::Refunding, receipt_mint)
+        };
+        let vault = Pubkey::find_program_address(&[b"vault", key.as_ref()], &nlov::ID).0;
+        let info = UserInfo {
+            lamports_paid: 40,
+            ..user_info(contributor, 1_000)
+        };
+        let [mint, receipt_account, token_2022] = receipt_accounts(receipt_mint, holder);
+        let mut accounts = vec![
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+            TestAccount::wallet(vault),
+            user_info_account(&key, &info),
+            TestAccount::signer(holder),
+            TestAccount::program(anchor_lang::system_program::ID),
+            mint,
+            holding(receipt_account, receipts),
+            token_2022,
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    #[test]
+    fn test_transferable_receipts_refund_their_holder() {
+        let mut accounts = refund_accounts(1_000);
+        claim_refund(&mut accounts).unwrap();
+
+        let event = &emitted::<RefundClaimed>()[0];
+        assert_eq!(event.user, accounts[3].key);
+        assert_ne!(event.allocation, event.user);
+        assert_eq!((event.sol_amount, event.nlov_amount), (40, 1_000));
+    }
+
+    #[test]
+    fn test_transferable_refund_needs_receipts_for_whole_allocation() {
+        let mut accounts = refund_accounts(999);
+        assert_eq!(
+            claim_refund(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::InsufficientReceipts)
+        );
+    }
+}
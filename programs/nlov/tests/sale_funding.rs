@@ -0,0 +1,341 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::{Context, Pubkey};
+    use anchor_lang::{AccountDeserialize, Accounts, AccountsExit, AnchorSerialize, Space};
+    use anchor_spl::token::spl_token::instruction::TokenInstruction;
+    use nlov::emission::EmissionSchedule;
+    use nlov::vesting::{VestingCategory, VestingLedger};
+    use nlov::{
+        neurolov_presale, ErrorCode, FundLiquidityLock, FundLiquidityLockBumps, FundPresale,
+        FundPresaleBumps, LiquidityLock, Presale, ProtocolConfig, SaleFunded, SalePhase,
+    };
+
+    use crate::common::{
+        claim_tokens, emitted, event_cpi_accounts, infos, install_stubs, invoked, presale,
+        user_info, user_info_account, TestAccount, NOW,
+    };
+
+    const PRESALE: usize = 0;
+    const SUPPLY: u64 = 1_000_000;
+
+    fn category_vault(mint: &Pubkey, category: VestingCategory) -> TestAccount {
+        let key = Pubkey::find_program_address(
+            &[b"category_vault", mint.as_ref(), &[category as u8]],
+            &nlov::ID,
+        )
+        .0;
+        TestAccount::token_account(key, *mint, TestAccount::category_vault_authority(mint).key)
+    }
+
+    fn ledger(mint: Pubkey) -> TestAccount {
+        let (key, bump) =
+            Pubkey::find_program_address(&[b"vesting_ledger", mint.as_ref()], &nlov::ID);
+        let ledger = VestingLedger {
+            mint,
+            bump,
+            ..VestingLedger::default()
+        };
+        TestAccount::anchor(key, &ledger, VestingLedger::INIT_SPACE)
+    }
+
+    /// The protocol config account and its authority, who owns the sales.
+    fn protocol_config(authority: Pubkey) -> TestAccount {
+        let (key, bump) = Pubkey::find_program_address(&[b"protocol_config"], &nlov::ID);
+        let config = ProtocolConfig {
+            authority,
+            bump,
+            ..ProtocolConfig::default()
+        };
+        TestAccount::anchor(key, &config, ProtocolConfig::INIT_SPACE)
+    }
+
+    fn protocol_presale(owner: Pubkey) -> Presale {
+        Presale {
+            owner,
+            token_mint: Pubkey::new_unique(),
+            presale_token_account: Pubkey::new_unique(),
+            presale_supply: SUPPLY,
+            ..presale(SalePhase::Pending)
+        }
+    }
+
+    /// Accounts for `fund_presale` by the protocol authority, drawing on the
+    /// vault of `category`.
+    fn fund_presale_accounts(category: VestingCategory) -> Vec<TestAccount> {
+        let authority = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let presale = protocol_presale(authority);
+        let mint = presale.token_mint;
+        let mut accounts = vec![
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+            TestAccount::token_account(presale.presale_token_account, mint, key),
+            TestAccount::mint(mint),
+            ledger(mint),
+            category_vault(&mint, category),
+            TestAccount::category_vault_authority(&mint),
+            protocol_config(authority),
+            TestAccount::signer(authority),
+            TestAccount::program(anchor_spl::token::ID),
+            TestAccount::program(anchor_lang::system_program::ID),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn fund_presale(
+        accounts: &mut [TestAccount],
+        category: VestingCategory,
+        amount: u64,
+    ) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = FundPresaleBumps::default();
+        let mut ix = FundPresale::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &category.try_to_vec().unwrap(),
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::fund_presale(
+            Context::new(&nlov::ID, &mut ix, &[], bumps),
+            category,
+            amount,
+        )?;
+        ix.exit(&nlov::ID)
+    }
+
+    /// Accounts for `fund_liquidity_lock` by the protocol authority, on the
+    /// lock of a finalized sale it owns.
+    fn fund_lock_accounts() -> Vec<TestAccount> {
+        let authority = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let presale = Presale {
+            phase: SalePhase::Finalized,
+            ..protocol_presale(authority)
+        };
+        let mint = presale.token_mint;
+        let (lock_key, bump) =
+            Pubkey::find_program_address(&[b"liquidity_lock", key.as_ref()], &nlov::ID);
+        let lock = LiquidityLock {
+            presale: key,
+            nlov_amount: 500,
+            bump,
+            ..LiquidityLock::default()
+        };
+        let lock_token_key =
+            Pubkey::find_program_address(&[b"liquidity_lock_nlov", lock_key.as_ref()], &nlov::ID).0;
+        let mut accounts = vec![
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+            TestAccount::anchor(lock_key, &lock, LiquidityLock::INIT_SPACE),
+            TestAccount::token_account(lock_token_key, mint, lock_key),
+            TestAccount::mint(mint),
+            ledger(mint),
+            category_vault(&mint, VestingCategory::Liquidity),
+            TestAccount::category_vault_authority(&mint),
+            protocol_config(authority),
+            TestAccount::signer(authority),
+            TestAccount::program(anchor_spl::token::ID),
+            TestAccount::program(anchor_lang::system_program::ID),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn fund_lock(accounts: &mut [TestAccount], amount: u64) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = FundLiquidityLockBumps::default();
+        let mut ix = FundLiquidityLock::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::fund_liquidity_lock(
+            Context::new(&nlov::ID, &mut ix, &[], bumps),
+            amount,
+        )?;
+        ix.exit(&nlov::ID)
+    }
+
+    const EMISSION_SCHEDULE: usize = 9;
+
+    /// Accounts for `claim_tokens` of a 1_000 NLOV allocation just past TGE,
+    /// from a sale that is `genesis_funded` or not.
+    fn claim_tokens_accounts(genesis_funded: bool) -> Vec<TestAccount> {
+        let key = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let presale = Presale {
+            phase: SalePhase::Claiming,
+            tge_timestamp: NOW - 1,
+            genesis_funded,
+            ..protocol_presale(Pubkey::new_unique())
+        };
+        let mut accounts = vec![
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+            TestAccount::token_account(presale.presale_token_account, presale.token_mint, key),
+            TestAccount::token_account(Pubkey::new_unique(), presale.token_mint, user),
+            user_info_account(&key, &user_info(user, 1_000)),
+            TestAccount::signer(user),
+            TestAccount::program(anchor_spl::token::ID),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::emission_schedule(&presale.token_mint),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn emitted_supply(accounts: &[TestAccount]) -> u64 {
+        EmissionSchedule::try_deserialize(&mut &accounts[EMISSION_SCHEDULE].data[..])
+            .unwrap()
+            .emitted
+    }
+
+    /// The last token transfer the program invoked, as `(amount, from, to)`.
+    fn transfer() -> (u64, Pubkey, Pubkey) {
+        let ix = invoked()
+            .into_iter()
+            .rfind(|ix| ix.program_id == anchor_spl::token::ID)
+            .unwrap();
+        let TokenInstruction::Transfer { amount } = TokenInstruction::unpack(&ix.data).unwrap()
+        else {
+            panic!("not a transfer");
+        };
+        (amount, ix.accounts[0].pubkey, ix.accounts[1].pubkey)
+    }
+
+    #[test]
+    fn test_fund_presale_from_sale_vaults() {
+        for category in [VestingCategory::Presale, VestingCategory::PublicSale] {
+            let mut accounts = fund_presale_accounts(category);
+            fund_presale(&mut accounts, category, 400).unwrap();
+
+            assert_eq!(transfer(), (400, accounts[4].key, accounts[1].key));
+            let presale = Presale::try_deserialize(&mut &accounts[PRESALE].data[..]).unwrap();
+            assert_eq!(presale.presale_supply, SUPPLY + 400);
+            assert!(presale.genesis_funded);
+            let ledger = VestingLedger::try_deserialize(&mut &accounts[3].data[..]).unwrap();
+            assert_eq!(ledger.allocated[category as usize], 400);
+
+            let event = emitted::<SaleFunded>().pop().unwrap();
+            assert_eq!(event.category, category);
+            assert_eq!(event.destination, accounts[1].key);
+            assert_eq!(event.amount, 400);
+        }
+    }
+
+    #[test]
+    fn test_fund_presale_rejects_other_categories() {
+        for category in [VestingCategory::Liquidity, VestingCategory::Team] {
+            let mut accounts = fund_presale_accounts(category);
+            assert_eq!(
+                fund_presale(&mut accounts, category, 400).unwrap_err(),
+                Error::from(ErrorCode::InvalidFundingCategory)
+            );
+        }
+    }
+
+    #[test]
+    fn test_fund_presale_counts_against_allocation() {
+        let mut accounts = fund_presale_accounts(VestingCategory::Presale);
+        let mut ledger = VestingLedger::try_deserialize(&mut &accounts[3].data[..]).unwrap();
+        let allocation = VestingCategory::Presale.terms().allocation(9).unwrap();
+        ledger.allocated[VestingCategory::Presale as usize] = allocation - 399;
+        accounts[3] = TestAccount::anchor(accounts[3].key, &ledger, VestingLedger::INIT_SPACE);
+        assert_eq!(
+            fund_presale(&mut accounts, VestingCategory::Presale, 400).unwrap_err(),
+            Error::from(ErrorCode::VestingAllocationExceeded)
+        );
+    }
+
+    #[test]
+    fn test_fund_presale_only_for_protocol_sales() {
+        let mut accounts = fund_presale_accounts(VestingCategory::Presale);
+        let mut presale = Presale::try_deserialize(&mut &accounts[PRESALE].data[..]).unwrap();
+        presale.owner = Pubkey::new_unique();
+        accounts[PRESALE] =
+            TestAccount::anchor(accounts[PRESALE].key, &presale, Presale::INIT_SPACE);
+        assert_eq!(
+            fund_presale(&mut accounts, VestingCategory::Presale, 400).unwrap_err(),
+            Error::from(ErrorCode::Unauthorized)
+        );
+    }
+
+    #[test]
+    fn test_fund_presale_before_the_sale_opens() {
+        let mut accounts = fund_presale_accounts(VestingCategory::Presale);
+        let mut presale = Presale::try_deserialize(&mut &accounts[PRESALE].data[..]).unwrap();
+        presale.phase = SalePhase::Live;
+        accounts[PRESALE] =
+            TestAccount::anchor(accounts[PRESALE].key, &presale, Presale::INIT_SPACE);
+        assert_eq!(
+            fund_presale(&mut accounts, VestingCategory::Presale, 400).unwrap_err(),
+            Error::from(ErrorCode::InvalidPhase)
+        );
+    }
+
+    #[test]
+    fn test_fund_liquidity_lock() {
+        let mut accounts = fund_lock_accounts();
+        fund_lock(&mut accounts, 300).unwrap();
+
+        assert_eq!(transfer(), (300, accounts[5].key, accounts[2].key));
+        let lock = LiquidityLock::try_deserialize(&mut &accounts[1].data[..]).unwrap();
+        assert_eq!(lock.nlov_amount, 800);
+        assert!(lock.genesis_funded);
+        let ledger = VestingLedger::try_deserialize(&mut &accounts[4].data[..]).unwrap();
+        assert_eq!(ledger.allocated[VestingCategory::Liquidity as usize], 300);
+        assert_eq!(
+            emitted::<SaleFunded>()[0].category,
+            VestingCategory::Liquidity
+        );
+    }
+
+    #[test]
+    fn test_fund_liquidity_lock_rejects_released_lock() {
+        let mut accounts = fund_lock_accounts();
+        let mut lock = LiquidityLock::try_deserialize(&mut &accounts[1].data[..]).unwrap();
+        lock.released = true;
+        accounts[1] = TestAccount::anchor(accounts[1].key, &lock, LiquidityLock::INIT_SPACE);
+        assert_eq!(
+            fund_lock(&mut accounts, 300).unwrap_err(),
+            Error::from(ErrorCode::LiquidityAlreadyReleased)
+        );
+    }
+
+    #[test]
+    fn test_claim_from_genesis_sale_counts_against_ceiling() {
+        let mut accounts = claim_tokens_accounts(true);
+        claim_tokens(&mut accounts).unwrap();
+        // Just past TGE only the presale's 10% share has unlocked.
+        assert_eq!(emitted_supply(&accounts), 100);
+
+        let mut accounts = claim_tokens_accounts(true);
+        accounts[EMISSION_SCHEDULE] = TestAccount::absent();
+        assert_eq!(
+            claim_tokens(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::MissingEmissionSchedule)
+        );
+    }
+
+    #[test]
+    fn test_claim_from_other_sales_skips_ceiling() {
+        let mut accounts = claim_tokens_accounts(false);
+        claim_tokens(&mut accounts).unwrap();
+        assert_eq!(emitted_supply(&accounts), 0);
+
+        // A partner mint has no emission schedule at all.
+        let mut accounts = claim_tokens_accounts(false);
+        accounts[EMISSION_SCHEDULE] = TestAccount::absent();
+        claim_tokens(&mut accounts).unwrap();
+    }
+}
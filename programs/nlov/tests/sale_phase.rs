@@ -0,0 +1,112 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::error::Error;
+    use nlov::{ErrorCode, Presale, SalePhase};
+
+    use crate::common::presale;
+
+    fn is_legal(from: SalePhase, to: SalePhase) -> bool {
+        use SalePhase::*;
+        [
+            (Pending, Live),
+            (Live, Ended),
+            (Ended, Finalized),
+            (Finalized, Claiming),
+            (Pending, Refunding),
+            (Live, Refunding),
+            (Ended, Refunding),
+            (Claiming, Closed),
+            (Refunding, Closed),
+        ]
+        .contains(&(from, to))
+    }
+
+    #[test]
+    fn test_legal_transitions() {
+        for from in SalePhase::ALL {
+            for to in SalePhase::ALL {
+                if !is_legal(from, to) {
+                    continue;
+                }
+                let mut presale = presale(from);
+                assert!(presale.transition(to).is_ok(), "{from:?} -> {to:?}");
+                assert_eq!(presale.phase, to);
+            }
+        }
+    }
+
+    #[test]
+    fn test_illegal_transitions() {
+        let mut checked = 0;
+        for from in SalePhase::ALL {
+            for to in SalePhase::ALL {
+                if is_legal(from, to) {
+                    continue;
+                }
+                let mut presale = presale(from);
+                let err = presale.transition(to).unwrap_err();
+                assert_eq!(
+                    err,
+                    Error::from(ErrorCode::InvalidPhaseTransition),
+                    "{from:?} -> {to:?}"
+                );
+                assert_eq!(presale.phase, from, "{from:?} -> {to:?} changed state");
+                checked += 1;
+            }
+        }
+        assert_eq!(checked, 7 * 7 - 9);
+    }
+
+    #[test]
+    fn test_close_requires_settled_allocations() {
+        for from in [SalePhase::Claiming, SalePhase::Refunding] {
+            let mut presale = Presale {
+                total_contributed: 1_000,
+                total_claimed: 400,
+                ..presale(from)
+            };
+            assert_eq!(
+                presale.transition(SalePhase::Closed).unwrap_err(),
+                Error::from(ErrorCode::AllocationsOutstanding)
+            );
+            assert_eq!(presale.phase, from);
+
+            presale.total_claimed = 1_000;
+            assert!(presale.transition(SalePhase::Closed).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_transition_blocked_while_paused() {
+        let mut presale = presale(SalePhase::Pending);
+        presale.is_paused = true;
+        assert_eq!(
+            presale.transition(SalePhase::Live).unwrap_err(),
+            Error::from(ErrorCode::PresalePaused)
+        );
+        assert_eq!(presale.phase, SalePhase::Pending);
+    }
+
+    #[test]
+    fn test_require_phase() {
+        let presale = presale(SalePhase::Live);
+        assert!(presale.require_phase(&[SalePhase::Live]).is_ok());
+        assert_eq!(
+            presale.require_phase(&[SalePhase::Claiming]).unwrap_err(),
+            Error::from(ErrorCode::InvalidPhase)
+        );
+    }
+
+    #[test]
+    fn test_require_phase_respects_pause() {
+        let mut presale = presale(SalePhase::Finalized);
+        presale.is_paused = true;
+        assert_eq!(
+            presale.require_phase(&[SalePhase::Finalized]).unwrap_err(),
+            Error::from(ErrorCode::PresalePaused)
+        );
+        assert!(presale.check_phase(&[SalePhase::Finalized]).is_ok());
+    }
+}
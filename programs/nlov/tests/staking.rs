@@ -0,0 +1,216 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::{Context, Pubkey};
+    use anchor_lang::{AccountDeserialize, Accounts, AccountsExit, Space};
+    use nlov::staking::StakePosition;
+    use nlov::vesting::{VestingCategory, VestingSchedule, VESTING_MONTH};
+    use nlov::{
+        neurolov_presale, ErrorCode, LockedStakeChanged, StakeLocked, StakeLockedBumps,
+        UnstakeLocked, UnstakeLockedBumps,
+    };
+
+    use crate::common::{
+        emitted, event_cpi_accounts, infos, install_stubs, vesting_schedule, TestAccount, NOW,
+    };
+
+    const MONTH: i64 = VESTING_MONTH;
+
+    /// A seed sale schedule of 1,000,000: 5% at TGE, then 12 months linear
+    /// after a 3 month cliff.
+    fn schedule() -> VestingSchedule {
+        VestingSchedule {
+            revocable: true,
+            ..vesting_schedule(VestingCategory::SeedSale, 1_000_000)
+        }
+    }
+
+    #[test]
+    fn test_voting_weight_follows_locked_balance() {
+        let tge = 1_000_000;
+        let mut schedule = schedule();
+        let position = StakePosition {
+            staked_amount: 950_000,
+            ..StakePosition::default()
+        };
+
+        assert_eq!(
+            position.voting_weight(&schedule, tge, tge - 1).unwrap(),
+            950_000
+        );
+        assert_eq!(
+            position.voting_weight(&schedule, tge, tge).unwrap(),
+            950_000
+        );
+        // Half of the linear release has vested.
+        let halfway = tge + 3 * MONTH + 6 * MONTH;
+        assert_eq!(schedule.locked_amount(tge, halfway).unwrap(), 475_000);
+        assert_eq!(
+            position.voting_weight(&schedule, tge, halfway).unwrap(),
+            475_000
+        );
+        assert_eq!(position.voting_weight(&schedule, tge, i64::MAX).unwrap(), 0);
+
+        // Releasing does not change what is locked.
+        schedule.released_amount = schedule.releasable(tge, halfway).unwrap();
+        assert_eq!(
+            position.voting_weight(&schedule, tge, halfway).unwrap(),
+            475_000
+        );
+
+        schedule.revoke(tge, halfway).unwrap();
+        assert_eq!(position.voting_weight(&schedule, tge, halfway).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_weight_and_release_never_double_count() {
+        let tge = 1_000_000;
+        let schedule = schedule();
+        let position = StakePosition {
+            staked_amount: schedule.total_amount,
+            ..StakePosition::default()
+        };
+        for month in 0..=16 {
+            let now = tge + month * MONTH;
+            let weight = position.voting_weight(&schedule, tge, now).unwrap();
+            let vested = schedule.vested_amount(tge, now).unwrap();
+            assert_eq!(weight + vested, schedule.total_amount, "month {month}");
+        }
+    }
+
+    /// Accounts for `stake_locked`, in declaration order, for a schedule
+    /// whose mint had its TGE at `tge` and whose beneficiary has
+    /// `staked_amount` staked already.
+    fn stake_accounts(
+        schedule: &VestingSchedule,
+        tge: i64,
+        staked_amount: u64,
+    ) -> Vec<TestAccount> {
+        let schedule_key = Pubkey::new_unique();
+        let (position_key, bump) =
+            Pubkey::find_program_address(&[b"stake_position", schedule_key.as_ref()], &nlov::ID);
+        let position = StakePosition {
+            vesting_schedule: schedule_key,
+            beneficiary: schedule.beneficiary,
+            staked_amount,
+            bump,
+        };
+        let mut accounts = vec![
+            TestAccount::anchor(schedule_key, schedule, VestingSchedule::INIT_SPACE),
+            TestAccount::anchor(position_key, &position, StakePosition::INIT_SPACE),
+            TestAccount::signer(schedule.beneficiary),
+            TestAccount::program(anchor_lang::system_program::ID),
+            TestAccount::emission_schedule_with_tge(&schedule.mint, tge),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    /// `stake_accounts` without the system program, as `unstake_locked`
+    /// takes them.
+    fn unstake_accounts(
+        schedule: &VestingSchedule,
+        tge: i64,
+        staked_amount: u64,
+    ) -> Vec<TestAccount> {
+        let mut accounts = stake_accounts(schedule, tge, staked_amount);
+        accounts.remove(3);
+        accounts
+    }
+
+    fn stake(accounts: &mut [TestAccount], amount: u64) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = StakeLockedBumps::default();
+        let mut ix = StakeLocked::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::stake_locked(Context::new(&nlov::ID, &mut ix, &[], bumps), amount)?;
+        ix.exit(&nlov::ID)
+    }
+
+    fn unstake(accounts: &mut [TestAccount], amount: u64) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = UnstakeLockedBumps::default();
+        let mut ix = UnstakeLocked::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::unstake_locked(Context::new(&nlov::ID, &mut ix, &[], bumps), amount)?;
+        ix.exit(&nlov::ID)
+    }
+
+    fn staked_amount(accounts: &[TestAccount]) -> u64 {
+        StakePosition::try_deserialize(&mut &accounts[1].data[..])
+            .unwrap()
+            .staked_amount
+    }
+
+    #[test]
+    fn test_stake_locked() {
+        // Still in the cliff: 950,000 locked.
+        let schedule = schedule();
+        let mut accounts = stake_accounts(&schedule, NOW - MONTH, 0);
+        stake(&mut accounts, 600_000).unwrap();
+        stake(&mut accounts, 350_000).unwrap();
+        assert_eq!(staked_amount(&accounts), 950_000);
+        let event = &emitted::<LockedStakeChanged>()[1];
+        assert_eq!(event.staked_amount, 950_000);
+        assert_eq!(event.voting_weight, 950_000);
+
+        // The vested 5% cannot be staked.
+        assert_eq!(
+            stake(&mut accounts, 1).unwrap_err(),
+            Error::from(ErrorCode::StakeExceedsLocked)
+        );
+    }
+
+    #[test]
+    fn test_stake_locked_requires_beneficiary() {
+        let schedule = schedule();
+        let mut accounts = stake_accounts(&schedule, NOW, 0);
+        accounts[2] = TestAccount::signer(Pubkey::new_unique());
+        assert_eq!(
+            stake(&mut accounts, 1).unwrap_err(),
+            Error::from(ErrorCode::Unauthorized)
+        );
+    }
+
+    #[test]
+    fn test_stake_locked_after_revocation() {
+        let mut schedule = schedule();
+        schedule.revoke(NOW - MONTH, NOW - 1).unwrap();
+        assert_eq!(
+            stake(&mut stake_accounts(&schedule, NOW - MONTH, 0), 1).unwrap_err(),
+            Error::from(ErrorCode::StakeExceedsLocked)
+        );
+    }
+
+    #[test]
+    fn test_unstake_locked() {
+        let schedule = schedule();
+        let mut accounts = unstake_accounts(&schedule, NOW - MONTH, 500_000);
+        unstake(&mut accounts, 200_000).unwrap();
+        assert_eq!(staked_amount(&accounts), 300_000);
+        assert_eq!(emitted::<LockedStakeChanged>()[0].voting_weight, 300_000);
+
+        for amount in [0, 300_001] {
+            assert_eq!(
+                unstake(&mut accounts, amount).unwrap_err(),
+                Error::from(ErrorCode::InvalidAmount)
+            );
+        }
+    }
+}
@@ -0,0 +1,241 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::{AccountDeserialize, Space};
+    use nlov::emission::EmissionSchedule;
+    use nlov::vesting::{VestingCategory, VestingSchedule, VESTING_MONTH};
+    use nlov::{ErrorCode, Presale, SalePhase, MAX_TGE_POSTPONEMENT, MAX_TGE_POSTPONEMENTS};
+
+    use crate::common::{event_cpi_accounts, postpone_tge, presale, set_tge, TestAccount, NOW};
+
+    const END_TIME: i64 = 1_700_000_000;
+    const DAY: i64 = 24 * 60 * 60;
+
+    fn scheduled_presale() -> Presale {
+        Presale {
+            start_time: END_TIME - 7 * DAY,
+            end_time: END_TIME,
+            ..presale(SalePhase::Pending)
+        }
+    }
+
+    #[test]
+    fn test_claim_start_requires_tge() {
+        assert_eq!(
+            scheduled_presale().claim_start().unwrap_err(),
+            Error::from(ErrorCode::TgeNotSet)
+        );
+    }
+
+    #[test]
+    fn test_schedule_tge() {
+        let mut presale = scheduled_presale();
+        presale.schedule_tge(END_TIME + 10 * DAY, END_TIME).unwrap();
+        assert_eq!(presale.claim_start().unwrap(), END_TIME + 10 * DAY);
+        assert_eq!(presale.tge_postponements, 0);
+    }
+
+    #[test]
+    fn test_schedule_tge_only_once() {
+        let mut presale = scheduled_presale();
+        presale.schedule_tge(END_TIME + 10 * DAY, END_TIME).unwrap();
+        assert_eq!(
+            presale
+                .schedule_tge(END_TIME + 20 * DAY, END_TIME)
+                .unwrap_err(),
+            Error::from(ErrorCode::TgeAlreadySet)
+        );
+    }
+
+    #[test]
+    fn test_schedule_tge_rejects_past_or_before_sale_end() {
+        let mut presale = scheduled_presale();
+        assert_eq!(
+            presale
+                .schedule_tge(END_TIME - DAY, END_TIME - 2 * DAY)
+                .unwrap_err(),
+            Error::from(ErrorCode::InvalidTgeTimestamp)
+        );
+        assert_eq!(
+            presale
+                .schedule_tge(END_TIME + DAY, END_TIME + 2 * DAY)
+                .unwrap_err(),
+            Error::from(ErrorCode::InvalidTgeTimestamp)
+        );
+    }
+
+    #[test]
+    fn test_postpone_tge() {
+        let mut presale = scheduled_presale();
+        let tge = END_TIME + 10 * DAY;
+        presale.schedule_tge(tge, END_TIME).unwrap();
+        presale
+            .postpone_tge(tge + MAX_TGE_POSTPONEMENT, END_TIME)
+            .unwrap();
+        assert_eq!(presale.claim_start().unwrap(), tge + MAX_TGE_POSTPONEMENT);
+        assert_eq!(presale.tge_postponements, 1);
+    }
+
+    #[test]
+    fn test_postpone_tge_limits() {
+        let mut presale = scheduled_presale();
+        let mut tge = END_TIME + 10 * DAY;
+        presale.schedule_tge(tge, END_TIME).unwrap();
+
+        assert_eq!(
+            presale
+                .postpone_tge(tge + MAX_TGE_POSTPONEMENT + 1, END_TIME)
+                .unwrap_err(),
+            Error::from(ErrorCode::InvalidTgeTimestamp)
+        );
+        assert_eq!(
+            presale.postpone_tge(tge - DAY, END_TIME).unwrap_err(),
+            Error::from(ErrorCode::InvalidTgeTimestamp)
+        );
+
+        for _ in 0..MAX_TGE_POSTPONEMENTS {
+            tge += DAY;
+            presale.postpone_tge(tge, END_TIME).unwrap();
+        }
+        assert_eq!(
+            presale.postpone_tge(tge + DAY, END_TIME).unwrap_err(),
+            Error::from(ErrorCode::TgePostponementLimit)
+        );
+    }
+
+    #[test]
+    fn test_postpone_tge_after_it_arrived() {
+        let mut presale = scheduled_presale();
+        let tge = END_TIME + 10 * DAY;
+        presale.schedule_tge(tge, END_TIME).unwrap();
+        assert_eq!(
+            presale.postpone_tge(tge + DAY, tge).unwrap_err(),
+            Error::from(ErrorCode::TgeAlreadyReached)
+        );
+    }
+
+    #[test]
+    fn test_postpone_requires_schedule() {
+        assert_eq!(
+            scheduled_presale()
+                .postpone_tge(END_TIME + DAY, END_TIME)
+                .unwrap_err(),
+            Error::from(ErrorCode::TgeNotSet)
+        );
+    }
+
+    const EMISSION_SCHEDULE: usize = 5;
+
+    /// Accounts for `set_tge` and `postpone_tge` by the owner of a finalized
+    /// sale with its TGE at `tge`, whose mint's emission schedule follows
+    /// `followed`, or this sale if `None`.
+    fn set_tge_accounts(tge: i64, followed: Option<Pubkey>) -> Vec<TestAccount> {
+        let key = Pubkey::new_unique();
+        let presale = Presale {
+            phase: SalePhase::Finalized,
+            owner: Pubkey::new_unique(),
+            token_mint: Pubkey::new_unique(),
+            tge_timestamp: tge,
+            ..scheduled_presale()
+        };
+        let mint_tge = if followed.is_none() { tge } else { 0 };
+        let mut accounts = vec![
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+            TestAccount::signer(presale.owner),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::sale_emission_schedule(
+                &presale.token_mint,
+                &followed.unwrap_or(key),
+                mint_tge,
+            ),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn mint_tge(accounts: &[TestAccount]) -> i64 {
+        EmissionSchedule::try_deserialize(&mut &accounts[EMISSION_SCHEDULE].data[..])
+            .unwrap()
+            .tge()
+    }
+
+    #[test]
+    fn test_set_tge_needs_schedule_only_for_genesis_sales() {
+        let mut accounts = set_tge_accounts(0, None);
+        accounts[EMISSION_SCHEDULE] = TestAccount::absent();
+        set_tge(&mut accounts, NOW + DAY).unwrap();
+
+        let mut accounts = set_tge_accounts(0, None);
+        let mut presale = Presale::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+        presale.genesis_funded = true;
+        accounts[0] = TestAccount::anchor(accounts[0].key, &presale, Presale::INIT_SPACE);
+        accounts[EMISSION_SCHEDULE] = TestAccount::absent();
+        assert_eq!(
+            set_tge(&mut accounts, NOW + DAY).unwrap_err(),
+            Error::from(ErrorCode::MissingEmissionSchedule)
+        );
+    }
+
+    #[test]
+    fn test_set_tge_schedules_mint_tge() {
+        let mut accounts = set_tge_accounts(0, None);
+        assert_eq!(mint_tge(&accounts), i64::MAX);
+        set_tge(&mut accounts, NOW + DAY).unwrap();
+        assert_eq!(mint_tge(&accounts), NOW + DAY);
+    }
+
+    #[test]
+    fn test_postpone_tge_moves_vesting() {
+        let tge = NOW + DAY;
+        let mut accounts = set_tge_accounts(tge, None);
+        // Liquidity unlocks 30% at TGE.
+        let terms = VestingCategory::Liquidity.terms();
+        let schedule = VestingSchedule {
+            mint: Pubkey::new_unique(),
+            id: 0,
+            beneficiary: Pubkey::new_unique(),
+            category: VestingCategory::Liquidity,
+            escrow: Pubkey::new_unique(),
+            total_amount: 1_000,
+            released_amount: 0,
+            tge_bps: terms.tge_bps,
+            cliff_duration: 0,
+            linear_duration: terms.linear_months as i64 * VESTING_MONTH,
+            bump: 0,
+            revocable: false,
+            revoked_at: 0,
+            step_period: 0,
+            step_bps: Vec::new(),
+        };
+        assert_eq!(
+            schedule.vested_amount(mint_tge(&accounts), tge).unwrap(),
+            300
+        );
+
+        postpone_tge(&mut accounts, tge + DAY).unwrap();
+        assert_eq!(mint_tge(&accounts), tge + DAY);
+        assert_eq!(schedule.vested_amount(mint_tge(&accounts), tge).unwrap(), 0);
+        assert_eq!(
+            schedule
+                .vested_amount(mint_tge(&accounts), tge + DAY)
+                .unwrap(),
+            300
+        );
+    }
+
+    #[test]
+    fn test_postpone_tge_of_other_sale_keeps_mint_tge() {
+        let tge = NOW + DAY;
+        let mut accounts = set_tge_accounts(tge, Some(Pubkey::new_unique()));
+        postpone_tge(&mut accounts, tge + DAY).unwrap();
+
+        let presale = Presale::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+        assert_eq!(presale.claim_start().unwrap(), tge + DAY);
+        assert_eq!(mint_tge(&accounts), i64::MAX);
+    }
+}
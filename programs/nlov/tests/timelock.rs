@@ -0,0 +1,528 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::{Context, Pubkey};
+    use anchor_lang::{AccountDeserialize, Accounts, AccountsExit, Space};
+    use nlov::council::{AdminAction, Council};
+    use nlov::neurolov_presale;
+    use nlov::timelock::{QueuedAction, MAX_TIMELOCK_DELAY, MIN_TIMELOCK_DELAY};
+    use nlov::{
+        ActionQueued, AdminUpdate, AdminUpdateBumps, CancelQueuedAction, CancelQueuedActionBumps,
+        CreateCouncil, CreateCouncilBumps, ErrorCode, OwnershipTransferred, Presale, QueueAction,
+        QueueActionBumps, QueuedActionCancelled, QueuedActionExecuted, SalePhase,
+        TimelockDelayChanged,
+    };
+
+    use crate::common::{
+        emitted, event_cpi_accounts, infos, install_stubs, presale, set_tge, stored_presale,
+        TestAccount, NOW,
+    };
+
+    const PRESALE: usize = 0;
+    const DELAY: i64 = 2 * 86_400;
+    const TGE: i64 = NOW + 86_400;
+
+    fn timelocked_presale(timelock_delay: i64) -> Presale {
+        Presale {
+            end_time: NOW,
+            owner: Pubkey::new_unique(),
+            timelock_delay,
+            ..presale(SalePhase::Pending)
+        }
+    }
+
+    fn queued_action(presale: Pubkey, action: AdminAction, eta: i64) -> QueuedAction {
+        QueuedAction {
+            presale,
+            id: 0,
+            action,
+            queued_by: Pubkey::new_unique(),
+            council: Pubkey::default(),
+            eta,
+            executed: false,
+            cancelled: false,
+            bump: 0,
+        }
+    }
+
+    /// Accounts for an `AdminUpdate` instruction by the owner of a sale with
+    /// a two-day timelock, spending `queued`.
+    fn timelocked_accounts(queued: Option<(AdminAction, i64)>) -> Vec<TestAccount> {
+        let key = Pubkey::new_unique();
+        let presale = timelocked_presale(DELAY);
+        let mut accounts = vec![
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+            TestAccount::signer(presale.owner),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            match queued {
+                Some((action, eta)) => TestAccount::anchor(
+                    Pubkey::new_unique(),
+                    &queued_action(key, action, eta),
+                    QueuedAction::INIT_SPACE,
+                ),
+                None => TestAccount::absent(),
+            },
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    /// `timelocked_accounts` plus the mint's emission schedule, as `set_tge`
+    /// takes them.
+    fn set_tge_accounts(queued: Option<(AdminAction, i64)>) -> Vec<TestAccount> {
+        let mut accounts = timelocked_accounts(queued);
+        let presale = stored_presale(&accounts[PRESALE]);
+        let emission_schedule =
+            TestAccount::sale_emission_schedule(&presale.token_mint, &accounts[PRESALE].key, 0);
+        accounts.insert(5, emission_schedule);
+        accounts
+    }
+
+    /// Accounts for an `AdminUpdate` instruction by the owner of a sale
+    /// without a timelock.
+    fn admin_update_accounts() -> Vec<TestAccount> {
+        let presale = timelocked_presale(0);
+        let mut accounts = vec![
+            TestAccount::anchor(Pubkey::new_unique(), &presale, Presale::INIT_SPACE),
+            TestAccount::signer(presale.owner),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::absent(),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn admin_update(
+        accounts: &mut [TestAccount],
+        handler: impl FnOnce(Context<AdminUpdate>) -> anchor_lang::Result<()>,
+    ) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = AdminUpdateBumps::default();
+        let mut ix = AdminUpdate::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        handler(Context::new(&nlov::ID, &mut ix, &[], bumps))?;
+        ix.exit(&nlov::ID)
+    }
+
+    /// Accounts for `create_council` by the owner of a sale with a two-day
+    /// timelock, spending `queued`.
+    fn create_council_accounts(queued: Option<AdminAction>) -> Vec<TestAccount> {
+        let key = Pubkey::new_unique();
+        let presale = timelocked_presale(DELAY);
+        let (council, _) = Pubkey::find_program_address(&[b"council", key.as_ref()], &nlov::ID);
+        let mut accounts = vec![
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+            TestAccount::uninitialized(council, Council::INIT_SPACE),
+            TestAccount::signer(presale.owner),
+            TestAccount::program(anchor_lang::system_program::ID),
+            match queued {
+                Some(action) => TestAccount::anchor(
+                    Pubkey::new_unique(),
+                    &queued_action(key, action, NOW),
+                    QueuedAction::INIT_SPACE,
+                ),
+                None => TestAccount::absent(),
+            },
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn create_council(
+        accounts: &mut [TestAccount],
+        members: Vec<Pubkey>,
+        threshold: u8,
+    ) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = CreateCouncilBumps::default();
+        let mut ix = CreateCouncil::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::create_council(
+            Context::new(&nlov::ID, &mut ix, &[], bumps),
+            members,
+            threshold,
+        )?;
+        ix.exit(&nlov::ID)
+    }
+
+    fn queue(accounts: &mut [TestAccount], action: AdminAction) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = QueueActionBumps::default();
+        let mut ix = QueueAction::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::queue_action(Context::new(&nlov::ID, &mut ix, &[], bumps), action)?;
+        ix.exit(&nlov::ID)
+    }
+
+    /// Accounts for `queue_action` by the owner of a sale with the given delay.
+    fn queue_accounts(timelock_delay: i64) -> Vec<TestAccount> {
+        let key = Pubkey::new_unique();
+        let presale = timelocked_presale(timelock_delay);
+        let queued_action_key = Pubkey::find_program_address(
+            &[b"queued_action", key.as_ref(), &0u64.to_le_bytes()],
+            &nlov::ID,
+        )
+        .0;
+        let mut accounts = vec![
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+            TestAccount::uninitialized(queued_action_key, QueuedAction::INIT_SPACE),
+            TestAccount::signer(presale.owner),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::program(anchor_lang::system_program::ID),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn cancel(accounts: &mut [TestAccount]) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = CancelQueuedActionBumps::default();
+        let mut ix = CancelQueuedAction::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::cancel_queued_action(Context::new(&nlov::ID, &mut ix, &[], bumps))?;
+        ix.exit(&nlov::ID)
+    }
+
+    #[test]
+    fn test_execute_queued_action() {
+        let action = AdminAction::Withdraw { amount: 1_000 };
+        let mut queued = queued_action(Pubkey::new_unique(), action, NOW + DELAY);
+
+        assert_eq!(
+            queued
+                .execute(&action, Pubkey::default(), NOW + DELAY - 1)
+                .unwrap_err(),
+            Error::from(ErrorCode::TimelockNotExpired)
+        );
+        assert_eq!(
+            queued
+                .execute(
+                    &AdminAction::Withdraw { amount: 1_001 },
+                    Pubkey::default(),
+                    NOW + DELAY
+                )
+                .unwrap_err(),
+            Error::from(ErrorCode::QueuedActionMismatch)
+        );
+        // Queued before a council took over, so it no longer applies.
+        assert_eq!(
+            queued
+                .execute(&action, Pubkey::new_unique(), NOW + DELAY)
+                .unwrap_err(),
+            Error::from(ErrorCode::QueuedActionMismatch)
+        );
+
+        queued
+            .execute(&action, Pubkey::default(), NOW + DELAY)
+            .unwrap();
+        assert!(queued.executed);
+        assert_eq!(
+            queued
+                .execute(&action, Pubkey::default(), NOW + DELAY)
+                .unwrap_err(),
+            Error::from(ErrorCode::QueuedActionAlreadyExecuted)
+        );
+        assert_eq!(
+            queued.cancel().unwrap_err(),
+            Error::from(ErrorCode::QueuedActionAlreadyExecuted)
+        );
+    }
+
+    #[test]
+    fn test_cancelled_action_cannot_run() {
+        let action = AdminAction::Withdraw { amount: 1_000 };
+        let mut queued = queued_action(Pubkey::new_unique(), action, NOW);
+
+        queued.cancel().unwrap();
+        assert_eq!(
+            queued.execute(&action, Pubkey::default(), NOW).unwrap_err(),
+            Error::from(ErrorCode::QueuedActionCancelled)
+        );
+        assert_eq!(
+            queued.cancel().unwrap_err(),
+            Error::from(ErrorCode::QueuedActionCancelled)
+        );
+    }
+
+    #[test]
+    fn test_timelocked_set_tge_needs_queued_action() {
+        let mut accounts = set_tge_accounts(None);
+        assert_eq!(
+            set_tge(&mut accounts, TGE).unwrap_err(),
+            Error::from(ErrorCode::QueuedActionRequired)
+        );
+    }
+
+    #[test]
+    fn test_timelocked_set_tge_waits_for_eta() {
+        let action = AdminAction::SetTge { tge_timestamp: TGE };
+        let mut accounts = set_tge_accounts(Some((action, NOW + 1)));
+        assert_eq!(
+            set_tge(&mut accounts, TGE).unwrap_err(),
+            Error::from(ErrorCode::TimelockNotExpired)
+        );
+    }
+
+    #[test]
+    fn test_timelocked_set_tge_spends_queued_action() {
+        let action = AdminAction::SetTge { tge_timestamp: TGE };
+        let mut accounts = set_tge_accounts(Some((action, NOW)));
+        set_tge(&mut accounts, TGE).unwrap();
+
+        assert_eq!(stored_presale(&accounts[PRESALE]).tge_timestamp, TGE);
+        let queued = QueuedAction::try_deserialize(&mut &accounts[4].data[..]).unwrap();
+        assert!(queued.executed);
+
+        let events = emitted::<QueuedActionExecuted>();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].queued_action, accounts[4].key);
+        assert_eq!(events[0].executor, accounts[1].key);
+        assert_eq!(events[0].action, action);
+    }
+
+    #[test]
+    fn test_timelocked_cancel_presale() {
+        let mut accounts = timelocked_accounts(None);
+        assert_eq!(
+            admin_update(&mut accounts, neurolov_presale::cancel_presale).unwrap_err(),
+            Error::from(ErrorCode::QueuedActionRequired)
+        );
+
+        let mut accounts = timelocked_accounts(Some((AdminAction::CancelPresale, NOW)));
+        admin_update(&mut accounts, neurolov_presale::cancel_presale).unwrap();
+        assert_eq!(
+            stored_presale(&accounts[PRESALE]).phase,
+            SalePhase::Refunding
+        );
+        assert_eq!(emitted::<QueuedActionExecuted>().len(), 1);
+    }
+
+    #[test]
+    fn test_timelocked_create_council() {
+        let members = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut accounts = create_council_accounts(None);
+        assert_eq!(
+            create_council(&mut accounts, members.clone(), 1).unwrap_err(),
+            Error::from(ErrorCode::QueuedActionRequired)
+        );
+
+        let action = AdminAction::CreateCouncil {
+            members_hash: Council::members_hash(&members),
+            threshold: 2,
+        };
+        let mut accounts = create_council_accounts(Some(action));
+        assert_eq!(
+            create_council(&mut accounts, members.clone(), 1).unwrap_err(),
+            Error::from(ErrorCode::QueuedActionMismatch)
+        );
+
+        create_council(&mut accounts, members, 2).unwrap();
+        assert_eq!(stored_presale(&accounts[PRESALE]).council, accounts[1].key);
+        let queued = QueuedAction::try_deserialize(&mut &accounts[4].data[..]).unwrap();
+        assert!(queued.executed);
+    }
+
+    #[test]
+    fn test_queue_action() {
+        let mut accounts = queue_accounts(DELAY);
+        let action = AdminAction::TransferOwnership {
+            new_owner: Pubkey::new_unique(),
+        };
+        queue(&mut accounts, action).unwrap();
+
+        let queued = QueuedAction::try_deserialize(&mut &accounts[1].data[..]).unwrap();
+        assert_eq!(queued.presale, accounts[PRESALE].key);
+        assert_eq!(queued.action, action);
+        assert_eq!(queued.queued_by, accounts[2].key);
+        assert_eq!(queued.eta, NOW + DELAY);
+        assert_eq!(stored_presale(&accounts[PRESALE]).queued_action_count, 1);
+
+        let events = emitted::<ActionQueued>();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].queued_action, accounts[1].key);
+        assert_eq!(events[0].eta, NOW + DELAY);
+    }
+
+    #[test]
+    fn test_queue_action_checks_authority() {
+        let action = AdminAction::Withdraw { amount: 1_000 };
+
+        let mut accounts = queue_accounts(0);
+        assert_eq!(
+            queue(&mut accounts, action).unwrap_err(),
+            Error::from(ErrorCode::TimelockDisabled)
+        );
+
+        let mut accounts = queue_accounts(DELAY);
+        accounts[2] = TestAccount::signer(Pubkey::new_unique());
+        assert_eq!(
+            queue(&mut accounts, action).unwrap_err(),
+            Error::from(ErrorCode::Unauthorized)
+        );
+    }
+
+    #[test]
+    fn test_cancel_queued_action() {
+        let key = Pubkey::new_unique();
+        let presale = timelocked_presale(DELAY);
+        let action = AdminAction::Withdraw { amount: 1_000 };
+        let mut accounts = vec![
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+            TestAccount::anchor(
+                Pubkey::new_unique(),
+                &queued_action(key, action, NOW + DELAY),
+                QueuedAction::INIT_SPACE,
+            ),
+            TestAccount::signer(Pubkey::new_unique()),
+            TestAccount::absent(),
+        ];
+        accounts.extend(event_cpi_accounts());
+        assert_eq!(
+            cancel(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::Unauthorized)
+        );
+
+        accounts[2] = TestAccount::signer(presale.owner);
+        cancel(&mut accounts).unwrap();
+        let queued = QueuedAction::try_deserialize(&mut &accounts[1].data[..]).unwrap();
+        assert!(queued.cancelled);
+
+        let events = emitted::<QueuedActionCancelled>();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].queued_action, accounts[1].key);
+        assert_eq!(events[0].cancelled_by, presale.owner);
+    }
+
+    #[test]
+    fn test_set_timelock_delay() {
+        let mut accounts = admin_update_accounts();
+        for delay in [0, MIN_TIMELOCK_DELAY - 1, MAX_TIMELOCK_DELAY + 1] {
+            assert_eq!(
+                admin_update(&mut accounts, |ctx| {
+                    neurolov_presale::set_timelock_delay(ctx, delay)
+                })
+                .unwrap_err(),
+                Error::from(ErrorCode::InvalidTimelockDelay)
+            );
+        }
+
+        admin_update(&mut accounts, |ctx| {
+            neurolov_presale::set_timelock_delay(ctx, DELAY)
+        })
+        .unwrap();
+        assert_eq!(stored_presale(&accounts[PRESALE]).timelock_delay, DELAY);
+
+        // From now on the owner has to queue changes, including to the delay.
+        assert_eq!(
+            admin_update(&mut accounts, |ctx| neurolov_presale::set_timelock_delay(
+                ctx,
+                MIN_TIMELOCK_DELAY
+            ))
+            .unwrap_err(),
+            Error::from(ErrorCode::QueuedActionRequired)
+        );
+        let event = &emitted::<TimelockDelayChanged>()[0];
+        assert_eq!((event.previous_delay, event.delay), (0, DELAY));
+        assert_eq!(event.effective_at, NOW);
+    }
+
+    #[test]
+    fn test_timelock_delay_reduction_waits_out_current_delay() {
+        let action = AdminAction::SetTimelockDelay {
+            delay: MIN_TIMELOCK_DELAY,
+        };
+        let mut accounts = timelocked_accounts(Some((action, NOW)));
+        admin_update(&mut accounts, |ctx| {
+            neurolov_presale::set_timelock_delay(ctx, MIN_TIMELOCK_DELAY)
+        })
+        .unwrap();
+
+        let event = &emitted::<TimelockDelayChanged>()[0];
+        assert_eq!(event.previous_delay, DELAY);
+        assert_eq!(event.effective_at, NOW + DELAY);
+        let presale = stored_presale(&accounts[PRESALE]);
+        assert_eq!(presale.timelock_delay_at(NOW), DELAY);
+        assert_eq!(presale.timelock_delay_at(NOW + DELAY - 1), DELAY);
+        assert_eq!(presale.timelock_delay_at(NOW + DELAY), MIN_TIMELOCK_DELAY);
+    }
+
+    #[test]
+    fn test_change_timelock_delay() {
+        let mut presale = timelocked_presale(DELAY);
+        assert_eq!(
+            presale
+                .change_timelock_delay(MIN_TIMELOCK_DELAY, NOW)
+                .unwrap(),
+            NOW + DELAY
+        );
+
+        // A longer delay applies at once and drops the pending reduction.
+        assert_eq!(presale.change_timelock_delay(3 * DELAY, NOW).unwrap(), NOW);
+        assert_eq!(presale.timelock_delay_at(NOW + DELAY), 3 * DELAY);
+
+        // A reduction that has taken effect is the base for the next change.
+        presale
+            .change_timelock_delay(MIN_TIMELOCK_DELAY, NOW)
+            .unwrap();
+        let later = NOW + 3 * DELAY;
+        assert_eq!(presale.change_timelock_delay(DELAY, later).unwrap(), later);
+        assert_eq!(presale.timelock_delay_at(later), DELAY);
+        assert_eq!(presale.pending_timelock_delay_at, 0);
+    }
+
+    #[test]
+    fn test_transfer_ownership() {
+        let mut accounts = admin_update_accounts();
+        let previous_owner = accounts[1].key;
+        assert_eq!(
+            admin_update(&mut accounts, |ctx| {
+                neurolov_presale::transfer_ownership(ctx, Pubkey::default())
+            })
+            .unwrap_err(),
+            Error::from(ErrorCode::InvalidNewOwner)
+        );
+
+        let new_owner = Pubkey::new_unique();
+        admin_update(&mut accounts, |ctx| {
+            neurolov_presale::transfer_ownership(ctx, new_owner)
+        })
+        .unwrap();
+        assert_eq!(stored_presale(&accounts[PRESALE]).owner, new_owner);
+
+        let events = emitted::<OwnershipTransferred>();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].previous_owner, previous_owner);
+        assert_eq!(events[0].new_owner, new_owner);
+    }
+}
@@ -0,0 +1,256 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::{AccountDeserialize, Space};
+    use nlov::vesting::{
+        UnlockSchedule, VestingCategory, VestingSchedule, MAX_UNLOCK_STEPS, VESTING_MONTH,
+    };
+    use nlov::{ErrorCode, Presale, UserInfo, VestingReleased};
+
+    use crate::common::{emitted, event_cpi_accounts, release, TestAccount, NOW};
+
+    const TGE: i64 = 1_000_000;
+    const MONTH: i64 = VESTING_MONTH;
+
+    fn steps(tge_bps: u16, cliff_months: i64, step_bps: &[u16]) -> UnlockSchedule<'_> {
+        UnlockSchedule {
+            tge_bps,
+            cliff_duration: cliff_months * MONTH,
+            linear_duration: step_bps.len() as i64 * MONTH,
+            step_period: MONTH,
+            step_bps,
+        }
+    }
+
+    #[test]
+    fn test_monthly_steps() {
+        // The improved public sale structure: 25% at TGE, then 15% a month.
+        let unlock = steps(2_500, 0, &[1_500; 5]);
+        let total = 1_000_000;
+
+        assert_eq!(unlock.vested_amount(total, TGE, TGE - 1).unwrap(), 0);
+        assert_eq!(unlock.vested_amount(total, TGE, TGE).unwrap(), 250_000);
+        // Nothing between steps.
+        assert_eq!(
+            unlock.vested_amount(total, TGE, TGE + MONTH - 1).unwrap(),
+            250_000
+        );
+        for month in 1..=5 {
+            assert_eq!(
+                unlock
+                    .vested_amount(total, TGE, TGE + month * MONTH)
+                    .unwrap(),
+                250_000 + 150_000 * month as u64
+            );
+        }
+        assert_eq!(unlock.vested_amount(total, TGE, i64::MAX).unwrap(), total);
+    }
+
+    #[test]
+    fn test_uneven_steps_after_cliff() {
+        // A 2 month cliff, then 10%, 30% and 60%.
+        let unlock = steps(0, 2, &[1_000, 3_000, 6_000]);
+        let cliff_end = TGE + 2 * MONTH;
+        let total = 999;
+
+        assert_eq!(unlock.vested_amount(total, TGE, TGE).unwrap(), 0);
+        assert_eq!(unlock.vested_amount(total, TGE, cliff_end).unwrap(), 0);
+        assert_eq!(
+            unlock.vested_amount(total, TGE, cliff_end + MONTH).unwrap(),
+            99
+        );
+        assert_eq!(
+            unlock
+                .vested_amount(total, TGE, cliff_end + 2 * MONTH)
+                .unwrap(),
+            399
+        );
+        // The last step releases the rounding dust too.
+        assert_eq!(
+            unlock
+                .vested_amount(total, TGE, cliff_end + 3 * MONTH)
+                .unwrap(),
+            total
+        );
+    }
+
+    #[test]
+    fn test_linear_is_unchanged() {
+        let linear = UnlockSchedule {
+            tge_bps: 1_000,
+            cliff_duration: 0,
+            linear_duration: 10 * MONTH,
+            step_period: 0,
+            step_bps: &[],
+        };
+        assert_eq!(linear.vested_amount(1_000, TGE, TGE).unwrap(), 100);
+        assert_eq!(
+            linear.vested_amount(1_000, TGE, TGE + MONTH / 2).unwrap(),
+            145
+        );
+        assert_eq!(
+            linear.vested_amount(1_000, TGE, TGE + 10 * MONTH).unwrap(),
+            1_000
+        );
+    }
+
+    #[test]
+    fn test_endless_cliff_holds_rest() {
+        let unlock = UnlockSchedule {
+            tge_bps: 2_500,
+            cliff_duration: i64::MAX,
+            linear_duration: 0,
+            step_period: 0,
+            step_bps: &[],
+        };
+        assert_eq!(unlock.vested_amount(1_000, TGE, TGE - 1).unwrap(), 0);
+        assert_eq!(unlock.vested_amount(1_000, TGE, i64::MAX - 1).unwrap(), 250);
+    }
+
+    #[test]
+    fn test_presale_claims_follow_category() {
+        let mut presale = Presale::default();
+        assert_eq!(
+            presale.claim_start().unwrap_err(),
+            Error::from(ErrorCode::TgeNotSet)
+        );
+
+        // Presale: 10% at TGE, then 8 months linear.
+        presale.tge_timestamp = TGE;
+        let (unlock, start) = (presale.unlock(), presale.claim_start().unwrap());
+        let user_info = UserInfo {
+            nlov_allocated: 1_000,
+            amount_claimed: 100,
+            ..UserInfo::default()
+        };
+        assert_eq!(user_info.claimable(&unlock, start, TGE - 1).unwrap(), 0);
+        assert_eq!(user_info.claimable(&unlock, start, TGE).unwrap(), 0);
+        assert_eq!(
+            user_info
+                .claimable(&unlock, start, TGE + 4 * MONTH)
+                .unwrap(),
+            450
+        );
+        assert_eq!(
+            user_info
+                .claimable(&unlock, start, TGE + 8 * MONTH)
+                .unwrap(),
+            900
+        );
+    }
+
+    #[test]
+    fn test_check_unlock() {
+        // Seed sale: 5% at TGE, 3 months cliff, then 12 months. 9,500 bps do
+        // not split evenly, so the larger steps come last.
+        let seed = VestingCategory::SeedSale.terms();
+        let mut monthly = [791; 12];
+        monthly[4..].fill(792);
+        seed.check_unlock(&steps(500, 3, &monthly)).unwrap();
+        // Or quarterly.
+        seed.check_unlock(&UnlockSchedule {
+            linear_duration: 12 * MONTH,
+            step_period: 3 * MONTH,
+            ..steps(500, 3, &[2_375; 4])
+        })
+        .unwrap();
+
+        // Larger steps first would be ahead of the linear release.
+        let mut front_loaded = [792; 12];
+        front_loaded[8..].fill(791);
+        let too_many = [1; MAX_UNLOCK_STEPS + 1];
+        let invalid = [
+            steps(500, 3, &front_loaded),
+            // Not adding up to the rest.
+            steps(500, 3, &[791; 12]),
+            // Shorter than the category.
+            steps(500, 3, &[1_000, 1_000, 1_000, 1_000, 5_500]),
+            steps(
+                500,
+                3,
+                &[0, 791, 791, 791, 791, 791, 791, 791, 791, 791, 791, 1_799],
+            ),
+            steps(500, 3, &too_many),
+            UnlockSchedule {
+                step_period: 0,
+                ..steps(500, 3, &monthly)
+            },
+            UnlockSchedule {
+                linear_duration: 11 * MONTH,
+                ..steps(500, 3, &monthly)
+            },
+        ];
+        for unlock in invalid {
+            assert_eq!(
+                seed.check_unlock(&unlock).unwrap_err(),
+                Error::from(ErrorCode::InvalidVestingSchedule),
+                "{unlock:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_check_unlock_case_by_case() {
+        // Partnerships pick their own duration up to 36 months, and the steps
+        // follow that duration.
+        let partnerships = VestingCategory::Partnerships.terms();
+        partnerships
+            .check_unlock(&steps(0, 3, &[2_500; 4]))
+            .unwrap();
+        assert_eq!(
+            partnerships
+                .check_unlock(&steps(0, 3, &[5_000, 2_500, 2_500]))
+                .unwrap_err(),
+            Error::from(ErrorCode::InvalidVestingSchedule)
+        );
+    }
+
+    #[test]
+    fn test_release_steps() {
+        // Public sale tokens: 15% at TGE, then six monthly steps. Two have
+        // passed.
+        let step_bps = vec![1_416, 1_416, 1_417, 1_417, 1_417, 1_417];
+        VestingCategory::PublicSale
+            .terms()
+            .check_unlock(&steps(1_500, 0, &step_bps))
+            .unwrap();
+        let schedule = VestingSchedule {
+            mint: Pubkey::new_unique(),
+            id: 0,
+            beneficiary: Pubkey::new_unique(),
+            category: VestingCategory::PublicSale,
+            escrow: Pubkey::new_unique(),
+            total_amount: 100_000,
+            released_amount: 0,
+            tge_bps: 1_500,
+            cliff_duration: 0,
+            linear_duration: 6 * MONTH,
+            bump: 0,
+            revocable: false,
+            revoked_at: 0,
+            step_period: MONTH,
+            step_bps,
+        };
+        let mut accounts = vec![
+            TestAccount::anchor(Pubkey::new_unique(), &schedule, VestingSchedule::INIT_SPACE),
+            TestAccount::token_account(schedule.escrow, schedule.mint, Pubkey::new_unique()),
+            TestAccount::token_account(Pubkey::new_unique(), schedule.mint, schedule.beneficiary),
+            TestAccount::signer(schedule.beneficiary),
+            TestAccount::program(anchor_spl::token::ID),
+            TestAccount::emission_schedule_with_tge(&schedule.mint, NOW - 2 * MONTH - 1),
+        ];
+        accounts.extend(event_cpi_accounts());
+
+        release(&mut accounts).unwrap();
+
+        let stored = VestingSchedule::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+        assert_eq!(stored.released_amount, 15_000 + 14_160 + 14_160);
+        assert_eq!(
+            emitted::<VestingReleased>()[0].amount,
+            stored.released_amount
+        );
+    }
+}
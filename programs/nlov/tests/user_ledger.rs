@@ -0,0 +1,161 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::solana_program::system_instruction;
+    use anchor_lang::{AccountDeserialize, Space};
+    use nlov::{ErrorCode, Presale, RefundClaimed, SalePhase, UserInfo, NLOV_PER_LAMPORT};
+
+    use crate::common::{
+        claim_refund, emitted, event_cpi_accounts, invoked, presale, user_info, user_info_account,
+        TestAccount, NOW,
+    };
+
+    #[test]
+    fn test_record_contribution() {
+        let mut info = user_info(Pubkey::new_unique(), 0);
+        info.record_contribution(40, 40 * NLOV_PER_LAMPORT, 0, NOW)
+            .unwrap();
+        info.record_contribution(10, 10 * NLOV_PER_LAMPORT, 50, NOW + 60)
+            .unwrap();
+
+        assert_eq!(info.lamports_paid, 50);
+        assert_eq!(info.nlov_allocated, 50 * NLOV_PER_LAMPORT + 50);
+        assert_eq!(info.bonus_allocated, 50);
+        assert_eq!(info.first_contribution_at, NOW);
+        assert_eq!(info.last_contribution_at, NOW + 60);
+        assert_eq!(info.contribution_count, 2);
+    }
+
+    #[test]
+    fn test_record_contribution_rejects_overflow() {
+        let mut info = user_info(Pubkey::new_unique(), 0);
+        info.nlov_allocated = u64::MAX;
+        assert_eq!(
+            info.record_contribution(1, 1, 0, NOW).unwrap_err(),
+            Error::from(ErrorCode::CalculationError)
+        );
+    }
+
+    #[test]
+    fn test_record_claim() {
+        let mut info = user_info(Pubkey::new_unique(), 0);
+        info.record_contribution(40, 1_000, 0, NOW).unwrap();
+
+        info.record_claim(600).unwrap();
+        assert_eq!(info.unclaimed().unwrap(), 400);
+        assert_eq!(
+            info.record_claim(401).unwrap_err(),
+            Error::from(ErrorCode::NothingToClaim)
+        );
+    }
+
+    #[test]
+    fn test_record_refund_keeps_payment_history() {
+        let mut info = user_info(Pubkey::new_unique(), 0);
+        info.record_contribution(40, 800, 200, NOW).unwrap();
+        info.record_claim(900).unwrap();
+
+        assert_eq!(
+            info.lamports_paid_for(info.unclaimed().unwrap()).unwrap(),
+            4
+        );
+        info.record_refund(4).unwrap();
+        assert_eq!(info.unclaimed().unwrap(), 0);
+        assert_eq!(info.nlov_allocated, 900);
+        assert_eq!(info.bonus_allocated, 180);
+        assert_eq!(info.lamports_paid, 40);
+        assert_eq!(info.lamports_refunded, 4);
+        assert_eq!(info.contribution_count, 1);
+    }
+
+    #[test]
+    fn test_lamports_paid_for() {
+        let mut info = user_info(Pubkey::new_unique(), 0);
+        assert_eq!(info.lamports_paid_for(1_000).unwrap(), 0);
+
+        // Allocation migrated from v1, or moved in, need not be at the
+        // contribution rate; the refund follows what was paid.
+        info.nlov_allocated = 1_000;
+        info.lamports_paid = 30;
+        assert_eq!(info.lamports_paid_for(1_000).unwrap(), 30);
+        assert_eq!(info.lamports_paid_for(500).unwrap(), 15);
+        assert_eq!(info.lamports_paid_for(1).unwrap(), 0);
+
+        info.nlov_allocated = u64::MAX;
+        info.lamports_paid = u64::MAX;
+        assert_eq!(info.lamports_paid_for(u64::MAX).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn test_move_allocation_moves_payment_and_bonus() {
+        let mut from = user_info(Pubkey::new_unique(), 0);
+        let mut to = user_info(Pubkey::new_unique(), 0);
+        from.record_contribution(40, 800, 200, NOW).unwrap();
+
+        from.move_allocation(&mut to, 750).unwrap();
+        assert_eq!(from.nlov_allocated, 250);
+        assert_eq!(from.lamports_paid, 10);
+        assert_eq!(from.bonus_allocated, 50);
+        // A refund of the moved allocation goes to whoever holds it.
+        assert_eq!(to.nlov_allocated, 750);
+        assert_eq!(to.lamports_paid, 30);
+        assert_eq!(to.bonus_allocated, 150);
+        assert_eq!(to.contribution_count, 0);
+    }
+
+    /// Accounts for `claim_refund` after `cancel_presale`, by a user holding
+    /// `info`.
+    fn claim_refund_accounts(info: &UserInfo) -> Vec<TestAccount> {
+        let key = Pubkey::new_unique();
+        let presale = Presale {
+            total_contributed: info.nlov_allocated,
+            ..presale(SalePhase::Refunding)
+        };
+        let vault = Pubkey::find_program_address(&[b"vault", key.as_ref()], &nlov::ID).0;
+        let mut accounts = vec![
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+            TestAccount::wallet(vault),
+            user_info_account(&key, info),
+            TestAccount::signer(info.user),
+            TestAccount::program(anchor_lang::system_program::ID),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::absent(),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    #[test]
+    fn test_claim_refund_returns_lamports_paid() {
+        // Bought 250 NLOV for 10 lamports, then was sold 750 for another 30.
+        let info = UserInfo {
+            lamports_paid: 40,
+            ..user_info(Pubkey::new_unique(), 1_000)
+        };
+        let mut accounts = claim_refund_accounts(&info);
+        claim_refund(&mut accounts).unwrap();
+
+        let transfer = invoked()
+            .into_iter()
+            .find(|ix| ix.program_id == anchor_lang::system_program::ID)
+            .unwrap();
+        assert_eq!(
+            transfer,
+            system_instruction::transfer(&accounts[1].key, &info.user, 40)
+        );
+        let stored = UserInfo::try_deserialize(&mut &accounts[2].data[..]).unwrap();
+        assert_eq!(stored.lamports_refunded, 40);
+        assert_eq!(stored.unclaimed().unwrap(), 0);
+        let event = &emitted::<RefundClaimed>()[0];
+        assert_eq!((event.sol_amount, event.nlov_amount), (40, 1_000));
+
+        assert_eq!(
+            claim_refund(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::NothingToRefund)
+        );
+    }
+}
@@ -0,0 +1,539 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::{Context, Pubkey};
+    use anchor_lang::{AccountDeserialize, Accounts, AccountsExit, Space};
+    use anchor_spl::token::spl_token::instruction::TokenInstruction;
+    use nlov::vesting::{
+        VestingCategory, VestingLedger, VestingSchedule, VESTING_CATEGORIES, VESTING_MONTH,
+    };
+    use nlov::{
+        neurolov_presale, CreateVesting, CreateVestingBumps, ErrorCode, GrantRevoked,
+        ProtocolConfig, RevokeGrant, RevokeGrantBumps, VestingCreated, VestingReleased,
+    };
+
+    use crate::common::{
+        emitted, event_cpi_accounts, infos, install_stubs, invoked, release, vesting_schedule,
+        TestAccount, NOW,
+    };
+
+    const NLOV: u64 = 1_000_000_000;
+    const TGE: i64 = NOW - 100;
+
+    /// `Documents/NLOV_Structure.md` section 2: category, share of supply in
+    /// percent, tokens, TGE unlock in percent, cliff months, initial unlock
+    /// and linear months.
+    #[rustfmt::skip]
+    const STRUCTURE_TABLE: [(VestingCategory, u64, u64, u64, u8, u64, u8); VESTING_CATEGORIES] = [
+        (VestingCategory::SeedSale,      2, 10_000_000,  5,  3,    500_000, 12),
+        (VestingCategory::PrivateSale,   5, 25_000_000,  8,  2,  2_000_000, 10),
+        (VestingCategory::Presale,       4, 20_000_000, 10,  0,  2_000_000,  8),
+        (VestingCategory::PublicSale,    7, 35_000_000, 15,  0,  5_250_000,  6),
+        (VestingCategory::Team,         15, 75_000_000,  0, 12,          0, 36),
+        (VestingCategory::Treasury,     12, 60_000_000, 10,  3,  6_000_000, 36),
+        (VestingCategory::Development,   5, 25_000_000, 10,  2,  2_500_000, 24),
+        (VestingCategory::Advisors,      3, 15_000_000,  0,  6,          0, 24),
+        (VestingCategory::Ecosystem,    15, 75_000_000,  5,  1,  3_750_000, 48),
+        (VestingCategory::NodeRewards,   8, 40_000_000,  5,  0,  2_000_000, 48),
+        (VestingCategory::Liquidity,     8, 40_000_000, 30,  0, 12_000_000, 24),
+        (VestingCategory::Partnerships,  5, 25_000_000,  0,  3,          0, 36),
+        (VestingCategory::Marketing,     3, 15_000_000, 10,  0,  1_500_000, 24),
+        (VestingCategory::Community,     8, 40_000_000,  5,  0,  2_000_000, 48),
+    ];
+
+    #[test]
+    fn test_terms_reproduce_structure_table() {
+        let mut supply = 0;
+        let mut initial_circulating = 0;
+        for (category, percent, tokens, tge_percent, cliff, initial, linear) in STRUCTURE_TABLE {
+            let terms = category.terms();
+            assert_eq!(terms.tokens, tokens, "{category:?}");
+            assert_eq!(terms.tokens * 100 / 500_000_000, percent, "{category:?}");
+            assert_eq!(terms.tge_bps as u64, tge_percent * 100, "{category:?}");
+            assert_eq!(terms.cliff_months, cliff, "{category:?}");
+            assert_eq!(terms.linear_months, linear, "{category:?}");
+
+            let whole = vesting_schedule(category, tokens * NLOV);
+            assert_eq!(
+                whole.vested_amount(TGE, TGE).unwrap(),
+                initial * NLOV,
+                "{category:?}"
+            );
+            supply += tokens;
+            initial_circulating += initial;
+        }
+        assert_eq!(
+            STRUCTURE_TABLE.map(|row| row.0),
+            VestingCategory::ALL,
+            "every category appears once, in order"
+        );
+        assert_eq!(supply, 500_000_000);
+        // "initialCirculatingSupply: 39.5M (7.9%)"
+        assert_eq!(initial_circulating, 39_500_000);
+    }
+
+    #[test]
+    fn test_check_terms() {
+        let seed = VestingCategory::SeedSale.terms();
+        seed.check(500, 3 * VESTING_MONTH, 12 * VESTING_MONTH)
+            .unwrap();
+        for (tge_bps, cliff, linear) in [(501, 3, 12), (500, 2, 12), (500, 3, 11), (500, 3, 13)] {
+            assert_eq!(
+                seed.check(tge_bps, cliff * VESTING_MONTH, linear * VESTING_MONTH)
+                    .unwrap_err(),
+                Error::from(ErrorCode::InvalidVestingSchedule)
+            );
+        }
+
+        // Partnerships are agreed case by case, over at most 36 months.
+        let partnerships = VestingCategory::Partnerships.terms();
+        partnerships
+            .check(0, 3 * VESTING_MONTH, 18 * VESTING_MONTH)
+            .unwrap();
+        for linear in [0, 37 * VESTING_MONTH] {
+            assert_eq!(
+                partnerships
+                    .check(0, 3 * VESTING_MONTH, linear)
+                    .unwrap_err(),
+                Error::from(ErrorCode::InvalidVestingSchedule)
+            );
+        }
+    }
+
+    #[test]
+    fn test_vested_amount() {
+        // 10M seed tokens: 5% at TGE, 3 months cliff, then 12 months linear.
+        let seed = vesting_schedule(VestingCategory::SeedSale, 10_000_000);
+        let cliff_end = TGE + 3 * VESTING_MONTH;
+
+        assert_eq!(seed.vested_amount(TGE, TGE - 1).unwrap(), 0);
+        assert_eq!(seed.vested_amount(TGE, TGE).unwrap(), 500_000);
+        assert_eq!(seed.vested_amount(TGE, cliff_end - 1).unwrap(), 500_000);
+        assert_eq!(seed.vested_amount(TGE, cliff_end).unwrap(), 500_000);
+        assert_eq!(
+            seed.vested_amount(TGE, cliff_end + 6 * VESTING_MONTH)
+                .unwrap(),
+            500_000 + 9_500_000 / 2
+        );
+        assert_eq!(
+            seed.vested_amount(TGE, cliff_end + 12 * VESTING_MONTH)
+                .unwrap(),
+            10_000_000
+        );
+        assert_eq!(seed.vested_amount(TGE, i64::MAX).unwrap(), 10_000_000);
+    }
+
+    #[test]
+    fn test_ledger_caps_each_category() {
+        let mut ledger = VestingLedger::default();
+        let advisors = 15_000_000 * NLOV;
+
+        ledger
+            .allocate(VestingCategory::Advisors, advisors - 1, 9)
+            .unwrap();
+        ledger.allocate(VestingCategory::Advisors, 1, 9).unwrap();
+        assert_eq!(
+            ledger
+                .allocate(VestingCategory::Advisors, 1, 9)
+                .unwrap_err(),
+            Error::from(ErrorCode::VestingAllocationExceeded)
+        );
+        // Other categories are unaffected.
+        ledger.allocate(VestingCategory::Team, advisors, 9).unwrap();
+    }
+
+    /// Accounts for `create_vesting` by the protocol authority, funding the
+    /// ledger's next schedule.
+    fn create_vesting_accounts(ledger: &VestingLedger) -> Vec<TestAccount> {
+        let mint = ledger.mint;
+        let authority = Pubkey::new_unique();
+        let ledger_key =
+            Pubkey::find_program_address(&[b"vesting_ledger", mint.as_ref()], &nlov::ID).0;
+        let schedule_key = Pubkey::find_program_address(
+            &[
+                b"vesting",
+                mint.as_ref(),
+                &ledger.schedule_count.to_le_bytes(),
+            ],
+            &nlov::ID,
+        )
+        .0;
+        let (config_key, bump) = Pubkey::find_program_address(&[b"protocol_config"], &nlov::ID);
+        let config = ProtocolConfig {
+            authority,
+            bump,
+            ..ProtocolConfig::default()
+        };
+        let mut accounts = vec![
+            TestAccount::anchor(ledger_key, ledger, VestingLedger::INIT_SPACE),
+            TestAccount::uninitialized(schedule_key, VestingSchedule::INIT_SPACE),
+            TestAccount::token_account(Pubkey::new_unique(), mint, schedule_key),
+            TestAccount::mint(mint),
+            TestAccount::token_account(Pubkey::new_unique(), mint, authority),
+            TestAccount::anchor(config_key, &config, ProtocolConfig::INIT_SPACE),
+            TestAccount::signer(authority),
+            TestAccount::program(anchor_spl::token::ID),
+            TestAccount::program(anchor_lang::system_program::ID),
+            TestAccount::category_vault_authority(&mint),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn create_vesting(
+        accounts: &mut [TestAccount],
+        category: VestingCategory,
+        total_amount: u64,
+    ) -> anchor_lang::Result<()> {
+        install_stubs();
+        let terms = category.terms();
+        let infos = infos(accounts);
+        let mut bumps = CreateVestingBumps::default();
+        let mut ix = CreateVesting::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::create_vesting(
+            Context::new(&nlov::ID, &mut ix, &[], bumps),
+            Pubkey::new_unique(),
+            category,
+            total_amount,
+            terms.tge_bps,
+            terms.cliff_months as i64 * VESTING_MONTH,
+            terms.linear_months as i64 * VESTING_MONTH,
+            false,
+            0,
+            Vec::new(),
+        )?;
+        ix.exit(&nlov::ID)
+    }
+
+    #[test]
+    fn test_create_vesting() {
+        let ledger = VestingLedger {
+            mint: Pubkey::new_unique(),
+            schedule_count: 3,
+            ..VestingLedger::default()
+        };
+        let mut accounts = create_vesting_accounts(&ledger);
+        create_vesting(&mut accounts, VestingCategory::Treasury, 1_000 * NLOV).unwrap();
+
+        let schedule = VestingSchedule::try_deserialize(&mut &accounts[1].data[..]).unwrap();
+        assert_eq!(schedule.id, 3);
+        assert_eq!(schedule.category, VestingCategory::Treasury);
+        assert_eq!(schedule.escrow, accounts[2].key);
+        assert_eq!(schedule.tge_bps, 1_000);
+        let ledger = VestingLedger::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+        assert_eq!(ledger.schedule_count, 4);
+        assert_eq!(
+            ledger.allocated[VestingCategory::Treasury as usize],
+            1_000 * NLOV
+        );
+
+        let transfers: Vec<_> = invoked()
+            .into_iter()
+            .filter(|ix| ix.program_id == anchor_spl::token::ID)
+            .collect();
+        assert_eq!(
+            TokenInstruction::unpack(&transfers[0].data).unwrap(),
+            TokenInstruction::Transfer {
+                amount: 1_000 * NLOV
+            }
+        );
+        assert_eq!(transfers[0].accounts[1].pubkey, accounts[2].key);
+        assert_eq!(emitted::<VestingCreated>()[0].schedule, accounts[1].key);
+    }
+
+    #[test]
+    fn test_create_vesting_requires_protocol_authority() {
+        let ledger = VestingLedger {
+            mint: Pubkey::new_unique(),
+            ..VestingLedger::default()
+        };
+        let mut accounts = create_vesting_accounts(&ledger);
+        let stranger = Pubkey::new_unique();
+        accounts[4] = TestAccount::token_account(Pubkey::new_unique(), ledger.mint, stranger);
+        accounts[6] = TestAccount::signer(stranger);
+        assert_eq!(
+            create_vesting(&mut accounts, VestingCategory::Treasury, NLOV).unwrap_err(),
+            Error::from(ErrorCode::Unauthorized)
+        );
+    }
+
+    #[test]
+    fn test_create_vesting_from_category_vault() {
+        let ledger = VestingLedger {
+            mint: Pubkey::new_unique(),
+            ..VestingLedger::default()
+        };
+        let mut accounts = create_vesting_accounts(&ledger);
+        let vault_authority = accounts[9].key;
+        let vault = |category: VestingCategory| {
+            let key = Pubkey::find_program_address(
+                &[b"category_vault", ledger.mint.as_ref(), &[category as u8]],
+                &nlov::ID,
+            )
+            .0;
+            TestAccount::token_account(key, ledger.mint, vault_authority)
+        };
+
+        // A vault only funds its own category.
+        accounts[4] = vault(VestingCategory::Team);
+        assert_eq!(
+            create_vesting(&mut accounts, VestingCategory::Treasury, NLOV).unwrap_err(),
+            Error::from(ErrorCode::CategoryVaultMismatch)
+        );
+
+        accounts[4] = vault(VestingCategory::Treasury);
+        create_vesting(&mut accounts, VestingCategory::Treasury, NLOV).unwrap();
+        let transfer = invoked()
+            .into_iter()
+            .rfind(|ix| ix.program_id == anchor_spl::token::ID)
+            .unwrap();
+        assert_eq!(transfer.accounts[0].pubkey, accounts[4].key);
+        assert_eq!(transfer.accounts[2].pubkey, vault_authority);
+        assert!(transfer.accounts[2].is_signer);
+    }
+
+    #[test]
+    fn test_release() {
+        let schedule = vesting_schedule(VestingCategory::Liquidity, 1_000 * NLOV);
+        let mut accounts = vec![
+            TestAccount::anchor(Pubkey::new_unique(), &schedule, VestingSchedule::INIT_SPACE),
+            TestAccount::token_account(schedule.escrow, schedule.mint, Pubkey::new_unique()),
+            TestAccount::token_account(Pubkey::new_unique(), schedule.mint, schedule.beneficiary),
+            TestAccount::signer(schedule.beneficiary),
+            TestAccount::program(anchor_spl::token::ID),
+            TestAccount::emission_schedule_with_tge(&schedule.mint, TGE),
+        ];
+        accounts.extend(event_cpi_accounts());
+
+        // Liquidity unlocks 30% at TGE and has no cliff; 100 seconds of the
+        // 24 months have passed.
+        release(&mut accounts).unwrap();
+        let expected = 300 * NLOV + 700 * NLOV * 100 / (24 * VESTING_MONTH as u64);
+        let stored = VestingSchedule::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+        assert_eq!(stored.released_amount, expected);
+        let events = emitted::<VestingReleased>();
+        assert_eq!(events[0].amount, expected);
+        assert_eq!(events[0].released_amount, expected);
+
+        assert_eq!(
+            release(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::NothingToClaim)
+        );
+    }
+
+    #[test]
+    fn test_release_requires_beneficiary() {
+        let schedule = vesting_schedule(VestingCategory::Liquidity, 1_000 * NLOV);
+        let stranger = Pubkey::new_unique();
+        let mut accounts = vec![
+            TestAccount::anchor(Pubkey::new_unique(), &schedule, VestingSchedule::INIT_SPACE),
+            TestAccount::token_account(schedule.escrow, schedule.mint, Pubkey::new_unique()),
+            TestAccount::token_account(Pubkey::new_unique(), schedule.mint, stranger),
+            TestAccount::signer(stranger),
+            TestAccount::program(anchor_spl::token::ID),
+            TestAccount::emission_schedule_with_tge(&schedule.mint, TGE),
+        ];
+        accounts.extend(event_cpi_accounts());
+        assert_eq!(
+            release(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::Unauthorized)
+        );
+    }
+    fn revocable(category: VestingCategory, total_amount: u64) -> VestingSchedule {
+        VestingSchedule {
+            revocable: true,
+            ..vesting_schedule(category, total_amount)
+        }
+    }
+
+    #[test]
+    fn test_revoke_boundaries() {
+        // 24M advisor tokens: nothing at TGE, 6 months cliff, then 24 months
+        // linear.
+        let cliff_end = TGE + 6 * VESTING_MONTH;
+        let total = 24_000_000;
+        for (revoked_at, vested) in [
+            (TGE - 1, 0),
+            (cliff_end - 1, 0),
+            (cliff_end + 12 * VESTING_MONTH, total / 2),
+            (cliff_end + 24 * VESTING_MONTH, total),
+            (i64::MAX, total),
+        ] {
+            let mut grant = revocable(VestingCategory::Advisors, total);
+            assert_eq!(grant.revoke(TGE, revoked_at).unwrap(), total - vested);
+            // Nothing vests after the revocation.
+            assert_eq!(grant.vested_amount(TGE, i64::MAX).unwrap(), vested);
+            assert_eq!(
+                grant.revoke(TGE, revoked_at).unwrap_err(),
+                Error::from(ErrorCode::GrantAlreadyRevoked)
+            );
+        }
+
+        assert_eq!(
+            vesting_schedule(VestingCategory::Advisors, total)
+                .revoke(TGE, TGE)
+                .unwrap_err(),
+            Error::from(ErrorCode::GrantNotRevocable)
+        );
+    }
+
+    /// Accounts for `revoke_grant` by the protocol authority, with the whole
+    /// grant still in escrow and counted in the ledger, returning to the
+    /// grant's category vault.
+    fn revoke_accounts(grant: &VestingSchedule, tge: i64) -> Vec<TestAccount> {
+        let authority = Pubkey::new_unique();
+        let (ledger_key, ledger_bump) =
+            Pubkey::find_program_address(&[b"vesting_ledger", grant.mint.as_ref()], &nlov::ID);
+        let mut ledger = VestingLedger {
+            mint: grant.mint,
+            schedule_count: 1,
+            bump: ledger_bump,
+            ..VestingLedger::default()
+        };
+        ledger.allocated[grant.category as usize] = grant.total_amount;
+        let (config_key, bump) = Pubkey::find_program_address(&[b"protocol_config"], &nlov::ID);
+        let config = ProtocolConfig {
+            authority,
+            bump,
+            ..ProtocolConfig::default()
+        };
+        let vault_authority = TestAccount::category_vault_authority(&grant.mint);
+        let category_vault = Pubkey::find_program_address(
+            &[
+                b"category_vault",
+                grant.mint.as_ref(),
+                &[grant.category as u8],
+            ],
+            &nlov::ID,
+        )
+        .0;
+        let mut accounts = vec![
+            TestAccount::anchor(ledger_key, &ledger, VestingLedger::INIT_SPACE),
+            TestAccount::anchor(Pubkey::new_unique(), grant, VestingSchedule::INIT_SPACE),
+            TestAccount::token_account(grant.escrow, grant.mint, Pubkey::new_unique()),
+            TestAccount::token_account(category_vault, grant.mint, vault_authority.key),
+            TestAccount::anchor(config_key, &config, ProtocolConfig::INIT_SPACE),
+            TestAccount::signer(authority),
+            TestAccount::program(anchor_spl::token::ID),
+            TestAccount::emission_schedule_with_tge(&grant.mint, tge),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn revoke_grant(accounts: &mut [TestAccount]) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = RevokeGrantBumps::default();
+        let mut ix = RevokeGrant::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::revoke_grant(Context::new(&nlov::ID, &mut ix, &[], bumps))?;
+        ix.exit(&nlov::ID)
+    }
+
+    #[test]
+    fn test_revoke_grant_mid_vesting() {
+        // Marketing unlocks 10% at TGE and has no cliff; 100 seconds of the
+        // 24 months have passed.
+        let grant = revocable(VestingCategory::Marketing, 1_000 * NLOV);
+        let mut accounts = revoke_accounts(&grant, TGE);
+        revoke_grant(&mut accounts).unwrap();
+
+        let vested = 100 * NLOV + 900 * NLOV * 100 / (24 * VESTING_MONTH as u64);
+        let returned = 1_000 * NLOV - vested;
+        let stored = VestingSchedule::try_deserialize(&mut &accounts[1].data[..]).unwrap();
+        assert_eq!(stored.revoked_at, NOW);
+        assert_eq!(stored.vested_amount(TGE, i64::MAX).unwrap(), vested);
+        let ledger = VestingLedger::try_deserialize(&mut &accounts[0].data[..]).unwrap();
+        assert_eq!(
+            ledger.allocated[VestingCategory::Marketing as usize],
+            vested
+        );
+
+        let transfers: Vec<_> = invoked()
+            .into_iter()
+            .filter(|ix| ix.program_id == anchor_spl::token::ID)
+            .collect();
+        assert_eq!(
+            TokenInstruction::unpack(&transfers[0].data).unwrap(),
+            TokenInstruction::Transfer { amount: returned }
+        );
+        assert_eq!(transfers[0].accounts[1].pubkey, accounts[3].key);
+        let event = &emitted::<GrantRevoked>()[0];
+        assert_eq!(event.vested_amount, vested);
+        assert_eq!(event.returned_amount, returned);
+
+        assert_eq!(
+            revoke_grant(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::GrantAlreadyRevoked)
+        );
+    }
+
+    #[test]
+    fn test_revoke_grant_after_completion() {
+        let grant = revocable(VestingCategory::Marketing, 1_000 * NLOV);
+        let mut accounts = revoke_accounts(&grant, NOW - 24 * VESTING_MONTH);
+        revoke_grant(&mut accounts).unwrap();
+
+        // Everything has vested, so nothing goes back.
+        assert!(invoked()
+            .iter()
+            .all(|ix| ix.program_id != anchor_spl::token::ID));
+        assert_eq!(emitted::<GrantRevoked>()[0].returned_amount, 0);
+    }
+
+    #[test]
+    fn test_revoke_grant_only_returns_to_category_vault() {
+        let grant = revocable(VestingCategory::Marketing, 1_000 * NLOV);
+        let mut accounts = revoke_accounts(&grant, TGE);
+        // The treasury's vault would leave the Marketing allocation freed
+        // without the tokens to back it.
+        let treasury_vault = Pubkey::find_program_address(
+            &[
+                b"category_vault",
+                grant.mint.as_ref(),
+                &[VestingCategory::Treasury as u8],
+            ],
+            &nlov::ID,
+        )
+        .0;
+        let vault_authority = TestAccount::category_vault_authority(&grant.mint).key;
+        for vault in [
+            TestAccount::token_account(Pubkey::new_unique(), grant.mint, accounts[5].key),
+            TestAccount::token_account(treasury_vault, grant.mint, vault_authority),
+        ] {
+            accounts[3] = vault;
+            assert_eq!(
+                revoke_grant(&mut accounts).unwrap_err(),
+                Error::from(anchor_lang::error::ErrorCode::ConstraintSeeds)
+                    .with_account_name("category_vault")
+            );
+        }
+    }
+
+    #[test]
+    fn test_revoke_grant_requires_protocol_authority() {
+        let grant = revocable(VestingCategory::Marketing, 1_000 * NLOV);
+        let mut accounts = revoke_accounts(&grant, TGE);
+        accounts[5] = TestAccount::signer(Pubkey::new_unique());
+        assert_eq!(
+            revoke_grant(&mut accounts).unwrap_err(),
+            Error::from(ErrorCode::Unauthorized)
+        );
+    }
+}
@@ -0,0 +1,408 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anchor_lang::error::Error;
+    use anchor_lang::prelude::{Context, Pubkey};
+    use anchor_lang::{Accounts, AccountsExit, Space};
+    use nlov::council::{AdminAction, AdminProposal, Council};
+    use nlov::neurolov_presale;
+    use nlov::timelock::{QueuedAction, MIN_TIMELOCK_DELAY};
+    use nlov::withdrawal_limit::{
+        WithdrawalLimit, WithdrawalWindow, DEFAULT_WITHDRAWAL_LIMIT, WITHDRAWAL_BUCKET,
+    };
+    use nlov::{
+        AdminUpdate, AdminUpdateBumps, DistributeProceeds, DistributeProceedsBumps, ErrorCode,
+        FundsWithdrawn, Presale, ProceedsDestination, ProceedsLeg, ProtocolConfig, SalePhase,
+        Withdraw, WithdrawBumps, WithdrawalLimitConfigured, PROCEEDS_LEGS,
+    };
+
+    use crate::common::{
+        emitted, event_cpi_accounts, infos, install_stubs, presale, stored_presale, TestAccount,
+        NOW,
+    };
+
+    const PRESALE: usize = 0;
+    const VAULT_BALANCE: u64 = 1_000_000_000;
+
+    fn limit(max_lamports: u64, max_bps: u16) -> WithdrawalLimit {
+        WithdrawalLimit {
+            max_lamports,
+            max_bps,
+        }
+    }
+
+    /// Accounts for `withdraw` by the owner of a finalized sale without a
+    /// platform fee, whose vault holds `VAULT_BALANCE`.
+    fn withdraw_accounts(withdrawal_limit: WithdrawalLimit) -> Vec<TestAccount> {
+        let key = Pubkey::new_unique();
+        let treasury = Pubkey::new_unique();
+        let mut proceeds = [ProceedsLeg::default(); PROCEEDS_LEGS];
+        proceeds[ProceedsDestination::Treasury as usize] = ProceedsLeg {
+            wallet: treasury,
+            bps: 10_000,
+        };
+        let presale = Presale {
+            owner: Pubkey::new_unique(),
+            proceeds_configured: true,
+            proceeds,
+            withdrawal_limit,
+            ..presale(SalePhase::Finalized)
+        };
+        let vault = Pubkey::find_program_address(&[b"vault", key.as_ref()], &nlov::ID).0;
+        let (config_key, bump) = Pubkey::find_program_address(&[b"protocol_config"], &nlov::ID);
+        let fee_recipient = Pubkey::new_unique();
+        let config = ProtocolConfig {
+            fee_recipient,
+            bump,
+            ..ProtocolConfig::default()
+        };
+        let mut accounts = vec![
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+            TestAccount::wallet(vault),
+            TestAccount::wallet(treasury),
+            TestAccount::anchor(config_key, &config, ProtocolConfig::INIT_SPACE),
+            TestAccount::wallet(fee_recipient),
+            TestAccount::signer(presale.owner),
+            TestAccount::program(anchor_lang::system_program::ID),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::absent(),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn withdraw(accounts: &mut [TestAccount], amount: u64) -> anchor_lang::Result<()> {
+        run_withdraw(accounts, |ctx| neurolov_presale::withdraw(ctx, amount))
+    }
+
+    fn run_withdraw(
+        accounts: &mut [TestAccount],
+        handler: impl FnOnce(Context<Withdraw>) -> anchor_lang::Result<()>,
+    ) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = WithdrawBumps::default();
+        let mut ix = Withdraw::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        handler(Context::new(&nlov::ID, &mut ix, &[], bumps))?;
+        ix.exit(&nlov::ID)
+    }
+
+    /// Accounts for `distribute_proceeds` by the owner of a finalized sale
+    /// without a platform fee, split evenly across the four wallets.
+    fn distribute_accounts(withdrawal_limit: WithdrawalLimit) -> Vec<TestAccount> {
+        let key = Pubkey::new_unique();
+        let wallets = [(); PROCEEDS_LEGS].map(|_| Pubkey::new_unique());
+        let presale = Presale {
+            owner: Pubkey::new_unique(),
+            proceeds_configured: true,
+            proceeds: wallets.map(|wallet| ProceedsLeg { wallet, bps: 2_500 }),
+            withdrawal_limit,
+            ..presale(SalePhase::Finalized)
+        };
+        let vault = Pubkey::find_program_address(&[b"vault", key.as_ref()], &nlov::ID).0;
+        let (config_key, bump) = Pubkey::find_program_address(&[b"protocol_config"], &nlov::ID);
+        let fee_recipient = Pubkey::new_unique();
+        let config = ProtocolConfig {
+            fee_recipient,
+            bump,
+            ..ProtocolConfig::default()
+        };
+        let mut accounts = vec![
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+            TestAccount::wallet(vault),
+        ];
+        accounts.extend(wallets.map(TestAccount::wallet));
+        accounts.extend([
+            TestAccount::anchor(config_key, &config, ProtocolConfig::INIT_SPACE),
+            TestAccount::wallet(fee_recipient),
+            TestAccount::signer(presale.owner),
+            TestAccount::program(anchor_lang::system_program::ID),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::absent(),
+        ]);
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn distribute(accounts: &mut [TestAccount], amount: u64) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = DistributeProceedsBumps::default();
+        let mut ix = DistributeProceeds::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::distribute_proceeds(
+            Context::new(&nlov::ID, &mut ix, &[], bumps),
+            amount,
+        )?;
+        ix.exit(&nlov::ID)
+    }
+
+    /// Accounts for `configure_withdrawal_limit` by the owner of a sale with
+    /// no council, timelock or limit.
+    fn configure_accounts() -> Vec<TestAccount> {
+        let presale = Presale {
+            owner: Pubkey::new_unique(),
+            ..presale(SalePhase::Pending)
+        };
+        let mut accounts = vec![
+            TestAccount::anchor(Pubkey::new_unique(), &presale, Presale::INIT_SPACE),
+            TestAccount::signer(presale.owner),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::absent(),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    /// Accounts for `configure_withdrawal_limit` to `limit`, approved by the
+    /// 1-of-1 council of a sale capped at 10%.
+    fn council_configure_accounts(limit: WithdrawalLimit) -> Vec<TestAccount> {
+        let council_key = Pubkey::new_unique();
+        let member = Pubkey::new_unique();
+        let presale = Presale {
+            owner: Pubkey::new_unique(),
+            council: council_key,
+            withdrawal_limit: DEFAULT_WITHDRAWAL_LIMIT,
+            ..presale(SalePhase::Pending)
+        };
+        let mut council = Council::default();
+        council.configure(vec![member], 1).unwrap();
+        let proposal = AdminProposal {
+            council: council_key,
+            id: 0,
+            proposer: member,
+            action: AdminAction::ConfigureWithdrawalLimit { limit },
+            approvals: 1,
+            expires_at: NOW + 3_600,
+            executed: false,
+            bump: 0,
+        };
+        let mut accounts = vec![
+            TestAccount::anchor(Pubkey::new_unique(), &presale, Presale::INIT_SPACE),
+            TestAccount::signer(member),
+            TestAccount::anchor(council_key, &council, Council::INIT_SPACE),
+            TestAccount::anchor(Pubkey::new_unique(), &proposal, AdminProposal::INIT_SPACE),
+            TestAccount::absent(),
+        ];
+        accounts.extend(event_cpi_accounts());
+        accounts
+    }
+
+    fn configure(accounts: &mut [TestAccount], limit: WithdrawalLimit) -> anchor_lang::Result<()> {
+        install_stubs();
+        let infos = infos(accounts);
+        let mut bumps = AdminUpdateBumps::default();
+        let mut ix = AdminUpdate::try_accounts(
+            &nlov::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut BTreeSet::new(),
+        )?;
+        neurolov_presale::configure_withdrawal_limit(
+            Context::new(&nlov::ID, &mut ix, &[], bumps),
+            limit,
+        )?;
+        ix.exit(&nlov::ID)
+    }
+
+    #[test]
+    fn test_cap() {
+        assert_eq!(limit(0, 0).cap(VAULT_BALANCE, 0), None);
+        assert_eq!(limit(5_000, 0).cap(VAULT_BALANCE, 0), Some(5_000));
+        // Measured against the vault before this window's withdrawals.
+        assert_eq!(limit(0, 1_000).cap(900, 100), Some(100));
+        assert_eq!(limit(50, 1_000).cap(900, 100), Some(50));
+        assert_eq!(
+            limit(0, 10_001).validate().unwrap_err(),
+            Error::from(ErrorCode::InvalidWithdrawalLimit)
+        );
+    }
+
+    #[test]
+    fn test_is_loosened_by() {
+        let current = limit(1_000, 500);
+        assert!(!current.is_loosened_by(&limit(1_000, 500)));
+        assert!(!current.is_loosened_by(&limit(900, 100)));
+        assert!(current.is_loosened_by(&limit(1_001, 500)));
+        assert!(current.is_loosened_by(&limit(1_000, 0)));
+        assert!(current.is_loosened_by(&limit(0, 0)));
+        assert!(!limit(0, 0).is_loosened_by(&current));
+    }
+
+    #[test]
+    fn test_window_rolls_hourly() {
+        let limit = limit(1_000, 0);
+        let mut window = WithdrawalWindow::default();
+
+        window.record(&limit, 600, VAULT_BALANCE, NOW).unwrap();
+        window
+            .record(&limit, 400, VAULT_BALANCE, NOW + 12 * WITHDRAWAL_BUCKET)
+            .unwrap();
+        assert_eq!(
+            window
+                .record(&limit, 1, VAULT_BALANCE, NOW + 23 * WITHDRAWAL_BUCKET)
+                .unwrap_err(),
+            Error::from(ErrorCode::WithdrawalLimitExceeded)
+        );
+
+        // The first withdrawal leaves the window a day later; the second stays.
+        let later = NOW + 24 * WITHDRAWAL_BUCKET;
+        assert_eq!(window.withdrawn(later), 400);
+        window.record(&limit, 600, VAULT_BALANCE, later).unwrap();
+        assert_eq!(window.withdrawn(later + 13 * WITHDRAWAL_BUCKET), 600);
+        assert_eq!(window.withdrawn(later + 48 * WITHDRAWAL_BUCKET), 0);
+    }
+
+    #[test]
+    fn test_uncapped_withdrawals_still_count() {
+        let limit = limit(1_000, 0);
+        let mut window = WithdrawalWindow::default();
+        window.count(5_000, NOW).unwrap();
+        assert_eq!(
+            window.record(&limit, 1, VAULT_BALANCE, NOW).unwrap_err(),
+            Error::from(ErrorCode::WithdrawalLimitExceeded)
+        );
+    }
+
+    #[test]
+    fn test_withdraw_within_limit() {
+        let mut accounts = withdraw_accounts(limit(0, 2_500));
+        withdraw(&mut accounts, VAULT_BALANCE / 4).unwrap();
+
+        let presale = stored_presale(&accounts[PRESALE]);
+        assert_eq!(presale.withdrawal_window.withdrawn(NOW), VAULT_BALANCE / 4);
+        assert_eq!(emitted::<FundsWithdrawn>()[0].amount, VAULT_BALANCE / 4);
+    }
+
+    #[test]
+    fn test_withdraw_over_limit() {
+        let mut accounts = withdraw_accounts(limit(0, 2_500));
+        assert_eq!(
+            withdraw(&mut accounts, VAULT_BALANCE / 4 + 1).unwrap_err(),
+            Error::from(ErrorCode::WithdrawalLimitExceeded)
+        );
+    }
+
+    #[test]
+    fn test_distribute_proceeds_counts_against_limit() {
+        let mut accounts = distribute_accounts(limit(0, 2_500));
+        assert_eq!(
+            distribute(&mut accounts, VAULT_BALANCE / 4 + 1).unwrap_err(),
+            Error::from(ErrorCode::WithdrawalLimitExceeded)
+        );
+
+        distribute(&mut accounts, VAULT_BALANCE / 4).unwrap();
+        assert_eq!(
+            stored_presale(&accounts[PRESALE])
+                .withdrawal_window
+                .withdrawn(NOW),
+            VAULT_BALANCE / 4
+        );
+    }
+
+    #[test]
+    fn test_owner_alone_can_only_tighten() {
+        let mut accounts = configure_accounts();
+        configure(&mut accounts, limit(1_000, 0)).unwrap();
+        configure(&mut accounts, limit(500, 100)).unwrap();
+        assert_eq!(
+            stored_presale(&accounts[PRESALE]).withdrawal_limit,
+            limit(500, 100)
+        );
+
+        let events = emitted::<WithdrawalLimitConfigured>();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].previous_limit, limit(1_000, 0));
+        assert_eq!(events[1].limit, limit(500, 100));
+
+        for looser in [limit(501, 100), limit(500, 0), limit(0, 0)] {
+            assert_eq!(
+                configure(&mut accounts, looser).unwrap_err(),
+                Error::from(ErrorCode::WithdrawalLimitLocked)
+            );
+        }
+    }
+
+    #[test]
+    fn test_timelock_alone_cannot_loosen() {
+        let key = Pubkey::new_unique();
+        let presale = Presale {
+            owner: Pubkey::new_unique(),
+            timelock_delay: MIN_TIMELOCK_DELAY,
+            withdrawal_limit: DEFAULT_WITHDRAWAL_LIMIT,
+            ..presale(SalePhase::Pending)
+        };
+        let queued = QueuedAction {
+            presale: key,
+            id: 0,
+            action: AdminAction::ConfigureWithdrawalLimit { limit: limit(0, 0) },
+            queued_by: presale.owner,
+            council: Pubkey::default(),
+            eta: NOW,
+            executed: false,
+            cancelled: false,
+            bump: 0,
+        };
+        let mut accounts = vec![
+            TestAccount::anchor(key, &presale, Presale::INIT_SPACE),
+            TestAccount::signer(presale.owner),
+            TestAccount::absent(),
+            TestAccount::absent(),
+            TestAccount::anchor(Pubkey::new_unique(), &queued, QueuedAction::INIT_SPACE),
+        ];
+        accounts.extend(event_cpi_accounts());
+
+        assert_eq!(
+            configure(&mut accounts, limit(0, 0)).unwrap_err(),
+            Error::from(ErrorCode::WithdrawalLimitLocked)
+        );
+        assert_eq!(
+            stored_presale(&accounts[PRESALE]).withdrawal_limit,
+            DEFAULT_WITHDRAWAL_LIMIT
+        );
+    }
+
+    #[test]
+    fn test_council_can_loosen() {
+        let mut accounts = council_configure_accounts(limit(0, 0));
+        configure(&mut accounts, limit(0, 0)).unwrap();
+        assert_eq!(
+            stored_presale(&accounts[PRESALE]).withdrawal_limit,
+            limit(0, 0)
+        );
+        assert_eq!(
+            emitted::<WithdrawalLimitConfigured>()[0].previous_limit,
+            DEFAULT_WITHDRAWAL_LIMIT
+        );
+    }
+
+    #[test]
+    fn test_council_withdraw_requires_council() {
+        let mut accounts = withdraw_accounts(limit(0, 2_500));
+        assert_eq!(
+            run_withdraw(&mut accounts, |ctx| {
+                neurolov_presale::council_withdraw(ctx, VAULT_BALANCE)
+            })
+            .unwrap_err(),
+            Error::from(ErrorCode::CouncilRequired)
+        );
+    }
+}